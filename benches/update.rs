@@ -0,0 +1,66 @@
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use smart_road::collision::footprints_overlap;
+use smart_road::game::{Game, HEADLESS_STEP};
+use smart_road::lanes::lane_start;
+use smart_road::types::*;
+
+const CAR_COUNTS: [usize; 5] = [10, 50, 100, 200, 400];
+
+// Bumper to bumper gap between queued cars
+const QUEUE_GAP: f32 = 15.0;
+
+// Longest queue built on one lane, the rest of a queue this long is off the map upstream
+const MAX_PER_LANE: usize = 40;
+
+// A game with `count` cars queued on every lane of every approach, one car length and a gap
+// apart and backed up from where cars enter the map, like traffic held upstream
+fn populated_game(count: usize) -> Game {
+    let mut game = Game::with_seed(1);
+    game.verbose = false;
+
+    let layout = game.scenario.lanes.clone();
+    let lanes: Vec<(Direction, usize, Route)> = Direction::ALL
+        .iter()
+        .flat_map(|d| layout.lanes(d).iter().enumerate().map(move |(lane, routes)| (d.clone(), lane, routes[0].clone())))
+        .collect();
+    assert!(count <= lanes.len() * MAX_PER_LANE, "{} cars don't fit in {} lanes", count, lanes.len());
+
+    for i in 0..count {
+        let (direction, lane, route) = lanes[i % lanes.len()].clone();
+        let (x, y) = lane_start(&direction, lane);
+        let offset = (i / lanes.len()) as f32 * (CAR_LENGTH + QUEUE_GAP);
+        let (x, y) = match direction {
+            Direction::North => (x, y - offset),
+            Direction::South => (x, y + offset),
+            Direction::East => (x - offset, y),
+            Direction::West => (x + offset, y),
+        };
+
+        let mut car = Car::new(game.next_car_id, direction, route, x, y, 0.0);
+        car.lane = lane;
+        game.cars.push(car);
+        game.next_car_id += 1;
+    }
+    for (i, car) in game.cars.iter().enumerate() {
+        assert!(!game.cars[i + 1..].iter().any(|other| footprints_overlap(car, other)), "car {} overlaps another", car.id);
+    }
+    game
+}
+
+fn bench_update(c: &mut Criterion) {
+    let mut group = c.benchmark_group("update");
+    for count in CAR_COUNTS {
+        let game = populated_game(count);
+        group.bench_with_input(BenchmarkId::from_parameter(count), &game, |b, game| {
+            b.iter_batched(
+                || game.clone(),
+                |mut game| game.update(HEADLESS_STEP),
+                BatchSize::LargeInput,
+            );
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_update);
+criterion_main!(benches);
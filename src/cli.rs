@@ -0,0 +1,394 @@
+use crate::collision::Manager;
+use crate::export::ExportFormat;
+use crate::game::Game;
+use crate::network::{Network, NetworkConfig};
+use crate::scenario::Scenario;
+use crate::types::Junction;
+use std::path::{Path, PathBuf};
+
+pub const USAGE: &str = "\
+Usage: smart-road [options]
+       smart-road batch <experiment.json> [--output <dir>] [--threads <n>]
+
+Options:
+  --headless              run without a window (needs a duration)
+  --scenario <file>       load traffic from a JSON scenario file
+  --network <file>        simulate several connected intersections from a JSON network file,
+                          demand comes from the file and there is no --scenario, --rate, --events or --save
+  --reroute               let network cars replan their way around congested links
+  --coordinate            network nodes share expected arrivals and pace cars into free slots
+  --seed <n>              random seed, same seed and inputs give the same run
+  --duration <seconds>    stop after this much simulated time
+  --manager <name>        reference-point or first-come
+  --junction <name>       cross or roundabout
+  --rate <n | n,s,e,w>    cars per minute for every approach, or per approach
+  --pedestrians <n>       people per minute crossing each leg of a cross junction
+  --cyclists <n>          cyclists per minute on the bike lane of each approach of a cross junction
+  --penetration <share>   share of cars that are connected, the others are driven by humans
+  --speed <x>             simulation speed multiplier of a window run
+  --ttc <seconds>         time-to-collision under which a conflict is a close call
+  --deadlock-timeout <s>  release waiting cars after one has stood still this long
+  --output <dir>          export statistics to this directory when the run ends
+  --events <file>         write the simulation's decisions to this file as JSON lines
+  --events-car <ids>      only log events involving these cars, comma separated
+  --load <file>           resume from a snapshot, --seed reseeds what comes next and the
+                          demand and run options override what it saved
+  --save <file>           snapshot file for --save-at and the F5/F9 keys
+  --save-at <seconds>     save a snapshot once the simulation reaches this time
+  --format <csv|json|both>
+                          file format of --output exports (default both)
+  --help                  show this message";
+
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub headless: bool,
+    pub scenario: Option<PathBuf>,
+    pub network: Option<PathBuf>,
+    pub reroute: bool,
+    pub coordinate: bool,
+    pub seed: Option<u64>,
+    pub duration: Option<f32>,
+    pub manager: Option<Manager>,
+    pub junction: Option<Junction>,
+    pub rates: Option<Vec<f32>>,
+    pub pedestrians: Option<f32>,
+    pub cyclists: Option<f32>,
+    pub penetration: Option<f32>,
+    pub speed: Option<f32>,
+    pub ttc: Option<f32>,
+    pub deadlock_timeout: Option<f32>,
+    pub output: Option<PathBuf>,
+    pub events: Option<PathBuf>,
+    pub events_cars: Option<Vec<usize>>,
+    pub load: Option<PathBuf>,
+    pub save: Option<PathBuf>,
+    pub save_at: Option<f32>,
+    pub format: ExportFormat,
+    pub help: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            headless: false,
+            scenario: None,
+            network: None,
+            reroute: false,
+            coordinate: false,
+            seed: None,
+            duration: None,
+            manager: None,
+            junction: None,
+            rates: None,
+            pedestrians: None,
+            cyclists: None,
+            penetration: None,
+            speed: None,
+            ttc: None,
+            deadlock_timeout: None,
+            output: None,
+            events: None,
+            events_cars: None,
+            load: None,
+            save: None,
+            save_at: None,
+            format: ExportFormat::Both,
+            help: false,
+        }
+    }
+}
+
+impl Options {
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let mut options = Options::default();
+        let mut args = args.iter();
+
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("Missing value for {}", arg));
+            match arg.as_str() {
+                "--headless" => options.headless = true,
+                "--help" | "-h" => options.help = true,
+                "--scenario" => options.scenario = Some(PathBuf::from(value()?)),
+                "--network" => options.network = Some(PathBuf::from(value()?)),
+                "--reroute" => options.reroute = true,
+                "--coordinate" => options.coordinate = true,
+                "--seed" => options.seed = Some(parse_number(arg, value()?)?),
+                "--duration" => options.duration = Some(parse_positive(arg, value()?)?),
+                "--manager" => options.manager = Some(Manager::parse(value()?)?),
+                "--junction" => options.junction = Some(Junction::parse(value()?)?),
+                "--rate" => {
+                    let rates = value()?
+                        .split(',')
+                        .map(|r| parse_rate(arg, r))
+                        .collect::<Result<Vec<f32>, String>>()?;
+                    if rates.len() != 1 && rates.len() != 4 {
+                        return Err("--rate takes one value or four (north,south,east,west)".to_string());
+                    }
+                    options.rates = Some(rates);
+                }
+                "--pedestrians" => options.pedestrians = Some(parse_rate(arg, value()?)?),
+                "--cyclists" => options.cyclists = Some(parse_rate(arg, value()?)?),
+                "--penetration" => {
+                    let share: f32 = parse_number(arg, value()?)?;
+                    if !(0.0..=1.0).contains(&share) {
+                        return Err("--penetration takes a share between 0 and 1".to_string());
+                    }
+                    options.penetration = Some(share);
+                }
+                "--speed" => options.speed = Some(parse_positive(arg, value()?)?),
+                "--ttc" => options.ttc = Some(parse_positive(arg, value()?)?),
+                "--deadlock-timeout" => options.deadlock_timeout = Some(parse_positive(arg, value()?)?),
+                "--output" => options.output = Some(PathBuf::from(value()?)),
+                "--events" => options.events = Some(PathBuf::from(value()?)),
+                "--events-car" => {
+                    let cars = value()?
+                        .split(',')
+                        .map(|id| parse_number(arg, id))
+                        .collect::<Result<Vec<usize>, String>>()?;
+                    options.events_cars = Some(cars);
+                }
+                "--load" => options.load = Some(PathBuf::from(value()?)),
+                "--save" => options.save = Some(PathBuf::from(value()?)),
+                "--save-at" => options.save_at = Some(parse_number(arg, value()?)?),
+                "--format" => options.format = ExportFormat::parse(value()?)?,
+                _ => return Err(format!("Unknown argument '{}'\n\n{}", arg, USAGE)),
+            }
+        }
+
+        let has_duration = options.duration.is_some()
+            || options.scenario.is_some()
+            || options.network.is_some()
+            || options.load.is_some();
+        if options.headless && !has_duration {
+            return Err("--headless needs --duration or a scenario with a duration".to_string());
+        }
+        // A snapshot brings its own scenario and the cars on it are on its junction
+        if options.load.is_some() {
+            if options.scenario.is_some() || options.network.is_some() {
+                return Err("--load resumes the snapshot's own scenario, not with --scenario or --network".to_string());
+            }
+            if options.junction.is_some() {
+                return Err("--junction can't change the junction under the cars of a snapshot".to_string());
+            }
+        }
+        // A network's nodes and demand come from its file, and it has no single event log or snapshot
+        if options.network.is_some() {
+            let unsupported = [
+                ("--scenario", options.scenario.is_some()),
+                ("--rate", options.rates.is_some()),
+                ("--events", options.events.is_some()),
+                ("--events-car", options.events_cars.is_some()),
+                ("--save", options.save.is_some()),
+                ("--save-at", options.save_at.is_some()),
+            ];
+            if let Some((flag, _)) = unsupported.iter().find(|(_, given)| *given) {
+                return Err(format!("{} doesn't apply to --network runs", flag));
+            }
+        }
+        // Headless runs already go as fast as they can on a fixed step
+        if options.headless && options.speed.is_some() {
+            return Err("--speed only applies to window runs, not with --headless".to_string());
+        }
+        Ok(options)
+    }
+
+    // Build the game, command line values win over the scenario file or the snapshot
+    pub fn build_game(&self) -> Result<Game, String> {
+        let (mut game, mut scenario) = match &self.load {
+            Some(path) => {
+                let mut game = Game::new();
+                game.load_snapshot(path)?;
+                // Same situation, a different draw from here on
+                if let Some(seed) = self.seed {
+                    game.seed = seed;
+                    game.rng = fastrand::Rng::with_seed(seed);
+                }
+                let scenario = game.scenario.clone();
+                (game, scenario)
+            }
+            None => self.new_game()?,
+        };
+
+        // Arrivals already drawn at the old rates are drawn again
+        if let Some(rates) = &self.rates {
+            game.traffic.next_arrival.clear();
+            match rates.as_slice() {
+                [rate] => scenario.set_rate(*rate),
+                _ => {
+                    for (direction, rate) in crate::types::Direction::ALL.into_iter().zip(rates) {
+                        scenario.rates.insert(direction, *rate);
+                    }
+                }
+            }
+        }
+        if let Some(rate) = self.pedestrians {
+            game.traffic.next_pedestrian.clear();
+            scenario.set_pedestrian_rate(rate);
+        }
+        if let Some(rate) = self.cyclists {
+            game.traffic.next_cyclist.clear();
+            scenario.set_cyclist_rate(rate);
+        }
+        if let Some(share) = self.penetration {
+            scenario.penetration = share;
+        }
+        if self.duration.is_some() {
+            scenario.duration = self.duration;
+        }
+        if let Some(junction) = self.junction {
+            scenario.junction = junction;
+        }
+        // The overrides get the checks a scenario file does
+        scenario.prepare()?;
+        if scenario.duration.is_none() && self.headless {
+            return Err("Headless runs need a duration".to_string());
+        }
+        if self.load.is_some() {
+            game.traffic.resume_arrivals(&scenario, game.sim_time, &mut game.rng);
+        }
+
+        if let Some(manager) = self.manager {
+            game.manager = manager;
+        }
+        if let Some(speed) = self.speed {
+            game.speed_multiplier = speed;
+        }
+        if let Some(ttc) = self.ttc {
+            game.ttc_threshold = ttc;
+        }
+        if let Some(timeout) = self.deadlock_timeout {
+            game.deadlocks.timeout = timeout;
+        }
+        if let Some(cars) = &self.events_cars {
+            game.events.cars = Some(cars.iter().copied().collect());
+        }
+        game.events_path = self.events.clone();
+        game.events.record = self.events.is_some();
+        if let Some(path) = &self.save {
+            game.snapshot_path = path.clone();
+        }
+        game.snapshot_at = self.save_at;
+        game.scenario = scenario;
+        game.export_format = self.format;
+        if let Some(dir) = &self.output {
+            game.export_dir = dir.clone();
+            game.auto_export = true;
+        }
+        // Headless runs are only useful if the numbers end up somewhere
+        if self.headless {
+            game.auto_export = true;
+        }
+        Ok(game)
+    }
+
+    // Network of intersections, each node keeps the manager and demand of its own scenario
+    pub fn build_network(&self, path: &Path) -> Result<Network, String> {
+        let config = NetworkConfig::load(path)?;
+        let seed = self.seed.or(config.seed).unwrap_or_else(|| fastrand::u64(..));
+        let mut network = Network::new(&config, seed)?;
+        if self.duration.is_some() {
+            network.duration = self.duration;
+        }
+        if network.duration.is_none() && self.headless {
+            return Err("Headless runs need a duration".to_string());
+        }
+        if self.reroute {
+            network.reroute = true;
+        }
+        if self.coordinate {
+            network.coordinate = true;
+        }
+        if let Some(manager) = self.manager {
+            for node in &mut network.nodes {
+                node.game.manager = manager;
+            }
+        }
+        if let Some(ttc) = self.ttc {
+            for node in &mut network.nodes {
+                node.game.ttc_threshold = ttc;
+            }
+        }
+        if let Some(timeout) = self.deadlock_timeout {
+            for node in &mut network.nodes {
+                node.game.deadlocks.timeout = timeout;
+            }
+        }
+        if let Some(junction) = self.junction {
+            for node in &mut network.nodes {
+                node.game.scenario.junction = junction;
+            }
+        }
+        if let Some(rate) = self.pedestrians {
+            for node in &mut network.nodes {
+                node.game.scenario.set_pedestrian_rate(rate);
+            }
+        }
+        if let Some(rate) = self.cyclists {
+            for node in &mut network.nodes {
+                node.game.scenario.set_cyclist_rate(rate);
+            }
+        }
+        if let Some(share) = self.penetration {
+            for node in &mut network.nodes {
+                node.game.scenario.penetration = share;
+            }
+        }
+        for node in &mut network.nodes {
+            node.game.scenario.prepare().map_err(|e| format!("Node '{}': {}", node.name, e))?;
+        }
+        if let Some(speed) = self.speed {
+            network.speed_multiplier = speed;
+        }
+        for node in &mut network.nodes {
+            node.game.export_format = self.format;
+        }
+        match &self.output {
+            Some(dir) => network.set_export(dir),
+            None if self.headless => network.set_export(&PathBuf::from("results")),
+            None => {}
+        }
+        Ok(network)
+    }
+
+    // Fresh game and its scenario, before the command line overrides
+    fn new_game(&self) -> Result<(Game, Scenario), String> {
+        let scenario = match &self.scenario {
+            Some(path) => Scenario::load(path)?,
+            None if self.headless => Scenario { name: "random".to_string(), ..Scenario::default() },
+            None => Scenario::default(),
+        };
+        let mut game = match self.seed.or(scenario.seed) {
+            Some(seed) => Game::with_seed(seed),
+            None => Game::new(),
+        };
+        if let Some(name) = &scenario.manager {
+            game.manager = Manager::parse(name)?;
+        }
+        Ok((game, scenario))
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(arg: &str, value: &str) -> Result<T, String> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| format!("Invalid value '{}' for {}", value, arg))
+}
+
+// Durations, speeds and thresholds, where zero or less would stop or reverse the run
+fn parse_positive(arg: &str, value: &str) -> Result<f32, String> {
+    let number: f32 = parse_number(arg, value)?;
+    if !(number > 0.0 && number.is_finite()) {
+        return Err(format!("{} takes a number above 0, not '{}'", arg, value));
+    }
+    Ok(number)
+}
+
+// Per minute demand, zero turns it off
+fn parse_rate(arg: &str, value: &str) -> Result<f32, String> {
+    let rate: f32 = parse_number(arg, value)?;
+    if !(rate >= 0.0 && rate.is_finite()) {
+        return Err(format!("{} takes a rate of 0 or more, not '{}'", arg, value));
+    }
+    Ok(rate)
+}
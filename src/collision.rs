@@ -1,105 +1,249 @@
 use crate::types::*;
+use crate::events::*;
+use crate::spatial::*;
+use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 
 const HITBOX_BUFFER: f32 = 2.0;
 
-pub fn build_car_tracking(car_data: &[(usize, f32, f32, Vec<CollisionType>, bool)]) -> HashMap<usize, Vec<(f32, f32, usize, CollisionType, bool)>> {
-    let mut car_tracking: HashMap<usize, Vec<(f32, f32, usize, CollisionType, bool)>> = HashMap::new();
+// How conflicts between two cars are decided
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Manager {
+    ReferencePoint, // closest to the shared reference point goes first
+    FirstCome,      // the car that spawned first goes first
+}
+
+impl Manager {
+    pub const ALL: [Manager; 2] = [Manager::ReferencePoint, Manager::FirstCome];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Manager::ReferencePoint => "reference-point",
+            Manager::FirstCome => "first-come",
+        }
+    }
+
+    pub fn parse(name: &str) -> Result<Self, String> {
+        Manager::ALL
+            .into_iter()
+            .find(|m| m.name() == name)
+            .ok_or_else(|| format!("Unknown manager '{}' (expected reference-point or first-come)", name))
+    }
+}
+
+// All collision types a movement can run into, its own lane type comes first.
+// Types and the table's reference points belong to a movement, not to a lane: cars making the
+// same movement from different lanes (a double left turn) share a type and a gate list, and the
+// manager never compares two cars of one approach. Their lanes keep them apart side by side,
+// and the footprint guard does where a turn swings wide. Only two cars driving straight across
+// each other are measured to where their own lanes cross.
+pub fn collision_types_for(route: &Route, dir: &Direction) -> Vec<CollisionType> {
+    match route {
+        Route::Straight => match dir {
+            Direction::North => vec![CollisionType::NS, CollisionType::WS, CollisionType::ES, CollisionType::WL, CollisionType::SL],
+            Direction::West => vec![CollisionType::WS, CollisionType::SS, CollisionType::NS, CollisionType::EL, CollisionType::SL],
+            Direction::East => vec![CollisionType::ES, CollisionType::SS, CollisionType::NS, CollisionType::WL, CollisionType::NL],
+            Direction::South => vec![CollisionType::SS, CollisionType::WS, CollisionType::ES, CollisionType::EL, CollisionType::NL],
+        },
+        Route::Left => match dir {
+            Direction::North => vec![CollisionType::NL, CollisionType::ES, CollisionType::SS],
+            Direction::West => vec![CollisionType::WL, CollisionType::NS, CollisionType::ES],
+            Direction::East => vec![CollisionType::EL, CollisionType::SS, CollisionType::WS],
+            Direction::South => vec![CollisionType::SL, CollisionType::NS, CollisionType::ES],
+        },
+        // Starts like a left turn, then crosses the opposing left and comes back on the
+        // opposing straight's side
+        Route::UTurn => match dir {
+            Direction::North => vec![CollisionType::NU, CollisionType::ES, CollisionType::SS, CollisionType::SL, CollisionType::SU],
+            Direction::West => vec![CollisionType::WU, CollisionType::NS, CollisionType::ES, CollisionType::EL, CollisionType::EU],
+            Direction::East => vec![CollisionType::EU, CollisionType::SS, CollisionType::WS, CollisionType::WL, CollisionType::WU],
+            Direction::South => vec![CollisionType::SU, CollisionType::NS, CollisionType::ES, CollisionType::NL, CollisionType::NU],
+        },
+        // Right turns keep to the kerb, only the bike lanes they cut across are in their way
+        Route::Right => vec![right_turn_type(dir)],
+    }
+}
+
+const RIGHT_TURNS: [CollisionType; 4] = [CollisionType::NR, CollisionType::SR, CollisionType::ER, CollisionType::WR];
+
+fn right_turn_type(dir: &Direction) -> CollisionType {
+    match dir {
+        Direction::North => CollisionType::NR,
+        Direction::West => CollisionType::WR,
+        Direction::East => CollisionType::ER,
+        Direction::South => CollisionType::SR,
+    }
+}
+
+// A cyclist crosses what a car going straight does, plus the right turns from its own
+// approach (the right hook) and the ones that turn onto its road across the bike lane
+pub fn bicycle_collision_types(dir: &Direction) -> Vec<CollisionType> {
+    let onto = match dir {
+        Direction::North => Direction::West,
+        Direction::West => Direction::South,
+        Direction::South => Direction::East,
+        Direction::East => Direction::North,
+    };
+    let mut types = collision_types_for(&Route::Straight, dir);
+    types.push(right_turn_type(dir));
+    types.push(right_turn_type(&onto));
+    types
+}
+
+// A cyclist and a car turning right across its bike lane
+pub fn right_hook(a: &Car, b: &Car) -> bool {
+    let hooks = |bike: &Car, car: &Car| {
+        bike.kind == VehicleKind::Bicycle
+            && car.kind != VehicleKind::Bicycle
+            && car.route == Route::Right
+            && bike.collision_types.contains(&right_turn_type(&car.direction))
+    };
+    hooks(a, b) || hooks(b, a)
+}
+
+// Two movements cross or merge somewhere in the intersection
+pub fn movements_conflict(dir_a: &Direction, route_a: &Route, dir_b: &Direction, route_b: &Route) -> bool {
+    let types_b = collision_types_for(route_b, dir_b);
+    collision_types_for(route_a, dir_a)
+        .iter()
+        .any(|t| *t != CollisionType::GG && types_b.contains(t))
+}
+
+// Another car as the manager compares it: where it is, its lane type and whether it has turned
+#[derive(Debug, Clone, Copy)]
+pub struct Tracked {
+    pub x: f32,
+    pub y: f32,
+    pub id: usize,
+    pub collision_type: CollisionType,
+    pub rotated: bool,
+}
+
+// Car id -> the cars near it that share a collision type with it
+pub type CarTracking = HashMap<usize, Vec<Tracked>>;
+
+// Only cars sharing a grid cell can have overlapping hitboxes, so those are the only ones compared.
+// Human drivers are left out until they have crossed their stop line, before that the manager
+// can't tell what they will do.
+pub fn build_car_tracking(cars: &[Car], grid: &SpatialGrid) -> CarTracking {
+    let mut car_tracking: CarTracking = HashMap::new();
     
-    for (id, _x, _y, types, _rotated) in car_data {
+    for car in cars {
         let mut temp_cor_car = Vec::new();
-        for (other_id, ox, oy, other_types, did_rotate) in car_data {
-            if id == other_id {
-                continue;
-            }
-            let filtered_types: Vec<CollisionType> = types.iter()
-                .cloned()
-                .filter(|t| *t != CollisionType::GG)
-                .collect();
-            let filtered_other_types: Vec<CollisionType> = other_types.iter()
-                .cloned()
-                .filter(|t| *t != CollisionType::GG)
-                .collect();
-                
-            if filtered_types.iter().any(|t| filtered_other_types.contains(t)) {
-                if !filtered_other_types.is_empty() {
-                    temp_cor_car.push((*ox, *oy, *other_id, filtered_other_types[0], *did_rotate));
+        // GG is never tracked, right turns are only tracked against bicycles: two cars turning
+        // right from one approach follow each other round the kerb
+        if car.collision_types.iter().any(|t| *t != CollisionType::GG) {
+            for i in grid.query(car_bounds(car)) {
+                let other = &cars[i];
+                let undecided = other.driver == Driver::Human && !other.entered && !other.rotated;
+                if other.id == car.id || undecided {
+                    continue;
+                }
+                let with_bicycle = car.kind == VehicleKind::Bicycle || other.kind == VehicleKind::Bicycle;
+                let shares_type = car.collision_types.iter().any(|t| {
+                    *t != CollisionType::GG
+                        && (with_bicycle || !RIGHT_TURNS.contains(t))
+                        && other.collision_types.contains(t)
+                });
+                    
+                if shares_type {
+                    if let Some(&other_type) = other.collision_types.iter().find(|t| **t != CollisionType::GG) {
+                        temp_cor_car.push(Tracked {
+                            x: other.x,
+                            y: other.y,
+                            id: other.id,
+                            collision_type: other_type,
+                            rotated: other.rotated,
+                        });
+                    }
                 }
             }
         }
-        car_tracking.insert(*id, temp_cor_car);
+        car_tracking.insert(car.id, temp_cor_car);
     }
     car_tracking
 }
 
+// Returns the car this one has to yield to, if any
 pub fn check_collision(
-    car_tracking: &HashMap<usize, Vec<(f32, f32, usize, CollisionType, bool)>>,
-    car_id: usize,
-    car_x: f32,
-    car_y: f32,
-    collision_types: &[CollisionType],
-    rotated: bool,
+    car_tracking: &CarTracking,
+    car: &Car,
     priority_map: &mut HashMap<(usize, usize), usize>,
     priority_ref: &mut HashMap<(usize, usize), usize>,
-    stats: &mut Stats,
-) -> bool {
-    let primary_type = collision_types.get(0).copied().unwrap_or(CollisionType::GG);
-    let car_corners = compute_rotated_corners(car_x, car_y, primary_type, rotated);
+    events: &mut EventLog,
+    manager: Manager,
+) -> Option<usize> {
+    let (car_id, car_x, car_y) = (car.id, car.x, car.y);
+    let primary_type = car.collision_types.first().copied().unwrap_or(CollisionType::GG);
+    let car_corners = compute_rotated_corners(car_x, car_y, primary_type, car.rotated);
     
     let mut temp_win = 0;
     
     if let Some(others) = car_tracking.get(&car_id) {
-        for &(x, y, other_id, other_type, did_rotate) in others {
+        for &Tracked { x, y, id: other_id, collision_type: other_type, rotated: did_rotate } in others {
             let other_corners = compute_rotated_corners(x, y, other_type, did_rotate);
             
             if sat_collision(&car_corners, &other_corners) {
                 let pair = (car_id.min(other_id), car_id.max(other_id));
                 
-                // Check existing winner from priority_map (This is where my algorithm takes place, given a set of reference points in a map,
-                // I compare their distances to a reference point and record who wins (who is closer) between two cars and
-                // make him the priority until the car leaves the intersection)
-                if let Some(&winner) = priority_map.get(&pair) {
-                    temp_win = winner;
-                    if winner != car_id {
-                        stats.close_call += 1;
-                        return true;
-                    }
+                // Determine reference point based on collision types, or where the two lanes
+                // really cross when both cars drive straight along them
+                let crossing = lane_crossing(primary_type, car.rotated, (car_x, car_y), other_type, did_rotate, (x, y));
+                let (ref_x, ref_y) = crossing.unwrap_or_else(|| get_reference_point(primary_type, other_type));
+                // Nothing to decide once either of them is through the crossing
+                if crossing.is_some()
+                    && (cleared(primary_type, car.rotated, (car_x, car_y), (ref_x, ref_y))
+                        || cleared(other_type, did_rotate, (x, y), (ref_x, ref_y)))
+                {
+                    continue;
                 }
                 
-                // Determine reference point based on collision types
-                let (ref_x, ref_y) = get_reference_point(primary_type, other_type);
-                
                 let key = (ref_x as usize, ref_y as usize);
-                // Check if either car has already reached the reference point
-                if let Some(&owner) = priority_ref.get(&key) {
+                // Check if either car has already reached the reference point, only the table's
+                // points are claimed. Checked before the map so both cars of a pair agree.
+                if let Some(&owner) = priority_ref.get(&key).filter(|_| crossing.is_none()) {
                     if owner == other_id {
-                        return true;
+                        return Some(other_id);
                     } else if owner == car_id {
                         continue;
                     }
                 }
                 
+                // Check existing winner from priority_map (This is where my algorithm takes place, given a set of reference points in a map,
+                // I compare their distances to a reference point and record who wins (who is closer) between two cars and
+                // make him the priority until the car leaves the intersection)
+                if let Some(&winner) = priority_map.get(&pair) {
+                    temp_win = winner;
+                    if winner != car_id {
+                        return Some(winner);
+                    }
+                }
+                
                 // Fallback to ID comparison for generic collisions
                 // Basically who respawned first
-                if ref_x == 500.0 {
-                    if car_id > other_id {
-                        return true;
-                    }
+                if ref_x == 500.0 && car_id > other_id {
+                    return Some(other_id);
                 }
                 
                 // Compare distances to reference point
                 let this_distance = ((car_x - ref_x).powi(2) + (car_y - ref_y).powi(2)).sqrt();
                 let other_distance = ((x - ref_x).powi(2) + (y - ref_y).powi(2)).sqrt();
                 
+                let loses = match manager {
+                    Manager::ReferencePoint => this_distance > other_distance,
+                    Manager::FirstCome => car_id > other_id,
+                };
+                
                 if temp_win == 0 && ref_x != 500.0 {
-                    if this_distance > other_distance {
+                    if loses {
                         if primary_type != other_type {
-                            stats.close_call += 1;
                             priority_map.insert(pair, other_id);
-                            return true;
+                            events.log(Some(other_id), EventKind::WonPriority { over: car_id });
+                            return Some(other_id);
                         }
                     } else if primary_type != other_type {
                         priority_map.insert(pair, car_id);
+                        events.log(Some(car_id), EventKind::WonPriority { over: other_id });
                     }
                 }
             }
@@ -107,23 +251,67 @@ pub fn check_collision(
     }
     
     // Update reference points
-    update_reference_points(car_x, car_y, &car_corners, car_id, priority_ref);
+    update_reference_points(car_x, car_y, &car_corners, car_id, priority_ref, events);
     
-    false
+    None
+}
+
+// Way a car of this lane type drives, left turns face their exit once they have turned.
+// None for right turns and U-turns, which don't drive straight across.
+fn lane_heading(collision_type: CollisionType, rotated: bool) -> Option<Vec2> {
+    let (before, after) = match collision_type {
+        CollisionType::NS => ((0.0, 1.0), (0.0, 1.0)),
+        CollisionType::SS => ((0.0, -1.0), (0.0, -1.0)),
+        CollisionType::ES => ((1.0, 0.0), (1.0, 0.0)),
+        CollisionType::WS => ((-1.0, 0.0), (-1.0, 0.0)),
+        CollisionType::NL => ((0.0, 1.0), (-1.0, 0.0)),
+        CollisionType::SL => ((0.0, -1.0), (1.0, 0.0)),
+        CollisionType::EL => ((1.0, 0.0), (0.0, 1.0)),
+        CollisionType::WL => ((-1.0, 0.0), (0.0, -1.0)),
+        _ => return None,
+    };
+    let (x, y) = if rotated { after } else { before };
+    Some(Vec2::new(x, y))
+}
+
+// Where two cars driving straight at right angles cross, from the lanes they are in. Bike lanes
+// and the other lanes of a multi-lane road cross away from the table's points.
+fn lane_crossing(
+    type1: CollisionType,
+    rotated1: bool,
+    (x1, y1): (f32, f32),
+    type2: CollisionType,
+    rotated2: bool,
+    (x2, y2): (f32, f32),
+) -> Option<(f32, f32)> {
+    let heading1 = lane_heading(type1, rotated1)?;
+    let heading2 = lane_heading(type2, rotated2)?;
+    if heading1.dot(heading2) != 0.0 {
+        return None;
+    }
+    Some(if heading1.x == 0.0 { (x1, y2) } else { (x2, y1) })
+}
+
+// The car's body has left the other lane at the crossing behind it
+fn cleared(collision_type: CollisionType, rotated: bool, (x, y): (f32, f32), (cross_x, cross_y): (f32, f32)) -> bool {
+    lane_heading(collision_type, rotated)
+        .is_some_and(|heading| heading.dot(Vec2::new(x - cross_x, y - cross_y)) > (CAR_LENGTH + CAR_WIDTH) / 2.0)
 }
 
 pub fn check_spawn_collision(
     x: f32,
     y: f32,
     collision_type: CollisionType,
-    car_data: &[(usize, f32, f32, Vec<CollisionType>, bool)],
+    cars: &[Car],
+    grid: &SpatialGrid,
 ) -> bool {
     let car_corners = compute_rotated_corners(x, y, collision_type, false);
     
-    for (_, ox, oy, other_types, did_rotate) in car_data {
-        if let Some(&other_type) = other_types.get(0) {
+    for i in grid.query(corners_bounds(&car_corners)) {
+        let other = &cars[i];
+        if let Some(&other_type) = other.collision_types.first() {
             if collision_type == other_type {
-                let other_corners = compute_rotated_corners(*ox, *oy, other_type, *did_rotate);
+                let other_corners = compute_rotated_corners(other.x, other.y, other_type, other.rotated);
                 if sat_collision(&car_corners, &other_corners) {
                     return true;
                 }
@@ -133,6 +321,12 @@ pub fn check_spawn_collision(
     false
 }
 
+// Where the paths of two movements cross, None when the tables have no point for the pair
+pub fn conflict_point(type1: CollisionType, type2: CollisionType) -> Option<(f32, f32)> {
+    let (ref_x, ref_y) = get_reference_point(type1, type2);
+    (ref_x != 500.0).then_some((ref_x, ref_y))
+}
+
 fn get_reference_point(type1: CollisionType, type2: CollisionType) -> (f32, f32) {
     match (type1, type2) {
         (CollisionType::NS, CollisionType::ES) | (CollisionType::ES, CollisionType::NS) => (600.0, 292.0),
@@ -147,6 +341,31 @@ fn get_reference_point(type1: CollisionType, type2: CollisionType) -> (f32, f32)
         (CollisionType::EL, CollisionType::WS) | (CollisionType::WS, CollisionType::EL) => (540.0, 415.0),
         (CollisionType::WL, CollisionType::NS) | (CollisionType::NS, CollisionType::WL) => (600.0, 365.0),
         (CollisionType::WL, CollisionType::ES) | (CollisionType::ES, CollisionType::WL) => (480.0, 292.0),
+        // Right turns from the lane next to the bike lane, where they cross a bike lane
+        (CollisionType::NR, CollisionType::NS) | (CollisionType::NS, CollisionType::NR) => (655.0, 278.0),
+        (CollisionType::NR, CollisionType::ES) | (CollisionType::ES, CollisionType::NR) => (595.0, 230.0),
+        (CollisionType::SR, CollisionType::SS) | (CollisionType::SS, CollisionType::SR) => (360.0, 390.0),
+        (CollisionType::SR, CollisionType::WS) | (CollisionType::WS, CollisionType::SR) => (420.0, 435.0),
+        (CollisionType::ER, CollisionType::ES) | (CollisionType::ES, CollisionType::ER) => (420.0, 230.0),
+        (CollisionType::ER, CollisionType::SS) | (CollisionType::SS, CollisionType::ER) => (360.0, 278.0),
+        (CollisionType::WR, CollisionType::WS) | (CollisionType::WS, CollisionType::WR) => (595.0, 435.0),
+        (CollisionType::WR, CollisionType::NS) | (CollisionType::NS, CollisionType::WR) => (655.0, 390.0),
+        // U-turns: where the way in crosses a straight or a left turn's way out, where the half
+        // circle comes closest to the opposing straight, and the middle the half circles share
+        (CollisionType::NU, CollisionType::ES) | (CollisionType::ES, CollisionType::NU) => (535.0, 278.0),
+        (CollisionType::NU, CollisionType::SS) | (CollisionType::SS, CollisionType::NU) => (420.0, 322.0),
+        (CollisionType::NU, CollisionType::SL) | (CollisionType::SL, CollisionType::NU) => (535.0, 315.0),
+        (CollisionType::SU, CollisionType::NS) | (CollisionType::NS, CollisionType::SU) => (595.0, 342.0),
+        (CollisionType::SU, CollisionType::ES) | (CollisionType::ES, CollisionType::SU) => (507.0, 278.0),
+        (CollisionType::SU, CollisionType::NL) | (CollisionType::NL, CollisionType::SU) => (480.0, 350.0),
+        (CollisionType::EU, CollisionType::SS) | (CollisionType::SS, CollisionType::EU) => (420.0, 315.0),
+        (CollisionType::EU, CollisionType::WS) | (CollisionType::WS, CollisionType::EU) => (517.0, 390.0),
+        (CollisionType::EU, CollisionType::WL) | (CollisionType::WL, CollisionType::EU) => (480.0, 315.0),
+        (CollisionType::WU, CollisionType::NS) | (CollisionType::NS, CollisionType::WU) => (595.0, 350.0),
+        (CollisionType::WU, CollisionType::ES) | (CollisionType::ES, CollisionType::WU) => (497.0, 278.0),
+        (CollisionType::WU, CollisionType::EL) | (CollisionType::EL, CollisionType::WU) => (535.0, 350.0),
+        (CollisionType::NU, CollisionType::SU) | (CollisionType::SU, CollisionType::NU)
+        | (CollisionType::EU, CollisionType::WU) | (CollisionType::WU, CollisionType::EU) => (507.0, 332.0),
         _ => (500.0, 500.0), // fallback
     }
 }
@@ -157,22 +376,130 @@ fn update_reference_points(
     car_corners: &[Vec2; 4],
     car_id: usize,
     priority_ref: &mut HashMap<(usize, usize), usize>,
+    events: &mut EventLog,
 ) {
     let reference_points = [
         (600.0, 292.0), (600.0, 410.0), (415.0, 410.0), (415.0, 292.0),
         (415.0, 365.0), (415.0, 292.0), (535.0, 292.0), (480.0, 410.0),
         (600.0, 330.0), (415.0, 330.0), (540.0, 415.0), (600.0, 365.0),
         (480.0, 292.0),
+        (655.0, 278.0), (595.0, 230.0), (360.0, 390.0), (420.0, 435.0),
+        (420.0, 230.0), (360.0, 278.0), (595.0, 435.0), (655.0, 390.0),
+        (535.0, 278.0), (420.0, 322.0), (535.0, 315.0), (595.0, 342.0),
+        (507.0, 278.0), (480.0, 350.0), (420.0, 315.0), (517.0, 390.0),
+        (480.0, 315.0), (595.0, 350.0), (497.0, 278.0), (535.0, 350.0),
+        (507.0, 332.0),
     ];
     
     for &(ref_x, ref_y) in &reference_points {
         if contains_point(car_corners, ref_x, ref_y) {
             let key = (ref_x.round() as usize, ref_y.round() as usize);
-            if !priority_ref.contains_key(&key) {
-                priority_ref.insert(key, car_id);
+            if let Entry::Vacant(entry) = priority_ref.entry(key) {
+                entry.insert(car_id);
+                events.log(Some(car_id), EventKind::ReferencePointClaimed { x: key.0, y: key.1 });
+            }
+        }
+    }
+}
+
+// Actual body of a car or bicycle (no hitbox buffer), rotated like the sprite
+pub fn car_footprint(car: &Car) -> [Vec2; 4] {
+    let (width, length) = car.kind.size();
+    let hw = width / 2.0;
+    let hh = length / 2.0;
+    let (sin, cos) = car.rotation.sin_cos();
+    let corner = |dx: f32, dy: f32| Vec2::new(car.x + dx * cos - dy * sin, car.y + dx * sin + dy * cos);
+    
+    [
+        corner(-hw, -hh),
+        corner(hw, -hh),
+        corner(hw, hh),
+        corner(-hw, hh),
+    ]
+}
+
+pub fn footprints_overlap(a: &Car, b: &Car) -> bool {
+    sat_collision(
+        &car_footprint(a),
+        &car_footprint(b),
+    )
+}
+
+// Id of a car whose body overlaps the car at `index`. Others may have moved a step or
+// turned since the grid was built, so it is searched half a car length wider.
+pub fn footprint_blocker(cars: &[Car], index: usize, grid: &SpatialGrid) -> Option<usize> {
+    let car = &cars[index];
+    let margin = CAR_LENGTH / 2.0;
+    let mut bounds = car_bounds(car);
+    bounds.min_x -= margin;
+    bounds.min_y -= margin;
+    bounds.max_x += margin;
+    bounds.max_y += margin;
+    grid.query(bounds)
+        .into_iter()
+        .find(|&i| i != index && footprints_overlap(car, &cars[i]))
+        .map(|i| cars[i].id)
+}
+
+// Grid bounds of a car: its hitbox and its body, whichever reaches further
+pub fn car_bounds(car: &Car) -> Aabb {
+    let primary_type = car.collision_types.first().copied().unwrap_or(CollisionType::GG);
+    corners_bounds(&compute_rotated_corners(car.x, car.y, primary_type, car.rotated))
+        .union(corners_bounds(&car_footprint(car)))
+}
+
+fn corners_bounds(corners: &[Vec2; 4]) -> Aabb {
+    let mut bounds = Aabb { min_x: f32::MAX, min_y: f32::MAX, max_x: f32::MIN, max_y: f32::MIN };
+    for corner in corners {
+        bounds.min_x = bounds.min_x.min(corner.x);
+        bounds.min_y = bounds.min_y.min(corner.y);
+        bounds.max_x = bounds.max_x.max(corner.x);
+        bounds.max_y = bounds.max_y.max(corner.y);
+    }
+    bounds
+}
+
+// Seconds until the two car bodies touch if both keep their current velocity, None if they
+// don't within `horizon`. Same separating axes as `sat_collision`, but each axis gives the
+// time interval during which the projections overlap.
+pub fn time_to_collision(a: &Car, b: &Car, horizon: f32) -> Option<f32> {
+    let a_corners = car_footprint(a);
+    let b_corners = car_footprint(b);
+    // Motion of b as seen from a
+    let relative = Vec2::new(b.velocity.x - a.velocity.x, b.velocity.y - a.velocity.y);
+    
+    let mut enter = 0.0_f32;
+    let mut exit = horizon;
+    for axis in edge_normals(&a_corners).into_iter().chain(edge_normals(&b_corners)) {
+        let (a_min, a_max) = project(&a_corners, axis);
+        let (b_min, b_max) = project(&b_corners, axis);
+        let speed = relative.dot(axis);
+        
+        if speed.abs() < f32::EPSILON {
+            if a_max < b_min || b_max < a_min {
+                return None; // Separated and staying that way
             }
+            continue;
+        }
+        
+        let t1 = (a_min - b_max) / speed;
+        let t2 = (a_max - b_min) / speed;
+        enter = enter.max(t1.min(t2));
+        exit = exit.min(t1.max(t2));
+        if enter > exit {
+            return None;
         }
     }
+    Some(enter)
+}
+
+fn edge_normals(corners: &[Vec2; 4]) -> [Vec2; 4] {
+    let normal = |i: usize| {
+        let p1 = corners[i];
+        let p2 = corners[(i + 1) % 4];
+        Vec2::new(p1.y - p2.y, p2.x - p1.x).normalize()
+    };
+    [normal(0), normal(1), normal(2), normal(3)]
 }
 
 fn compute_rotated_corners(x: f32, y: f32, collision_type: CollisionType, rotated: bool) -> [Vec2; 4] {
@@ -199,26 +526,8 @@ fn compute_rotated_corners(x: f32, y: f32, collision_type: CollisionType, rotate
 
 //Could have used SDL2 Rect.contains() but I just love this ALGO 
 fn sat_collision(a: &[Vec2; 4], b: &[Vec2; 4]) -> bool {
-    let mut axes = Vec::with_capacity(8);
-    
     // Get normals of each edge as potential separating axes
-    for i in 0..4 {
-        let p1 = a[i];
-        let p2 = a[(i + 1) % 4];
-        let edge = Vec2::new(p2.x - p1.x, p2.y - p1.y);
-        let normal = Vec2::new(-edge.y, edge.x).normalize();
-        axes.push(normal);
-    }
-    
-    for i in 0..4 {
-        let p1 = b[i];
-        let p2 = b[(i + 1) % 4];
-        let edge = Vec2::new(p2.x - p1.x, p2.y - p1.y);
-        let normal = Vec2::new(-edge.y, edge.x).normalize();
-        axes.push(normal);
-    }
-    
-    for axis in axes {
+    for axis in edge_normals(a).into_iter().chain(edge_normals(b)) {
         let (a_min, a_max) = project(a, axis);
         let (b_min, b_max) = project(b, axis);
         
@@ -243,7 +552,7 @@ fn project(points: &[Vec2; 4], axis: Vec2) -> (f32, f32) {
     (min, max)
 }
 
-fn contains_point(corners: &[Vec2; 4], px: f32, py: f32) -> bool {
+pub fn contains_point(corners: &[Vec2; 4], px: f32, py: f32) -> bool {
     let mut inside = false;
     for i in 0..4 {
         let j = (i + 1) % 4;
@@ -0,0 +1,182 @@
+use crate::collision::car_footprint;
+use crate::deadlock::{pedestrian_node, DeadlockDetector, WaitReason};
+use crate::lanes::{exit_direction, LaneLayout, CROSSING_X, CROSSING_Y};
+use crate::types::*;
+use serde::{Deserialize, Serialize};
+
+// Walking pace, about 1.4 m/s with a lane 3.5 m wide
+pub const PEDESTRIAN_SPEED: f32 = 25.0;
+
+// Drawn size of a pedestrian
+pub const PEDESTRIAN_SIZE: f32 = 10.0;
+
+// Depth of a crosswalk along its road
+pub const CROSSWALK_WIDTH: f32 = 24.0;
+
+// Room between the back of a car at the stop line and the crosswalk, so a car waiting for the
+// intersection doesn't stand on it
+const CROSSWALK_SETBACK: f32 = CAR_LENGTH / 2.0 + 6.0;
+
+// Cars this close to a crosswalk stop for someone on it, more than a step so none is missed
+const YIELD_DISTANCE: f32 = 12.0;
+
+// Sideways room a car keeps to a pedestrian
+const CLEARANCE: f32 = 24.0;
+
+// Pedestrians wait this far off the road
+const KERB_OFFSET: f32 = 8.0;
+
+// Crosswalks have no signals: people cross whenever no car is on their crosswalk and the manager
+// stops cars for them. Signal phases for people are out of scope.
+
+// Someone crossing one leg. Legs are named like exits: by the heading of the cars that leave
+// the intersection on them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pedestrian {
+    pub id: usize,
+    pub leg: Direction,
+    pub forward: bool, // walking towards higher x or y
+    pub position: f32, // across the road: x on north and south legs, y on east and west ones
+    pub spawn_time: f32,
+    pub wait_time: f32, // seconds spent standing, at the kerb or on the road
+    pub crossing: bool, // has stepped off the kerb
+    pub moving: bool, // false once on the far side
+}
+
+impl Pedestrian {
+    pub fn new(id: usize, leg: Direction, forward: bool, spawn_time: f32) -> Self {
+        let (from, to) = crosswalk_span(&leg);
+        Pedestrian {
+            id,
+            leg,
+            forward,
+            position: if forward { from - KERB_OFFSET } else { to + KERB_OFFSET },
+            spawn_time,
+            wait_time: 0.0,
+            crossing: false,
+            moving: true,
+        }
+    }
+
+    pub fn position(&self) -> (f32, f32) {
+        let (from, to) = crosswalk_band(&self.leg);
+        let along = (from + to) / 2.0;
+        match self.leg {
+            Direction::North | Direction::South => (self.position, along),
+            Direction::East | Direction::West => (along, self.position),
+        }
+    }
+}
+
+// A leg has a crosswalk when there is a road there, cars coming in or going out
+pub fn has_road(layout: &LaneLayout, leg: &Direction) -> bool {
+    layout.exit_lanes(leg) > 0 || !layout.lanes(&leg.opposite()).is_empty()
+}
+
+// Along the road: the strip a crosswalk covers, set back from the stop line of the cars
+// arriving on the leg
+pub fn crosswalk_band(leg: &Direction) -> (f32, f32) {
+    let (stop_line, outwards) = match leg {
+        Direction::North => (540.0, 1.0), // cars heading south arrive on the north leg
+        Direction::South => (170.0, -1.0),
+        Direction::East => (740.0, 1.0),
+        Direction::West => (273.0, -1.0),
+    };
+    let near = stop_line + outwards * CROSSWALK_SETBACK;
+    let far = near + outwards * CROSSWALK_WIDTH;
+    (near.min(far), near.max(far))
+}
+
+// Across the road, kerb to kerb like the crossing area
+pub fn crosswalk_span(leg: &Direction) -> (f32, f32) {
+    match leg {
+        Direction::North | Direction::South => CROSSING_X,
+        Direction::East | Direction::West => CROSSING_Y,
+    }
+}
+
+// Way the car faces now, turned cars face their exit
+pub fn car_heading(car: &Car) -> Direction {
+    if car.rotated {
+        exit_direction(&car.direction, &car.route)
+    } else {
+        car.direction.clone()
+    }
+}
+
+// Part of the car's body on a leg's crosswalk, across the road, None when it is off it
+fn on_crosswalk(car: &Car, leg: &Direction) -> Option<(f32, f32)> {
+    let corners = car_footprint(car);
+    let (band_from, band_to) = crosswalk_band(leg);
+    let extent = |coordinate: fn(&Vec2) -> f32| {
+        corners.iter().map(coordinate).fold((f32::MAX, f32::MIN), |(lo, hi), c| (lo.min(c), hi.max(c)))
+    };
+    let (along, across) = match leg {
+        Direction::North | Direction::South => (extent(|c| c.y), extent(|c| c.x)),
+        Direction::East | Direction::West => (extent(|c| c.x), extent(|c| c.y)),
+    };
+    (along.0 < band_to && along.1 > band_from).then_some(across)
+}
+
+// Pedestrian the car has to stop for: anyone waiting or walking close to its lane on a
+// crosswalk its front is about to reach. Cars already on a crosswalk drive on.
+pub fn pedestrian_ahead(car: &Car, pedestrians: &[Pedestrian]) -> Option<usize> {
+    let heading = car_heading(car);
+    let (along, lateral, sign) = match heading {
+        Direction::North => (car.y, car.x, 1.0),
+        Direction::South => (car.y, car.x, -1.0),
+        Direction::East => (car.x, car.y, 1.0),
+        Direction::West => (car.x, car.y, -1.0),
+    };
+    let (width, length) = car.kind.size();
+    let front = along + sign * length / 2.0;
+    let reach = width / 2.0 + CLEARANCE;
+
+    pedestrians
+        .iter()
+        .filter(|p| p.moving && (p.leg == heading || p.leg == heading.opposite()))
+        .filter(|p| {
+            let (band_from, band_to) = crosswalk_band(&p.leg);
+            let (span_from, span_to) = crosswalk_span(&p.leg);
+            let distance = if sign > 0.0 { band_from - front } else { front - band_to };
+            (0.0..=YIELD_DISTANCE).contains(&distance)
+                && lateral > span_from
+                && lateral < span_to
+                && (p.position - lateral).abs() < reach
+        })
+        .map(|p| p.id)
+        .next()
+}
+
+// Pedestrians at the kerb step off once no car is on their crosswalk, on the road they stop for
+// a car standing in their way and leave at the far kerb. Their waits go in the wait-for graph.
+pub fn move_pedestrians(pedestrians: &mut [Pedestrian], cars: &[Car], deadlocks: &mut DeadlockDetector, delta_time: f32) {
+    for pedestrian in pedestrians.iter_mut().filter(|p| p.moving) {
+        let step = if pedestrian.forward { PEDESTRIAN_SPEED } else { -PEDESTRIAN_SPEED } * delta_time;
+        let next = pedestrian.position + step;
+        let half = PEDESTRIAN_SIZE / 2.0;
+        // At the kerb any car on the crosswalk holds them, on the road only one in their way
+        let blockers: Vec<usize> = cars
+            .iter()
+            .filter(|car| {
+                on_crosswalk(car, &pedestrian.leg)
+                    .is_some_and(|(from, to)| !pedestrian.crossing || (next + half > from && next - half < to))
+            })
+            .map(|car| car.id)
+            .collect();
+        if !blockers.is_empty() {
+            for car_id in blockers {
+                deadlocks.wait(pedestrian_node(pedestrian.id), car_id, WaitReason::Crossing);
+            }
+            pedestrian.wait_time += delta_time;
+            continue;
+        }
+        pedestrian.crossing = true;
+        pedestrian.position = next;
+
+        let (from, to) = crosswalk_span(&pedestrian.leg);
+        if next < from - KERB_OFFSET || next > to + KERB_OFFSET {
+            pedestrian.moving = false;
+        }
+    }
+}
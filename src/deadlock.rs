@@ -0,0 +1,210 @@
+use crate::types::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+// Seconds a car may stand still before whatever it waits on is released
+pub const DEADLOCK_TIMEOUT: f32 = 10.0;
+// Seconds a released car ignores the manager's yields
+pub const RELEASE_TIME: f32 = 3.0;
+
+// Why a car didn't move this step
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum WaitReason {
+    Yield,   // the manager gave the other car priority
+    Blocked, // its body would have run into the other car
+    Gate,    // the intersection was full, it waits on every car inside
+    GiveWay, // at a roundabout entry or a human's stop line, the others keep moving so it is never released
+    Crossing, // a pedestrian is on the crosswalk ahead or a car on the pedestrian's, nobody drives over people
+}
+
+// Pedestrians wait and are waited on in the same graph as cars, under ids counted down from the
+// top so they never meet a car's
+pub fn pedestrian_node(pedestrian_id: usize) -> usize {
+    usize::MAX - pedestrian_id
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Wait {
+    pub on: usize,
+    pub reason: WaitReason,
+}
+
+// A wait cycle or a car stopped past the timeout, and the car let go to end it. None when no
+// car of it only waits for the manager, then it is only reported.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Deadlock {
+    pub car: usize, // the stuck car, or the lowest id of the cycle
+    pub released: Option<usize>,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeadlockDetector {
+    pub timeout: f32,
+    pub waits_for: HashMap<usize, Vec<Wait>>, // wait-for graph of the last step
+    pub stopped_since: HashMap<usize, f32>,
+    pub released: HashMap<usize, f32>, // car id -> sim time its release ends
+    pub unreleased_cycles: Vec<Vec<usize>>, // cycles of the last step nobody could be released from
+}
+
+impl DeadlockDetector {
+    pub fn new() -> Self {
+        DeadlockDetector {
+            timeout: DEADLOCK_TIMEOUT,
+            ..DeadlockDetector::default()
+        }
+    }
+
+    // Edges are collected again on every step
+    pub fn clear_waits(&mut self) {
+        self.waits_for.clear();
+    }
+
+    pub fn wait(&mut self, car_id: usize, on: usize, reason: WaitReason) {
+        if car_id != on {
+            self.waits_for.entry(car_id).or_default().push(Wait { on, reason });
+        }
+    }
+
+    pub fn is_released(&self, car_id: usize, now: f32) -> bool {
+        self.released.get(&car_id).is_some_and(|&until| now < until)
+    }
+
+    pub fn car_left(&mut self, car_id: usize) {
+        self.waits_for.remove(&car_id);
+        self.stopped_since.remove(&car_id);
+        self.released.remove(&car_id);
+    }
+
+    // Once per tick: release the oldest car of every wait cycle and of every car stopped
+    // past the timeout. Returns every deadlock found for the log, a cycle nobody can be
+    // released from is reported once for as long as it lasts.
+    pub fn resolve(&mut self, cars: &[Car], now: f32, stats: &mut Stats) -> Vec<Deadlock> {
+        self.released.retain(|_, &mut until| now < until);
+        for car in cars {
+            if car.velocity.x == 0.0 && car.velocity.y == 0.0 {
+                self.stopped_since.entry(car.id).or_insert(now);
+            } else {
+                self.stopped_since.remove(&car.id);
+            }
+        }
+
+        let by_id: HashMap<usize, &Car> = cars.iter().map(|car| (car.id, car)).collect();
+        let mut resolved = Vec::new();
+
+        let mut unreleased = Vec::new();
+        for cycle in self.find_cycles() {
+            let released = self.pick_release(&cycle, &by_id, now);
+            if released.is_some() {
+                stats.deadlocks += 1;
+            } else if self.unreleased_cycles.contains(&cycle) {
+                unreleased.push(cycle);
+                continue;
+            } else {
+                stats.unresolved_deadlocks += 1;
+            }
+            resolved.push(Deadlock { car: cycle[0], released, reason: format!("cars {:?} wait on each other", cycle) });
+            if released.is_none() {
+                unreleased.push(cycle);
+            }
+        }
+        self.unreleased_cycles = unreleased;
+
+        let mut stuck: Vec<(usize, f32)> = self.stopped_since
+            .iter()
+            .filter(|&(_, &since)| now - since > self.timeout)
+            .map(|(&id, &since)| (id, since))
+            .collect();
+        stuck.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+        for (id, since) in stuck {
+            if self.is_released(id, now) {
+                continue;
+            }
+            let waiting_on = self.reachable(id);
+            let released = self.pick_release(&waiting_on, &by_id, now);
+            if released.is_some() {
+                stats.deadlocks += 1;
+            } else {
+                stats.unresolved_deadlocks += 1;
+            }
+            resolved.push(Deadlock { car: id, released, reason: format!("car {} stopped for {:.1} s", id, now - since) });
+            // Give the release time to work before this car counts as stuck again
+            self.stopped_since.insert(id, now);
+        }
+        resolved
+    }
+
+    // The oldest stopped car that only waits for the manager, releasing a car held by the gate
+    // or pushed against another body wouldn't get it moving
+    fn pick_release(&mut self, candidates: &[usize], cars: &HashMap<usize, &Car>, now: f32) -> Option<usize> {
+        let id = candidates
+            .iter()
+            .filter_map(|id| cars.get(id))
+            .filter(|car| self.stopped_since.contains_key(&car.id) && !self.is_released(car.id, now))
+            .filter(|car| {
+                self.waits_for
+                    .get(&car.id)
+                    .is_some_and(|waits| waits.iter().all(|w| w.reason == WaitReason::Yield))
+            })
+            .min_by(|a, b| a.spawn_time.total_cmp(&b.spawn_time).then(a.id.cmp(&b.id)))
+            .map(|car| car.id)?;
+        self.released.insert(id, now + RELEASE_TIME);
+        Some(id)
+    }
+
+    // Every car reachable from `start` in the wait-for graph, `start` included
+    fn reachable(&self, start: usize) -> Vec<usize> {
+        let mut seen = vec![start];
+        let mut i = 0;
+        while i < seen.len() {
+            if let Some(waits) = self.waits_for.get(&seen[i]) {
+                for wait in waits {
+                    if !seen.contains(&wait.on) {
+                        seen.push(wait.on);
+                    }
+                }
+            }
+            i += 1;
+        }
+        seen
+    }
+
+    // Depth-first search, each cycle is reported once with its cars in wait order
+    fn find_cycles(&self) -> Vec<Vec<usize>> {
+        let mut starts: Vec<usize> = self.waits_for.keys().copied().collect();
+        starts.sort_unstable();
+
+        let mut done = HashSet::new();
+        let mut cycles = Vec::new();
+        for start in starts {
+            if done.contains(&start) {
+                continue;
+            }
+            let mut path: Vec<usize> = Vec::new();
+            let mut stack = vec![(start, 0)];
+            while let Some(&mut (node, ref mut next)) = stack.last_mut() {
+                if *next == 0 {
+                    path.push(node);
+                }
+                let waits = self.waits_for.get(&node).map_or(&[][..], |w| &w[..]);
+                if let Some(wait) = waits.get(*next) {
+                    *next += 1;
+                    if let Some(pos) = path.iter().position(|&id| id == wait.on) {
+                        // Started at the lowest id so the same cycle always reads the same
+                        let mut cycle = path[pos..].to_vec();
+                        let lowest = (0..cycle.len()).min_by_key(|&i| cycle[i]).unwrap_or(0);
+                        cycle.rotate_left(lowest);
+                        cycles.push(cycle);
+                    } else if !done.contains(&wait.on) {
+                        stack.push((wait.on, 0));
+                    }
+                } else {
+                    done.insert(node);
+                    path.pop();
+                    stack.pop();
+                }
+            }
+        }
+        cycles
+    }
+}
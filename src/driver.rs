@@ -0,0 +1,128 @@
+use crate::collision::{car_footprint, conflict_point};
+use crate::crosswalk::car_heading;
+use crate::lanes::{distance_to_stop_line, CROSSING_X, CROSSING_Y};
+use crate::movement::move_car;
+use crate::safety::conflicting;
+use crate::types::*;
+use std::collections::HashMap;
+
+// Seconds a human driver takes to move off once the way ahead is clear
+pub const REACTION_TIME: f32 = 1.0;
+
+// Seconds a conflicting car has to be away from its stop line for a human driver to go
+pub const ACCEPTED_GAP: f32 = 3.0;
+
+fn heading_vector(car: &Car) -> (f32, f32) {
+    match car_heading(car) {
+        Direction::North => (0.0, 1.0),
+        Direction::South => (0.0, -1.0),
+        Direction::East => (1.0, 0.0),
+        Direction::West => (-1.0, 0.0),
+    }
+}
+
+// The car's body is out of the crossing area and it is driving away from it
+fn cleared(car: &Car) -> bool {
+    let outside = car_footprint(car)
+        .iter()
+        .all(|corner| corner.x < CROSSING_X.0 || corner.x > CROSSING_X.1 || corner.y < CROSSING_Y.0 || corner.y > CROSSING_Y.1);
+    let (dx, dy) = heading_vector(car);
+    let centre = ((CROSSING_X.0 + CROSSING_X.1) / 2.0, (CROSSING_Y.0 + CROSSING_Y.1) / 2.0);
+    outside && (car.x - centre.0) * dx + (car.y - centre.1) * dy > 0.0
+}
+
+// The other car's back is beyond the point where its path meets the car's, so it is out of the
+// way. Pairs without a known point are never out of the way while crossing.
+fn past_conflict(car: &Car, other: &Car) -> bool {
+    let first = |c: &Car| c.collision_types.first().copied().unwrap_or(CollisionType::GG);
+    let Some((px, py)) = conflict_point(first(car), first(other)) else {
+        return false;
+    };
+    let (dx, dy) = heading_vector(other);
+    let (_, length) = other.kind.size();
+    (px - other.x) * dx + (py - other.y) * dy < -length / 2.0
+}
+
+// Paths that meet somewhere in the crossing. Left turns from different approaches share no
+// collision type, the gate keeps them apart for connected cars, so humans look out for them too.
+fn crosses(car: &Car, other: &Car) -> bool {
+    let turns_across = |c: &Car| matches!(c.route, Route::Left | Route::UTurn);
+    conflicting(car, other) || (car.direction != other.direction && turns_across(car) && turns_across(other))
+}
+
+// Car the car at `index` waits for before it crosses its stop line. Humans don't hear from the
+// manager, they look: a conflicting car crossing that hasn't got past their path, one arriving
+// at its own line within the accepted gap, or another human who got to the line first.
+// Connected cars leave the rest to the manager, but don't pull out in front of a human who is
+// crossing since it won't give way to them.
+pub fn gap_blocker(cars: &[Car], index: usize, delta_time: f32) -> Option<usize> {
+    let car = &cars[index];
+    if car.entered || car.rotated {
+        return None;
+    }
+    let left = distance_to_stop_line(car);
+    if left < 0.0 || left > car.speed * delta_time {
+        return None;
+    }
+    let human = car.driver == Driver::Human;
+    cars.iter()
+        .enumerate()
+        .filter(|&(i, other)| i != index && other.moving && crosses(car, other))
+        .find(|(_, other)| {
+            if other.entered || other.rotated {
+                return (human || other.driver == Driver::Human) && !cleared(other) && !past_conflict(car, other);
+            }
+            if !human {
+                return false;
+            }
+            let other_left = distance_to_stop_line(other);
+            if other_left < 0.0 {
+                return false;
+            }
+            let speed = other.velocity.length();
+            if speed > 0.0 {
+                other_left / speed < ACCEPTED_GAP
+            } else {
+                // Both standing at their lines, the one that came first goes
+                other.driver == Driver::Human && other.id < car.id && other_left <= other.speed * delta_time
+            }
+        })
+        .map(|(_, other)| other.id)
+}
+
+// A human driver standing still only moves off once the way has been clear for the reaction
+// time. Returns true while it is still reacting, the move it made from (old_x, old_y) is taken
+// back then.
+pub fn still_reacting(car: &mut Car, old_x: f32, old_y: f32, delta_time: f32) -> bool {
+    if car.driver != Driver::Human || car.distance == 0.0 {
+        return false;
+    }
+    let moved = car.x != old_x || car.y != old_y;
+    let stood_still = car.velocity.x == 0.0 && car.velocity.y == 0.0;
+    if !stood_still || !moved {
+        car.reaction = 0.0;
+        return false;
+    }
+    car.reaction += delta_time;
+    car.reaction < REACTION_TIME
+}
+
+// Humans drive through whatever the intersection gate says. They move against an empty gate and
+// are then listed inside like every other car, so connected cars keep out of their way.
+pub fn move_human(car: &mut Car, delta_time: f32, in_intersection: &mut HashMap<CollisionType, Vec<usize>>, junction: Junction) {
+    let mut own: HashMap<CollisionType, Vec<usize>> = HashMap::new();
+    for (collision_type, ids) in in_intersection.iter() {
+        if ids.contains(&car.id) {
+            own.insert(*collision_type, vec![car.id]);
+        }
+    }
+    move_car(car, delta_time, &mut own, junction);
+    for (collision_type, ids) in own {
+        let inside = in_intersection.entry(collision_type).or_default();
+        for id in ids {
+            if !inside.contains(&id) {
+                inside.push(id);
+            }
+        }
+    }
+}
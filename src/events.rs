@@ -0,0 +1,172 @@
+use crate::types::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+// What happened, serialized as the "event" field of a log line
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum EventKind {
+    Spawned { direction: Direction, route: Route, x: f32, y: f32 },
+    SpawnBlocked { direction: Direction, route: Route },
+    SpawnCooldown { remaining: f32 }, // seconds left before the keyboard can spawn again
+    EnteredIntersection,
+    YieldedTo { other: usize },
+    WonPriority { over: usize },
+    ReferencePointClaimed { x: usize, y: usize },
+    Exited { travel_time: f32 },
+    Crash { other: usize },
+    Released { reason: String }, // let go by the deadlock detector
+    Unresolved { reason: String }, // in a deadlock no car can be let go from
+}
+
+impl EventKind {
+    // Second car of the event, if it involves two
+    pub fn other_car(&self) -> Option<usize> {
+        match self {
+            EventKind::YieldedTo { other } | EventKind::Crash { other } => Some(*other),
+            EventKind::WonPriority { over } => Some(*over),
+            _ => None,
+        }
+    }
+
+    // Worth a line on the console when the game is verbose, decisions made every step are not
+    pub fn is_notice(&self) -> bool {
+        matches!(
+            self,
+            EventKind::Spawned { .. }
+                | EventKind::SpawnBlocked { .. }
+                | EventKind::SpawnCooldown { .. }
+                | EventKind::Crash { .. }
+                | EventKind::Released { .. }
+                | EventKind::Unresolved { .. }
+        )
+    }
+}
+
+// One line of the log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SimEvent {
+    pub time: f32, // simulated seconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub car: Option<usize>,
+    #[serde(flatten)]
+    pub kind: EventKind,
+}
+
+impl SimEvent {
+    pub fn involves(&self, car_id: usize) -> bool {
+        self.car == Some(car_id) || self.kind.other_car() == Some(car_id)
+    }
+}
+
+impl fmt::Display for SimEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let car = self.car.unwrap_or(0);
+        match &self.kind {
+            EventKind::Spawned { x, y, .. } => write!(f, "Spawned car {} at ({}, {})", car, x, y),
+            EventKind::SpawnBlocked { direction, route } => {
+                write!(f, "Spawn blocked due to collision ({:?} {:?})", direction, route)
+            }
+            EventKind::SpawnCooldown { remaining } => {
+                write!(f, "Spawn on cooldown, please wait {:.1} more seconds", remaining)
+            }
+            EventKind::EnteredIntersection => write!(f, "Car {} entered the intersection", car),
+            EventKind::YieldedTo { other } => write!(f, "Car {} yields to car {}", car, other),
+            EventKind::WonPriority { over } => write!(f, "Car {} has priority over car {}", car, over),
+            EventKind::ReferencePointClaimed { x, y } => write!(f, "Car {} claimed reference point ({}, {})", car, x, y),
+            EventKind::Exited { travel_time } => write!(f, "Car {} left after {:.2} s", car, travel_time),
+            EventKind::Crash { other } => write!(f, "Crash between car {} and car {}", car, other),
+            EventKind::Released { reason } => write!(f, "Deadlock: {}, releasing car {}", reason, car),
+            EventKind::Unresolved { reason } => write!(f, "Deadlock: {}, no car can be released", reason),
+        }
+    }
+}
+
+// Every decision of a run in order, kept in memory and written as JSON lines at the end.
+// Events are only kept when something will read them, otherwise they are just passed on.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EventLog {
+    pub now: f32, // sim time stamped on new events, the game moves it forward every step
+    pub events: Vec<SimEvent>,
+    pub record: bool, // keep events, set when there is a file to write them to
+    pub cars: Option<HashSet<usize>>, // only keep events involving these cars
+    yielding_to: HashMap<usize, usize>, // car id -> the car it yields to right now
+}
+
+impl EventLog {
+    pub fn new() -> Self {
+        EventLog::default()
+    }
+
+    pub fn recording() -> Self {
+        EventLog {
+            record: true,
+            ..EventLog::default()
+        }
+    }
+
+    pub fn for_cars(cars: &[usize]) -> Self {
+        EventLog {
+            record: true,
+            cars: Some(cars.iter().copied().collect()),
+            ..EventLog::default()
+        }
+    }
+
+    // Returns the event if it passed the car filter, whether it was kept or not
+    pub fn log(&mut self, car: Option<usize>, kind: EventKind) -> Option<SimEvent> {
+        let event = SimEvent { time: self.now, car, kind };
+        let kept = match &self.cars {
+            Some(cars) => cars.iter().any(|&id| event.involves(id)),
+            None => true,
+        };
+        if !kept {
+            return None;
+        }
+        if self.record {
+            self.events.push(event.clone());
+        }
+        Some(event)
+    }
+
+    // Yielding goes on for many steps, only the start of a wait on a new car is logged
+    pub fn yielding(&mut self, car_id: usize, other: Option<usize>) {
+        match other {
+            Some(other) => {
+                if self.yielding_to.insert(car_id, other) != Some(other) {
+                    self.log(Some(car_id), EventKind::YieldedTo { other });
+                }
+            }
+            None => {
+                self.yielding_to.remove(&car_id);
+            }
+        }
+    }
+
+    pub fn car_left(&mut self, car_id: usize) {
+        self.yielding_to.remove(&car_id);
+    }
+
+    pub fn for_car(&self, car_id: usize) -> impl Iterator<Item = &SimEvent> {
+        self.events.iter().filter(move |event| event.involves(car_id))
+    }
+
+    pub fn to_json_lines(&self) -> Result<String, String> {
+        let mut out = String::new();
+        for event in &self.events {
+            out.push_str(&serde_json::to_string(event).map_err(|e| e.to_string())?);
+            out.push('\n');
+        }
+        Ok(out)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).map_err(|e| format!("Could not create {}: {}", dir.display(), e))?;
+        }
+        fs::write(path, self.to_json_lines()?).map_err(|e| format!("Could not write {}: {}", path.display(), e))
+    }
+}
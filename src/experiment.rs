@@ -0,0 +1,211 @@
+use crate::collision::Manager;
+use crate::export::*;
+use crate::game::Game;
+use crate::scenario::Scenario;
+use crate::types::Junction;
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+
+pub const BATCH_USAGE: &str = "\
+Usage: smart-road batch <experiment.json> [--output <dir>] [--threads <n>]";
+
+// Every combination of junctions x managers x demand levels x seeds is run once. The
+// roundabout has no manager, it runs once per demand level and seed.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Experiment {
+    pub name: String,
+    pub duration: f32, // simulated seconds per run
+    pub managers: Vec<String>,
+    #[serde(default = "cross_only")]
+    pub junctions: Vec<Junction>,
+    pub demand: Vec<f32>, // cars per minute on every approach
+    pub seeds: Vec<u64>,
+    #[serde(default)]
+    pub scenario: Option<PathBuf>, // base scenario, its rates are replaced by the demand level
+}
+
+fn cross_only() -> Vec<Junction> {
+    vec![Junction::Cross]
+}
+
+impl Experiment {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Could not read experiment {}: {}", path.display(), e))?;
+        let mut experiment: Experiment = serde_json::from_str(&text)
+            .map_err(|e| format!("Invalid experiment {}: {}", path.display(), e))?;
+        experiment.validate().map_err(|e| format!("Invalid experiment {}: {}", path.display(), e))?;
+
+        // Base scenario paths are relative to the experiment file
+        if let (Some(scenario), Some(dir)) = (&experiment.scenario, path.parent()) {
+            experiment.scenario = Some(dir.join(scenario));
+        }
+        Ok(experiment)
+    }
+
+    // An empty list or a run of no time would give an empty or meaningless table
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.duration > 0.0 && self.duration.is_finite()) {
+            return Err(format!("Duration {} is not a time above 0", self.duration));
+        }
+        for (list, empty) in [
+            ("managers", self.managers.is_empty()),
+            ("junctions", self.junctions.is_empty()),
+            ("demand", self.demand.is_empty()),
+            ("seeds", self.seeds.is_empty()),
+        ] {
+            if empty {
+                return Err(format!("\"{}\" needs at least one entry", list));
+            }
+        }
+        if let Some(rate) = self.demand.iter().find(|&&rate| !(rate >= 0.0 && rate.is_finite())) {
+            return Err(format!("Demand {} is not a rate of 0 or more", rate));
+        }
+        Ok(())
+    }
+
+    pub fn runs(&self) -> Result<Vec<Run>, String> {
+        let managers = self.managers.iter().map(|name| Manager::parse(name)).collect::<Result<Vec<_>, _>>()?;
+        let mut runs = Vec::new();
+        for &junction in &self.junctions {
+            let managers = match junction {
+                Junction::Cross => &managers[..],
+                Junction::Roundabout => &managers[..1],
+            };
+            for &manager in managers {
+                for &demand in &self.demand {
+                    for &seed in &self.seeds {
+                        runs.push(Run { manager, junction, demand, seed });
+                    }
+                }
+            }
+        }
+        Ok(runs)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Run {
+    pub manager: Manager,
+    pub junction: Junction,
+    pub demand: f32,
+    pub seed: u64,
+}
+
+impl Run {
+    // The base scenario with this run's demand and junction, checked like a scenario file
+    pub fn scenario(&self, base: &Scenario, duration: f32) -> Result<Scenario, String> {
+        let mut scenario = base.clone();
+        scenario.set_rate(self.demand);
+        scenario.duration = Some(duration);
+        scenario.junction = self.junction;
+        scenario
+            .prepare()
+            .map_err(|e| format!("{} run at demand {}: {}", self.junction.name(), self.demand, e))?;
+        Ok(scenario)
+    }
+
+    pub fn execute(&self, base: &Scenario, duration: f32) -> Result<RunSummary, String> {
+        let mut game = Game::with_seed(self.seed);
+        game.manager = self.manager;
+        game.scenario = self.scenario(base, duration)?;
+        game.verbose = false;
+        game.run_to_end()?;
+
+        Ok(RunSummary::new(&game.run_info(), &game.stats))
+    }
+}
+
+pub fn run_batch(args: &[String]) -> Result<(), String> {
+    let mut experiment_path = None;
+    let mut output = PathBuf::from("results");
+    let mut threads = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--output" => output = PathBuf::from(args.next().ok_or("Missing value for --output")?),
+            "--threads" => {
+                threads = args
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .filter(|&n| n > 0)
+                    .ok_or("--threads needs a positive number")?;
+            }
+            _ if experiment_path.is_none() && !arg.starts_with("--") => experiment_path = Some(PathBuf::from(arg)),
+            _ => return Err(format!("Unknown argument '{}'\n\n{}", arg, BATCH_USAGE)),
+        }
+    }
+    let experiment_path = experiment_path.ok_or_else(|| BATCH_USAGE.to_string())?;
+
+    let experiment = Experiment::load(&experiment_path)?;
+    let base = match &experiment.scenario {
+        Some(path) => Scenario::load(path)?,
+        None => Scenario { name: experiment.name.clone(), ..Scenario::default() },
+    };
+    let runs = experiment.runs()?;
+    // A run that can't start fails the batch before any of them do
+    for run in &runs {
+        run.scenario(&base, experiment.duration)?;
+    }
+    println!("Running {} simulations of {} s on {} threads", runs.len(), experiment.duration, threads);
+
+    let results = run_all(&runs, &base, experiment.duration, threads)?;
+
+    let mut table = format!("demand,{}\n", RunSummary::csv_header());
+    for (run, summary) in runs.iter().zip(&results) {
+        table.push_str(&format!("{},{}\n", run.demand, summary.csv_row()));
+    }
+
+    fs::create_dir_all(&output).map_err(|e| format!("Could not create {}: {}", output.display(), e))?;
+    let path = output.join(format!("{}_results.csv", experiment.name));
+    write_file(&path, &table)?;
+    println!("Wrote {}", path.display());
+    Ok(())
+}
+
+// Runs are independent, so they are shared between worker threads
+fn run_all(runs: &[Run], base: &Scenario, duration: f32, threads: usize) -> Result<Vec<RunSummary>, String> {
+    let next = Mutex::new(0);
+    let results = Mutex::new(vec![None; runs.len()]);
+
+    thread::scope(|scope| {
+        for _ in 0..threads.min(runs.len()) {
+            scope.spawn(|| loop {
+                let i = {
+                    let mut next = next.lock().unwrap();
+                    *next += 1;
+                    *next - 1
+                };
+                let Some(run) = runs.get(i) else {
+                    break;
+                };
+
+                let summary = match run.execute(base, duration) {
+                    Ok(summary) => summary,
+                    Err(e) => {
+                        results.lock().unwrap()[i] = Some(Err(e));
+                        continue;
+                    }
+                };
+                println!(
+                    "[{}/{}] {} demand {} seed {}: {} cars, mean delay {:.2} s",
+                    i + 1,
+                    runs.len(),
+                    summary.manager,
+                    run.demand,
+                    run.seed,
+                    summary.cars_passed,
+                    summary.mean_delay,
+                );
+                results.lock().unwrap()[i] = Some(Ok(summary));
+            });
+        }
+    });
+
+    results.into_inner().unwrap().into_iter().flatten().collect()
+}
@@ -0,0 +1,345 @@
+use crate::lanes::MAX_LANES;
+use crate::safety::ConflictEvent;
+use crate::stats::*;
+use crate::types::*;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+    Both,
+}
+
+impl ExportFormat {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name.to_lowercase().as_str() {
+            "csv" => Ok(ExportFormat::Csv),
+            "json" => Ok(ExportFormat::Json),
+            "both" => Ok(ExportFormat::Both),
+            _ => Err(format!("Unknown export format '{}' (expected csv, json or both)", name)),
+        }
+    }
+}
+
+// What produced the numbers, written next to every export
+#[derive(Debug, Clone, Serialize)]
+pub struct RunInfo {
+    pub seed: u64,
+    pub scenario: String,
+    pub manager: String,
+    pub duration: f32, // simulated seconds
+}
+
+impl RunInfo {
+    // File name prefix so runs don't overwrite each other
+    pub fn file_prefix(&self) -> String {
+        format!("{}-{}-{}", self.scenario, self.manager, self.seed)
+    }
+}
+
+// End-of-run aggregates, one flat row per run
+#[derive(Debug, Clone, Serialize)]
+pub struct RunSummary {
+    pub seed: u64,
+    pub scenario: String,
+    pub manager: String,
+    pub duration: f32,
+    pub total_cars: usize,
+    pub cars_passed: usize,
+    pub throughput_per_hour: f32,
+    pub max_velocity: f32,
+    pub min_velocity: f32,
+    pub max_travel_time: f32,
+    pub min_travel_time: f32,
+    pub mean_travel_time: f32,
+    pub mean_delay: f32,
+    pub median_delay: f32,
+    pub p95_delay: f32,
+    pub average_wait: f32,
+    pub max_wait: f32,
+    pub close_calls: usize,
+    pub min_ttc: f32,
+    pub mean_min_ttc: f32,
+    pub mean_pet: f32,
+    pub yield_time: f32,
+    pub crashes: usize,
+    pub deadlocks: usize,
+    pub unresolved_deadlocks: usize,
+    pub pedestrians_crossed: usize,
+    pub mean_pedestrian_wait: f32,
+    pub max_pedestrian_wait: f32,
+    pub cyclists_through: usize,
+    pub mean_cyclist_delay: f32,
+    pub right_hooks: usize,
+    pub autonomous_passed: usize,
+    pub mean_autonomous_delay: f32,
+    pub human_passed: usize,
+    pub mean_human_delay: f32,
+    pub human_close_calls: usize,
+}
+
+impl RunSummary {
+    pub fn new(info: &RunInfo, stats: &Stats) -> Self {
+        let delay = stats.delay_distribution();
+        let ttc = stats.ttc_distribution();
+        let pedestrian_wait = stats.pedestrian_wait_distribution();
+        let cyclist_delay = stats.cyclist_delay_distribution();
+        let autonomous_delay = stats.driver_delay_distribution(Driver::Autonomous);
+        let human_delay = stats.driver_delay_distribution(Driver::Human);
+        RunSummary {
+            seed: info.seed,
+            scenario: info.scenario.clone(),
+            manager: info.manager.clone(),
+            duration: info.duration,
+            total_cars: stats.max_number_cars,
+            cars_passed: stats.cars_passed,
+            throughput_per_hour: stats.throughput_per_hour(info.duration),
+            max_velocity: stats.max_velocity,
+            min_velocity: stats.min_velocity,
+            max_travel_time: stats.max_time.as_secs_f32(),
+            min_travel_time: if stats.trips.is_empty() { 0.0 } else { stats.min_time.as_secs_f32() },
+            mean_travel_time: stats.travel_time_distribution().mean,
+            mean_delay: delay.mean,
+            median_delay: delay.median,
+            p95_delay: delay.p95,
+            average_wait: stats.average_wait().as_secs_f32(),
+            max_wait: stats.max_wait.as_secs_f32(),
+            close_calls: stats.close_call,
+            min_ttc: ttc.min,
+            mean_min_ttc: ttc.mean,
+            mean_pet: stats.pet_distribution().mean,
+            yield_time: stats.yield_time,
+            crashes: stats.crashes,
+            deadlocks: stats.deadlocks,
+            unresolved_deadlocks: stats.unresolved_deadlocks,
+            pedestrians_crossed: pedestrian_wait.count,
+            mean_pedestrian_wait: pedestrian_wait.mean,
+            max_pedestrian_wait: pedestrian_wait.max,
+            cyclists_through: cyclist_delay.count,
+            mean_cyclist_delay: cyclist_delay.mean,
+            right_hooks: stats.right_hooks,
+            autonomous_passed: autonomous_delay.count,
+            mean_autonomous_delay: autonomous_delay.mean,
+            human_passed: human_delay.count,
+            mean_human_delay: human_delay.mean,
+            human_close_calls: stats.human_close_calls,
+        }
+    }
+
+    pub fn csv_header() -> &'static str {
+        "seed,scenario,manager,duration,total_cars,cars_passed,throughput_per_hour,max_velocity,min_velocity,\
+max_travel_time,min_travel_time,mean_travel_time,mean_delay,median_delay,p95_delay,average_wait,max_wait,close_calls,min_ttc,mean_min_ttc,mean_pet,yield_time,crashes,deadlocks,\
+unresolved_deadlocks,pedestrians_crossed,mean_pedestrian_wait,max_pedestrian_wait,cyclists_through,mean_cyclist_delay,right_hooks,\
+autonomous_passed,mean_autonomous_delay,human_passed,mean_human_delay,human_close_calls"
+    }
+
+    pub fn csv_row(&self) -> String {
+        format!(
+            "{},{},{},{:.3},{},{},{:.1},{:.3},{:.3},{:.3},{:.3},{:.3},{:.3},{:.3},{:.3},{:.3},{:.3},{},{:.3},{:.3},{:.3},{:.3},{},{},{},{},{:.3},{:.3},{},{:.3},{},{},{:.3},{},{:.3},{}",
+            self.seed,
+            csv_field(&self.scenario),
+            csv_field(&self.manager),
+            self.duration,
+            self.total_cars,
+            self.cars_passed,
+            self.throughput_per_hour,
+            self.max_velocity,
+            self.min_velocity,
+            self.max_travel_time,
+            self.min_travel_time,
+            self.mean_travel_time,
+            self.mean_delay,
+            self.median_delay,
+            self.p95_delay,
+            self.average_wait,
+            self.max_wait,
+            self.close_calls,
+            self.min_ttc,
+            self.mean_min_ttc,
+            self.mean_pet,
+            self.yield_time,
+            self.crashes,
+            self.deadlocks,
+            self.unresolved_deadlocks,
+            self.pedestrians_crossed,
+            self.mean_pedestrian_wait,
+            self.max_pedestrian_wait,
+            self.cyclists_through,
+            self.mean_cyclist_delay,
+            self.right_hooks,
+            self.autonomous_passed,
+            self.mean_autonomous_delay,
+            self.human_passed,
+            self.mean_human_delay,
+            self.human_close_calls,
+        )
+    }
+}
+
+#[derive(Serialize)]
+struct JsonExport<'a> {
+    run: &'a RunInfo,
+    summary: RunSummary,
+    movements: Vec<MovementSummary>,
+    trips: &'a [TripRecord],
+    conflicts: &'a [ConflictEvent],
+    timeseries: &'a [Sample],
+}
+
+// Write the requested files into `dir` and return their paths
+pub fn export_stats(dir: &Path, format: ExportFormat, info: &RunInfo, stats: &Stats) -> Result<Vec<PathBuf>, String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Could not create {}: {}", dir.display(), e))?;
+
+    let mut written = Vec::new();
+    if format != ExportFormat::Json {
+        written.extend(export_csv(dir, info, stats)?);
+    }
+    if format != ExportFormat::Csv {
+        written.push(export_json(dir, info, stats)?);
+    }
+    Ok(written)
+}
+
+pub fn export_csv(dir: &Path, info: &RunInfo, stats: &Stats) -> Result<Vec<PathBuf>, String> {
+    let prefix = info.file_prefix();
+    let run_columns = format!("{},{},{}", info.seed, csv_field(&info.scenario), csv_field(&info.manager));
+
+    let summary_path = dir.join(format!("{}_summary.csv", prefix));
+    let summary = format!("{}\n{}\n", RunSummary::csv_header(), RunSummary::new(info, stats).csv_row());
+    write_file(&summary_path, &summary)?;
+
+    let movements_path = dir.join(format!("{}_movements.csv", prefix));
+    let mut movements = String::from(
+        "seed,scenario,manager,direction,route,count,throughput_per_hour,mean_travel_time,median_travel_time,p95_travel_time,\
+mean_delay,median_delay,p95_delay,max_delay,mean_stopped_time,delay_histogram_min,delay_histogram_bin_width,delay_histogram\n",
+    );
+    for m in stats.movement_summaries(info.duration) {
+        let histogram: Vec<String> = m.delay_histogram.counts.iter().map(|c| c.to_string()).collect();
+        movements.push_str(&format!(
+            "{},{:?},{:?},{},{:.1},{:.3},{:.3},{:.3},{:.3},{:.3},{:.3},{:.3},{:.3},{:.3},{:.3},{}\n",
+            run_columns,
+            m.direction,
+            m.route,
+            m.count,
+            m.throughput_per_hour,
+            m.travel_time.mean,
+            m.travel_time.median,
+            m.travel_time.p95,
+            m.delay.mean,
+            m.delay.median,
+            m.delay.p95,
+            m.delay.max,
+            m.stopped_time.mean,
+            m.delay_histogram.min,
+            m.delay_histogram.bin_width,
+            histogram.join(";"),
+        ));
+    }
+    write_file(&movements_path, &movements)?;
+
+    let trips_path = dir.join(format!("{}_trips.csv", prefix));
+    let mut trips = String::from(
+        "seed,scenario,manager,id,direction,route,spawn_time,entry_time,exit_time,travel_time,stopped_time,free_flow_time,delay,distance,stops,driver\n",
+    );
+    for t in &stats.trips {
+        trips.push_str(&format!(
+            "{},{},{:?},{:?},{:.3},{},{:.3},{:.3},{:.3},{:.3},{:.3},{:.1},{},{}\n",
+            run_columns,
+            t.id,
+            t.direction,
+            t.route,
+            t.spawn_time,
+            t.entry_time.map(|e| format!("{:.3}", e)).unwrap_or_default(),
+            t.exit_time,
+            t.travel_time(),
+            t.stopped_time,
+            t.free_flow_time,
+            t.delay(),
+            t.distance,
+            t.stops,
+            t.driver.name(),
+        ));
+    }
+    write_file(&trips_path, &trips)?;
+
+    let conflicts_path = dir.join(format!("{}_conflicts.csv", prefix));
+    let mut conflicts = String::from("seed,scenario,manager,car_a,car_b,time,min_ttc,pet\n");
+    for c in &stats.conflicts {
+        conflicts.push_str(&format!(
+            "{},{},{},{:.3},{:.3},{}\n",
+            run_columns,
+            c.car_a,
+            c.car_b,
+            c.time,
+            c.min_ttc,
+            c.pet.map(|p| format!("{:.3}", p)).unwrap_or_default(),
+        ));
+    }
+    write_file(&conflicts_path, &conflicts)?;
+
+    let timeseries_path = dir.join(format!("{}_timeseries.csv", prefix));
+    write_file(&timeseries_path, &timeseries_csv(&run_columns, &stats.samples))?;
+
+    Ok(vec![summary_path, movements_path, trips_path, conflicts_path, timeseries_path])
+}
+
+// One row per sample, lanes spread out as <direction>_lane<n>_cars/_queue columns
+fn timeseries_csv(run_columns: &str, samples: &[Sample]) -> String {
+    let mut csv = String::from("seed,scenario,manager,time,cars,intersection_occupancy,mean_speed,close_calls");
+    for direction in Direction::ALL {
+        for lane in 0..MAX_LANES {
+            let lane = format!("{:?}_lane{}", direction, lane).to_lowercase();
+            csv.push_str(&format!(",{}_cars,{}_queue", lane, lane));
+        }
+    }
+    csv.push('\n');
+
+    for sample in samples {
+        csv.push_str(&format!(
+            "{},{:.3},{},{},{:.2},{}",
+            run_columns,
+            sample.time,
+            sample.cars,
+            sample.intersection_occupancy,
+            sample.mean_speed,
+            sample.close_calls,
+        ));
+        for lane in &sample.lanes {
+            csv.push_str(&format!(",{},{}", lane.cars, lane.queue));
+        }
+        csv.push('\n');
+    }
+    csv
+}
+
+pub fn export_json(dir: &Path, info: &RunInfo, stats: &Stats) -> Result<PathBuf, String> {
+    let export = JsonExport {
+        run: info,
+        summary: RunSummary::new(info, stats),
+        movements: stats.movement_summaries(info.duration),
+        trips: &stats.trips,
+        conflicts: &stats.conflicts,
+        timeseries: &stats.samples,
+    };
+    let json = serde_json::to_string_pretty(&export).map_err(|e| e.to_string())?;
+
+    let path = dir.join(format!("{}.json", info.file_prefix()));
+    write_file(&path, &json)?;
+    Ok(path)
+}
+
+pub fn write_file(path: &Path, contents: &str) -> Result<(), String> {
+    fs::write(path, contents).map_err(|e| format!("Could not write {}: {}", path.display(), e))
+}
+
+// Quote a CSV field if it would otherwise break the row
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
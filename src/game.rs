@@ -1,48 +1,102 @@
 use crate::types::*;
 use crate::collision::*;
+use crate::crosswalk::*;
+use crate::deadlock::*;
+use crate::driver::*;
+use crate::events::*;
+use crate::lanes::*;
 use crate::movement::*;
+use crate::export::*;
 use crate::renderer::*;
+use crate::roundabout::give_way_to;
+use crate::safety::*;
+use crate::scenario::*;
+use crate::snapshot::*;
+use crate::spatial::*;
+use crate::stats::*;
 use sdl2::event::Event;
 use sdl2::keyboard::Keycode;
 use sdl2::render::Canvas;
 use sdl2::video::Window;
 use sdl2::ttf::Font;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
-use std::f32::consts::PI;
-use fastrand;
+
+// Fixed time step for headless runs
+pub const HEADLESS_STEP: f32 = 1.0 / 60.0;
+
+// Furthest a car may travel between two collision checks, well under a car width
+pub const MAX_STEP_DISTANCE: f32 = 8.0;
+
+// Speed `step` places up or down the list of multipliers
+pub fn next_speed_multiplier(current: f32, step: i32) -> f32 {
+    let current = SPEED_MULTIPLIERS
+        .iter()
+        .position(|&m| m == current)
+        .unwrap_or(2) as i32;
+    let next = (current + step).clamp(0, SPEED_MULTIPLIERS.len() as i32 - 1);
+    SPEED_MULTIPLIERS[next as usize]
+}
 
 // Main game structure
+#[derive(Clone)]
 pub struct Game {
     pub app_state: AppState,
     pub stats: Stats,
     pub start_time: Instant,
     pub cars: Vec<Car>,
     pub next_car_id: usize,
-    pub spawn_coords: HashMap<(Route, Direction), (f32, f32)>,
+    pub pedestrians: Vec<Pedestrian>,
+    pub next_pedestrian_id: usize,
+    pub free_flow_times: &'static FreeFlowTimes, // by the lane the car left from
     pub priority_map: HashMap<(usize, usize), usize>,
     pub priority_ref: HashMap<(usize, usize), usize>, //PEAK LOGIC HONESTLY
     pub in_intersection: HashMap<CollisionType, Vec<usize>>,
     pub last_spawn_time: Instant,
     pub spawn_cooldown: Duration,
+    pub sim_time: f32, // simulated seconds since start
+    pub speed_multiplier: f32,
+    pub show_hud: bool,
+    pub fps: f32,
+    pub crash_pairs: HashSet<(usize, usize)>, // pairs already counted as a crash
+    pub grid: SpatialGrid, // indices into `cars`, rebuilt whenever cars move or leave
+    pub ttc_threshold: f32, // seconds, a conflicting pair under this is a close call
+    pub open_conflicts: HashMap<(usize, usize), ConflictEvent>,
+    pub deadlocks: DeadlockDetector,
+    pub events: EventLog,
+    pub events_path: Option<PathBuf>, // JSON lines file the event log is written to at the end
+    pub snapshot_path: PathBuf, // where F5 saves and F9 loads
+    pub snapshot_at: Option<f32>, // sim time to save a snapshot at without a key press
+    pub departures: Option<Vec<Car>>, // cars that drove off the map, collected when a network passes them on
+    pub seed: u64,
+    pub rng: fastrand::Rng,
+    pub scenario: Scenario,
+    pub traffic: Traffic,
+    pub manager: Manager,
+    pub export_dir: PathBuf,
+    pub export_format: ExportFormat,
+    pub auto_export: bool, // export as soon as the stats screen opens
+    pub sample_interval: f32,
+    pub next_sample_time: f32,
+    pub stats_page: usize,
+    pub verbose: bool, // print spawn/crash events and the end summary
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Game::new()
+    }
 }
 
 impl Game {
     pub fn new() -> Self {
-        let mut spawn_coords = HashMap::new();
-        
-        spawn_coords.insert((Route::Right, Direction::North), (655.0, 0.0));
-        spawn_coords.insert((Route::Right, Direction::West), (1024.0, 435.0));
-        spawn_coords.insert((Route::Right, Direction::East), (0.0, 230.0));
-        spawn_coords.insert((Route::Right, Direction::South), (360.0, 682.0));
-        spawn_coords.insert((Route::Straight, Direction::North), (595.0, 45.0));
-        spawn_coords.insert((Route::Straight, Direction::West), (978.0, 390.0));
-        spawn_coords.insert((Route::Straight, Direction::East), (0.0, 278.0));
-        spawn_coords.insert((Route::Straight, Direction::South), (420.0, 682.0));
-        spawn_coords.insert((Route::Left, Direction::North), (535.0, 45.0));
-        spawn_coords.insert((Route::Left, Direction::West), (978.0, 350.0));
-        spawn_coords.insert((Route::Left, Direction::East), (0.0, 315.0));
-        spawn_coords.insert((Route::Left, Direction::South), (480.0, 682.0));
+        Game::with_seed(fastrand::u64(..))
+    }
+    
+    // Same seed and same inputs give the same run
+    pub fn with_seed(seed: u64) -> Self {
+        let free_flow_times = free_flow_times();
         
         let now = Instant::now();
         Game {
@@ -51,12 +105,40 @@ impl Game {
             start_time: now,
             cars: Vec::new(),
             next_car_id: 1,
-            spawn_coords,
+            pedestrians: Vec::new(),
+            next_pedestrian_id: 1,
+            free_flow_times,
             priority_map: HashMap::new(),
             priority_ref: HashMap::new(),
             in_intersection: HashMap::new(),
             last_spawn_time: now,
-            spawn_cooldown: Duration::from_millis(0.8), // 0.8 second cooldown between spawns
+            spawn_cooldown: Duration::from_millis(800), // 0.8 second cooldown between spawns
+            sim_time: 0.0,
+            speed_multiplier: 1.0,
+            show_hud: true,
+            fps: 0.0,
+            crash_pairs: HashSet::new(),
+            grid: SpatialGrid::new(CELL_SIZE),
+            ttc_threshold: TTC_THRESHOLD,
+            open_conflicts: HashMap::new(),
+            deadlocks: DeadlockDetector::new(),
+            events: EventLog::new(),
+            events_path: None,
+            snapshot_path: PathBuf::from("snapshots/quicksave.json"),
+            snapshot_at: None,
+            departures: None,
+            seed,
+            rng: fastrand::Rng::with_seed(seed),
+            scenario: Scenario::default(),
+            traffic: Traffic::default(),
+            manager: Manager::ReferencePoint,
+            export_dir: PathBuf::from("results"),
+            export_format: ExportFormat::Both,
+            auto_export: false,
+            sample_interval: SAMPLE_INTERVAL,
+            next_sample_time: 0.0,
+            stats_page: 0,
+            verbose: true,
         }
     }
 
@@ -70,7 +152,7 @@ impl Game {
                 } => {
                     match self.app_state {
                         AppState::Running => {
-                            self.app_state = AppState::StatsDisplay;
+                            self.finish();
                         }
                         AppState::StatsDisplay => {
                             self.app_state = AppState::Exit;
@@ -79,13 +161,67 @@ impl Game {
                     }
                 }
                 Event::KeyDown {
-                    keycode: Some(keycode),
+                    keycode: Some(Keycode::E),
+                    ..
+                } if self.app_state == AppState::StatsDisplay => {
+                    self.export();
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::Right),
+                    ..
+                } if self.app_state == AppState::StatsDisplay => {
+                    self.stats_page = (self.stats_page + 1) % STATS_PAGES;
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::Left),
+                    ..
+                } if self.app_state == AppState::StatsDisplay => {
+                    self.stats_page = (self.stats_page + STATS_PAGES - 1) % STATS_PAGES;
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::F5),
+                    ..
+                } if self.app_state == AppState::Running => {
+                    let path = self.snapshot_path.clone();
+                    match self.save_snapshot(&path) {
+                        Ok(()) => println!("Saved snapshot to {}", path.display()),
+                        Err(e) => println!("Snapshot failed: {}", e),
+                    }
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::F9),
                     ..
                 } => {
-                    if self.app_state == AppState::Running {
-                        self.handle_car_spawn_input(keycode);
+                    let path = self.snapshot_path.clone();
+                    match self.load_snapshot(&path) {
+                        Ok(()) => println!("Loaded snapshot {} at {:.1} s", path.display(), self.sim_time),
+                        Err(e) => println!("Snapshot failed: {}", e),
                     }
                 }
+                Event::KeyDown {
+                    keycode: Some(Keycode::H),
+                    ..
+                } => {
+                    self.show_hud = !self.show_hud;
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::Equals | Keycode::KpPlus),
+                    ..
+                } => {
+                    self.change_speed_multiplier(1);
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::Minus | Keycode::KpMinus),
+                    ..
+                } => {
+                    self.change_speed_multiplier(-1);
+                }
+                Event::KeyDown {
+                    keycode: Some(keycode),
+                    ..
+                } if self.app_state == AppState::Running => {
+                    self.handle_car_spawn_input(keycode);
+                }
                 _ => {}
             }
         }
@@ -95,45 +231,52 @@ impl Game {
     }
 
     pub fn update(&mut self, delta_time: f32) {
+        // FPS is measured on real time, smoothed so the HUD is readable
+        if delta_time > 0.0 {
+            let current_fps = 1.0 / delta_time;
+            self.fps = if self.fps == 0.0 { current_fps } else { self.fps * 0.9 + current_fps * 0.1 };
+        }
+        
         match self.app_state {
             AppState::Running => {
-                // Update cars with collision detection and route-specific movement
-                let car_data: Vec<(usize, f32, f32, Vec<CollisionType>, bool)> = self.cars
-                    .iter()
-                    .map(|car| (car.id, car.x, car.y, car.collision_types.clone(), car.rotated))
-                    .collect();
+                let delta_time = delta_time * self.speed_multiplier;
+                self.sim_time += delta_time;
+                self.events.now = self.sim_time;
                 
-                let car_tracking = build_car_tracking(&car_data);
+                self.rebuild_grid();
+                self.spawn_scenario_cars();
+                self.spawn_pedestrians();
+                self.spawn_cyclists();
                 
-                for car in &mut self.cars {
-                    if car.moving {
-                        // Check collision before moving
-                        if !check_collision(
-                            &car_tracking,
-                            car.id,
-                            car.x,
-                            car.y,
-                            &car.collision_types,
-                            car.rotated,
-                            &mut self.priority_map,
-                            &mut self.priority_ref,
-                            &mut self.stats,
-                        ) {
-                            // Update speed stats
-                            car.max_speed = car.max_speed.max(car.speed);
-                            car.min_speed = car.min_speed.min(car.speed);
-                            
-                            // Update global speed stats
-                            self.stats.max_velocity = self.stats.max_velocity.max(car.speed);
-                            self.stats.min_velocity = 0.0; // Always 0 since cars stop
-                            
-                            // Move car based on route
-                            match car.route {
-                                Route::Straight => move_straight(car, delta_time, &mut self.in_intersection),
-                                Route::Right => move_right(car, delta_time, &mut self.in_intersection),
-                                Route::Left => move_left(car, delta_time, &mut self.in_intersection),
-                            }
-                        }
+                // Long frames are split so no car moves more than MAX_STEP_DISTANCE between
+                // collision checks, otherwise a fast car could jump through another one
+                let fastest = self.cars.iter().map(|car| car.speed).fold(0.0, f32::max);
+                let steps = ((fastest * delta_time / MAX_STEP_DISTANCE).ceil() as usize).max(1);
+                let step = delta_time / steps as f32;
+                let frame_start = self.sim_time - delta_time;
+                for i in 1..=steps {
+                    let now = frame_start + step * i as f32;
+                    self.events.now = now;
+                    self.move_cars(step, now);
+                    self.rebuild_grid();
+                    self.detect_crashes();
+                    measure_conflicts(&self.cars, self.ttc_threshold, now, &mut self.open_conflicts, &mut self.stats);
+                    self.take_samples(now);
+                }
+                
+                for deadlock in self.deadlocks.resolve(&self.cars, self.sim_time, &mut self.stats) {
+                    match deadlock.released {
+                        Some(car_id) => self.log(Some(car_id), EventKind::Released { reason: deadlock.reason }),
+                        None => self.log(Some(deadlock.car), EventKind::Unresolved { reason: deadlock.reason }),
+                    }
+                }
+                
+                if self.snapshot_at.is_some_and(|time| self.sim_time >= time) {
+                    self.snapshot_at = None;
+                    let path = self.snapshot_path.clone();
+                    match self.save_snapshot(&path) {
+                        Ok(()) => println!("Saved snapshot to {} at {:.1} s", path.display(), self.sim_time),
+                        Err(e) => println!("Snapshot failed: {}", e),
                     }
                 }
                 
@@ -141,29 +284,79 @@ impl Game {
                 let mut cars_to_remove = Vec::new();
                 for (i, car) in self.cars.iter().enumerate() {
                     if !car.moving {
+                        let travel_time = Duration::from_secs_f32(self.sim_time - car.spawn_time);
+                        let free_flow_time = self.free_flow_time(car.kind, &car.direction, &car.route, car.lane);
+                        let trip = TripRecord::from_car(car, self.sim_time, free_flow_time);
+                        cars_to_remove.push((i, car.id, car.collision_types[0], travel_time.as_secs_f32()));
+                        
+                        // Cyclists have their own trips and don't go on down network links
+                        if car.kind == VehicleKind::Bicycle {
+                            self.stats.cyclist_trips.push(trip);
+                            continue;
+                        }
+                        
                         // Update stats when car finishes journey
-                        let travel_time = car.spawn_time.elapsed();
                         self.stats.max_time = self.stats.max_time.max(travel_time);
                         if travel_time < self.stats.min_time {
                             self.stats.min_time = travel_time;
                         }
-                        cars_to_remove.push((i, car.id, car.collision_types[0]));
+                        self.stats.cars_passed += 1;
+                        self.stats.total_wait += Duration::from_secs_f32(car.wait_time);
+                        
+                        // Slowest car on average over its whole trip
+                        let average_speed = trip.average_speed();
+                        if self.stats.trips.is_empty() || average_speed < self.stats.min_velocity {
+                            self.stats.min_velocity = average_speed;
+                        }
+                        self.stats.trips.push(trip);
+                        if let Some(departures) = &mut self.departures {
+                            departures.push(car.clone());
+                        }
                     }
                 }
                 
                 // Remove cars in reverse order to maintain indices and clean up intersection
-                for &(i, car_id, collision_type) in cars_to_remove.iter().rev() {
+                for &(i, car_id, collision_type, travel_time) in cars_to_remove.iter().rev() {
                     self.cars.remove(i);
+                    self.log(Some(car_id), EventKind::Exited { travel_time });
                     
                     // Clean up priority maps
                     self.priority_map.retain(|&(id1, id2), _| id1 != car_id && id2 != car_id);
                     self.priority_ref.retain(|_, owner_id| *owner_id != car_id);
+                    self.crash_pairs.retain(|&(id1, id2)| id1 != car_id && id2 != car_id);
+                    car_left(car_id, &mut self.open_conflicts, &mut self.stats);
+                    self.deadlocks.car_left(car_id);
+                    self.events.car_left(car_id);
                     
                     // Remove from intersection
                     if let Some(cars_in_intersection) = self.in_intersection.get_mut(&collision_type) {
                         cars_in_intersection.retain(|&x| x != car_id);
                     }
                 }
+                if !cars_to_remove.is_empty() {
+                    self.rebuild_grid();
+                }
+                
+                // Pedestrians on the far side are done
+                for pedestrian in self.pedestrians.iter().filter(|p| !p.moving) {
+                    self.stats.pedestrian_waits.push(pedestrian.wait_time);
+                }
+                self.pedestrians.retain(|p| p.moving);
+                
+                // Turning cars out of the crossing free the gate. Like leaving the map this happens
+                // between steps, so the cars waiting longest go first.
+                for car in &self.cars {
+                    if matches!(car.route, Route::Left | Route::UTurn) && left_crossing(car) {
+                        if let Some(cars_in_intersection) = self.in_intersection.get_mut(&car.collision_types[0]) {
+                            cars_in_intersection.retain(|&x| x != car.id);
+                        }
+                    }
+                }
+                
+                // Last, so cars leaving in the final frame are in the exports and the event log
+                if self.scenario.duration.is_some_and(|duration| self.sim_time >= duration) {
+                    self.finish();
+                }
             }
             AppState::StatsDisplay => {
                 // Stats display is handled in render
@@ -172,25 +365,395 @@ impl Game {
         }
     }
 
-    pub fn render(&self, canvas: &mut Canvas<Window>, textures: &GameTextures, font: &Font) -> Result<(), String> {
+    pub fn render(&self, canvas: &mut Canvas<Window>, textures: &GameTextures, font: &Font, hud_font: &Font) -> Result<(), String> {
         match self.app_state {
             AppState::Running => {
-                render_game(canvas, textures, &self.cars)?;
+                render_game(canvas, textures, &self.cars, &self.pedestrians, &self.scenario)?;
+                if self.show_hud {
+                    render_hud(canvas, hud_font, &self.hud_lines())?;
+                }
+                canvas.present();
             }
             AppState::StatsDisplay => {
-                render_stats(canvas, font, &self.stats)?;
+                render_stats(canvas, font, hud_font, &self.stats, self.sim_time, self.stats_page)?;
             }
             _ => {}
         }
         Ok(())
     }
     
-    fn handle_car_spawn_input(&mut self, keycode: Keycode) {
+    // Run with a fixed time step until the scenario duration is reached. Without one nothing
+    // would ever end the run.
+    pub fn run_to_end(&mut self) -> Result<(), String> {
+        if self.scenario.duration.is_none() {
+            return Err("The scenario has no duration to run to".to_string());
+        }
+        while self.app_state == AppState::Running {
+            self.update(HEADLESS_STEP);
+        }
+        Ok(())
+    }
+    
+    // End of the run: show the statistics and export them if asked to
+    pub fn finish(&mut self) {
+        self.app_state = AppState::StatsDisplay;
+        close_all(&mut self.open_conflicts, &mut self.stats);
+        if self.verbose {
+            self.print_movement_summaries();
+        }
+        if self.auto_export {
+            self.export();
+        }
+        if let Some(path) = &self.events_path {
+            match self.events.write(path) {
+                Ok(()) => println!("Wrote {} events to {}", self.events.events.len(), path.display()),
+                Err(e) => println!("Event log failed: {}", e),
+            }
+        }
+    }
+    
+    // Record an event, notices are printed as well when the game is verbose
+    fn log(&mut self, car: Option<usize>, kind: EventKind) {
+        if let Some(event) = self.events.log(car, kind) {
+            if self.verbose && event.kind.is_notice() {
+                println!("{}", event);
+            }
+        }
+    }
+    
+    // Spawn the scenario's cars that are due, blocked ones retry next frame
+    fn spawn_scenario_cars(&mut self) {
+        let retries = self.traffic.backlog.len();
+        let due = self.traffic.due_cars(&self.scenario, self.sim_time, &mut self.rng);
+        for (i, (direction, route, lane)) in due.into_iter().enumerate() {
+            if !self.spawn_car_in_lane(direction.clone(), route.clone(), lane) {
+                // Only the first failed attempt is logged, the retries would flood the log
+                if i >= retries {
+                    self.log(None, EventKind::SpawnBlocked { direction: direction.clone(), route: route.clone() });
+                }
+                self.traffic.backlog.push_back((direction, route, lane));
+            }
+        }
+    }
+    
+    // People due on the crosswalks step up to the kerb
+    fn spawn_pedestrians(&mut self) {
+        for (leg, forward) in self.traffic.due_pedestrians(&self.scenario, self.sim_time, &mut self.rng) {
+            self.pedestrians.push(Pedestrian::new(self.next_pedestrian_id, leg, forward, self.sim_time));
+            self.next_pedestrian_id += 1;
+        }
+    }
+    
+    // Cyclists due on the bike lanes, one whose lane start is taken tries again next frame
+    fn spawn_cyclists(&mut self) {
+        for direction in self.traffic.due_cyclists(&self.scenario, self.sim_time, &mut self.rng) {
+            if !self.spawn_bicycle(direction.clone()) {
+                self.traffic.cyclist_backlog.push_back(direction);
+            }
+        }
+    }
+    
+    pub fn save_snapshot(&self, path: &Path) -> Result<(), String> {
+        Snapshot::take(self).save(path)
+    }
+    
+    // Carry on from a saved state, the window and export settings of this game are kept
+    pub fn load_snapshot(&mut self, path: &Path) -> Result<(), String> {
+        Snapshot::load(path)?.restore(self)?;
+        self.rebuild_grid();
+        Ok(())
+    }
+    
+    pub fn run_info(&self) -> RunInfo {
+        RunInfo {
+            seed: self.seed,
+            scenario: self.scenario.name.clone(),
+            manager: self.control_name().to_string(),
+            duration: self.sim_time,
+        }
+    }
+    
+    // What decides who goes first: the manager, or the give-way rule at a roundabout
+    pub fn control_name(&self) -> &'static str {
+        match self.scenario.junction {
+            Junction::Cross => self.manager.name(),
+            Junction::Roundabout => Junction::Roundabout.name(),
+        }
+    }
+    
+    // Seconds a lone car or cyclist takes through this game's junction
+    pub fn free_flow_time(&self, kind: VehicleKind, direction: &Direction, route: &Route, lane: usize) -> f32 {
+        self.free_flow_times
+            .get(&(self.scenario.junction, kind, direction.clone(), route.clone(), lane))
+            .copied()
+            .unwrap_or(0.0)
+    }
+    
+    pub fn export(&self) {
+        match export_stats(&self.export_dir, self.export_format, &self.run_info(), &self.stats) {
+            Ok(paths) => {
+                for path in paths {
+                    println!("Exported {}", path.display());
+                }
+            }
+            Err(e) => println!("Export failed: {}", e),
+        }
+    }
+    
+    fn change_speed_multiplier(&mut self, step: i32) {
+        self.speed_multiplier = next_speed_multiplier(self.speed_multiplier, step);
+    }
+    
+    // One collision check and move for every car, `now` is the sim time at the end of the step
+    fn move_cars(&mut self, delta_time: f32, now: f32) {
+        // Update cars with collision detection and route-specific movement
+        let car_tracking = build_car_tracking(&self.cars, &self.grid);
+        self.deadlocks.clear_waits();
+        move_pedestrians(&mut self.pedestrians, &self.cars, &mut self.deadlocks, delta_time);
+        
+        for i in 0..self.cars.len() {
+            if self.cars[i].moving {
+                let snapshot = MoveSnapshot::take(&self.cars[i], &self.in_intersection);
+                // People come first: the manager holds a car about to reach someone on a
+                // crosswalk before weighing it against other cars. Humans see them too.
+                let crossing = pedestrian_ahead(&self.cars[i], &self.pedestrians);
+                // Check collision before moving
+                let yields_to = match self.scenario.junction {
+                    _ if crossing.is_some() => None,
+                    Junction::Cross => {
+                        let car = &self.cars[i];
+                        // Human drivers look for a gap themselves instead of asking the manager
+                        let managed = if car.driver == Driver::Human {
+                            None
+                        } else {
+                            check_collision(
+                                &car_tracking,
+                                car,
+                                &mut self.priority_map,
+                                &mut self.priority_ref,
+                                &mut self.events,
+                                self.manager,
+                            )
+                        };
+                        managed.or_else(|| gap_blocker(&self.cars, i, delta_time))
+                    }
+                    Junction::Roundabout => give_way_to(&self.cars, i, delta_time),
+                };
+                let car = &mut self.cars[i];
+                let (old_x, old_y) = (car.x, car.y);
+                let released = self.deadlocks.is_released(car.id, now);
+                let human = car.driver == Driver::Human;
+                let yields_to = yields_to.filter(|_| !released);
+                self.events.yielding(car.id, yields_to);
+                if let Some(pedestrian_id) = crossing {
+                    // Released cars stop for people too, the wait is never released
+                    self.stats.pedestrian_yield_time += delta_time;
+                    self.deadlocks.wait(car.id, pedestrian_node(pedestrian_id), WaitReason::Crossing);
+                } else if let Some(other_id) = yields_to {
+                    self.stats.yield_time += delta_time;
+                    // Nobody can release a human from the gap it waits for
+                    let reason = if (self.scenario.junction == Junction::Roundabout || human) && !car.entered {
+                        WaitReason::GiveWay
+                    } else {
+                        WaitReason::Yield
+                    };
+                    self.deadlocks.wait(car.id, other_id, reason);
+                } else {
+                    // Update speed stats
+                    car.max_speed = car.max_speed.max(car.speed);
+                    car.min_speed = car.min_speed.min(car.speed);
+                    
+                    // Update global speed stats
+                    self.stats.max_velocity = self.stats.max_velocity.max(car.speed);
+                    
+                    // Get into a lane the route is allowed from, waiting at the stop line if needed
+                    let holds = change_lane(car, delta_time, &self.scenario.lanes);
+                    
+                    // Move car based on route
+                    if !holds {
+                        if human {
+                            move_human(car, delta_time, &mut self.in_intersection, self.scenario.junction);
+                        } else {
+                            move_car(car, delta_time, &mut self.in_intersection, self.scenario.junction);
+                        }
+                    }
+                    
+                    // The footprint guard has the last word on the move
+                    if !self.footprint_guard(i, &snapshot, delta_time) {
+                        if still_reacting(&mut self.cars[i], old_x, old_y, delta_time) {
+                            snapshot.restore(&mut self.cars[i], &mut self.in_intersection);
+                        } else if !human && self.cars[i].moving && self.cars[i].x == old_x && self.cars[i].y == old_y {
+                            // Held back by the intersection gate
+                            let mut inside: Vec<usize> = self.in_intersection.values().flatten().copied().collect();
+                            inside.sort_unstable();
+                            for other_id in inside {
+                                self.deadlocks.wait(self.cars[i].id, other_id, WaitReason::Gate);
+                            }
+                        }
+                    }
+                }
+                
+                let car = &mut self.cars[i];
+                // Track time spent standing still
+                let stood_still = car.moving && car.x == old_x && car.y == old_y;
+                if stood_still {
+                    if car.velocity.x != 0.0 || car.velocity.y != 0.0 {
+                        car.stops += 1;
+                    }
+                    car.wait_time += delta_time;
+                    self.stats.max_wait = self.stats.max_wait.max(Duration::from_secs_f32(car.wait_time));
+                }
+                if car.kind == VehicleKind::Bicycle {
+                    pedal(car, delta_time, stood_still);
+                }
+                car.distance += (car.x - old_x).hypot(car.y - old_y);
+                if delta_time > 0.0 {
+                    car.velocity = Vec2::new((car.x - old_x) / delta_time, (car.y - old_y) / delta_time);
+                }
+                
+                // Right turns never "enter", they start turning instead
+                if car.entry_time.is_none() && (car.entered || car.rotated) {
+                    car.entry_time = Some(now);
+                    let car_id = car.id;
+                    self.log(Some(car_id), EventKind::EnteredIntersection);
+                }
+            }
+        }
+    }
+    
+    // The manager compares hitboxes along each movement's own lane, it doesn't see a turning
+    // body swing into the next lane or a car changing lanes upstream. This guard is the last
+    // word: a move that leaves the car's body overlapping another one is taken back. If the
+    // turn or the lane change is what hits, the car keeps its heading or its lane and tries
+    // again a little further on. Returns true if it changed the move.
+    fn footprint_guard(&mut self, i: usize, snapshot: &MoveSnapshot, delta_time: f32) -> bool {
+        let Some(blocker) = footprint_blocker(&self.cars, i, &self.grid) else {
+            return false;
+        };
+        self.stats.footprint_hold_time += delta_time;
+        let mut still_blocked = true;
+        // On the ring and round a U-turn heading and position go together, so only a full
+        // undo there
+        let keeps = self.scenario.junction == Junction::Cross && self.cars[i].route != Route::UTurn;
+        if keeps && snapshot.keep_heading(&mut self.cars[i]) {
+            still_blocked = footprint_blocker(&self.cars, i, &self.grid).is_some();
+        }
+        if keeps && still_blocked && snapshot.keep_lane(&mut self.cars[i]) {
+            still_blocked = footprint_blocker(&self.cars, i, &self.grid).is_some();
+        }
+        if still_blocked {
+            snapshot.restore(&mut self.cars[i], &mut self.in_intersection);
+            self.deadlocks.wait(self.cars[i].id, blocker, WaitReason::Blocked);
+        }
+        true
+    }
+    
+    // One sample for every interval that ended by `now`, stamped with the interval's time so the
+    // series stays regular however long the frames are
+    fn take_samples(&mut self, now: f32) {
+        while self.next_sample_time <= now {
+            let sample = Sample::take(self.next_sample_time, &self.cars, &self.in_intersection, &self.stats);
+            self.stats.samples.push(sample);
+            self.next_sample_time += self.sample_interval;
+        }
+    }
+    
+    // File every car under the cells its bounds touch
+    fn rebuild_grid(&mut self) {
+        self.grid.clear();
+        for (i, car) in self.cars.iter().enumerate() {
+            self.grid.insert(i, car_bounds(car));
+        }
+    }
+    
+    // Count each pair of overlapping cars once as a crash
+    fn detect_crashes(&mut self) {
+        let mut crashes = Vec::new();
+        for (i, car) in self.cars.iter().enumerate() {
+            for j in self.grid.query(car_bounds(car)) {
+                let other = &self.cars[j];
+                if j > i && footprints_overlap(car, other) {
+                    let pair = (car.id.min(other.id), car.id.max(other.id));
+                    if self.crash_pairs.insert(pair) {
+                        self.stats.crashes += 1;
+                        crashes.push(pair);
+                    }
+                }
+            }
+        }
+        for (a, b) in crashes {
+            self.log(Some(a), EventKind::Crash { other: b });
+        }
+    }
+    
+    fn print_movement_summaries(&self) {
+        println!("{:<7} {:<9} {:>5} {:>10} {:>10} {:>10} {:>10} {:>9} {:>8}  delay histogram",
+            "Dir", "Route", "Cars", "Travel", "Delay", "Median", "P95", "Stopped", "Veh/h");
+        for summary in self.stats.movement_summaries(self.sim_time) {
+            println!("{:<7} {:<9} {:>5} {:>10.2} {:>10.2} {:>10.2} {:>10.2} {:>9.2} {:>8.0}  {:?} (from {:.1} s, {:.1} s bins)",
+                format!("{:?}", summary.direction),
+                format!("{:?}", summary.route),
+                summary.count,
+                summary.travel_time.mean,
+                summary.delay.mean,
+                summary.delay.median,
+                summary.delay.p95,
+                summary.stopped_time.mean,
+                summary.throughput_per_hour,
+                summary.delay_histogram.counts,
+                summary.delay_histogram.min,
+                summary.delay_histogram.bin_width,
+            );
+        }
+        if self.scenario.penetration < 1.0 {
+            for driver in Driver::ALL {
+                let delay = self.stats.driver_delay_distribution(driver);
+                println!("{:<10} {:>5} cars, delay mean {:.2} s, p95 {:.2} s", driver.name(), delay.count, delay.mean, delay.p95);
+            }
+            println!("Close calls with a human driver: {}", self.stats.human_close_calls);
+        }
+    }
+    
+    fn hud_lines(&self) -> Vec<String> {
+        let mut lines = vec![
+            format!("Cars on screen: {}", self.cars.len()),
+            format!("Cars passed: {}", self.stats.cars_passed),
+            format!("Throughput: {:.1} cars/min", self.stats.throughput_per_minute(self.sim_time)),
+            format!("Average wait: {:.2} s", self.stats.average_wait().as_secs_f32()),
+            format!("Max wait: {:.2} s", self.stats.max_wait.as_secs_f32()),
+            format!("Close calls: {} (TTC < {} s)", self.stats.close_call, self.ttc_threshold),
+            format!("Crashes: {}", self.stats.crashes),
+            format!("Deadlocks resolved: {}, unresolved: {}", self.stats.deadlocks, self.stats.unresolved_deadlocks),
+            format!("Sim time: {:.1} s", self.sim_time),
+            format!("Speed: x{}", self.speed_multiplier),
+            format!("FPS: {:.0}", self.fps),
+        ];
+        if !self.scenario.crosswalks().is_empty() {
+            let waits = self.stats.pedestrian_wait_distribution();
+            lines.insert(8, format!("Pedestrians across: {}", waits.count));
+            lines.insert(9, format!("Pedestrian wait: {:.2} s", waits.mean));
+        }
+        if !self.scenario.bike_lanes().is_empty() {
+            // After the other counts, before the clock
+            let at = lines.len() - 3;
+            lines.insert(at, format!("Cyclists through: {}", self.stats.cyclist_trips.len()));
+            lines.insert(at + 1, format!("Right-hook close calls: {}", self.stats.right_hooks));
+        }
+        if self.scenario.penetration < 1.0 {
+            let humans = self.cars.iter().filter(|car| car.driver == Driver::Human).count();
+            let at = lines.len() - 3;
+            lines.insert(at, format!("Connected: {:.0}%, human drivers on screen: {}", self.scenario.penetration * 100.0, humans));
+            lines.insert(at + 1, format!("Close calls with humans: {}", self.stats.human_close_calls));
+        }
+        lines
+    }
+    
+    pub fn handle_car_spawn_input(&mut self, keycode: Keycode) {
         // Check if enough time has passed since last spawn
         let now = Instant::now();
         if now.duration_since(self.last_spawn_time) < self.spawn_cooldown {
-            println!("Spawn on cooldown, please wait {:.1} more seconds", 
-                (self.spawn_cooldown - now.duration_since(self.last_spawn_time)).as_secs_f32());
+            let remaining = (self.spawn_cooldown - now.duration_since(self.last_spawn_time)).as_secs_f32();
+            self.log(None, EventKind::SpawnCooldown { remaining });
             return;
         }
         
@@ -199,9 +762,13 @@ impl Game {
             Keycode::Down => Some(Direction::South),
             Keycode::Right => Some(Direction::East),
             Keycode::Left => Some(Direction::West),
+            Keycode::U => {
+                self.spawn_random_u_turn(now);
+                None
+            }
             Keycode::R => {
                 // Random direction
-                let random_dir = fastrand::u32(0..4);
+                let random_dir = self.rng.u32(0..4);
                 match random_dir {
                     0 => Some(Direction::North),
                     1 => Some(Direction::South),
@@ -214,99 +781,110 @@ impl Game {
         };
         
         if let Some(dir) = direction {
-            // Random route
-            let random_route = fastrand::u32(0..3);
-            let route = match random_route {
-                0 => Route::Right,
-                1 => Route::Straight,
-                2 => Route::Left,
-                _ => Route::Straight,
-            };
-            
-            let speed = if route == Route::Straight { SUPER } else { FAST };
+            // Random turn among the ones the junction has from that side, U-turns have their own key
+            let routes: Vec<Route> = Route::ALL
+                .into_iter()
+                .filter(|route| *route != Route::UTurn && self.scenario.lanes.has_movement(&dir, route))
+                .collect();
+            if routes.is_empty() {
+                return;
+            }
+            let route = routes[self.rng.usize(..routes.len())].clone();
             
-            // Get spawn coordinates
-            if let Some(&(x, y)) = self.spawn_coords.get(&(route.clone(), dir.clone())) {
-                // Determine collision type
-                let collision_type = match route {
-                    Route::Straight => match dir {
-                        Direction::North => CollisionType::NS,
-                        Direction::West => CollisionType::WS,
-                        Direction::East => CollisionType::ES,
-                        Direction::South => CollisionType::SS,
-                    },
-                    Route::Left => match dir {
-                        Direction::North => CollisionType::NL,
-                        Direction::West => CollisionType::WL,
-                        Direction::East => CollisionType::EL,
-                        Direction::South => CollisionType::SL,
-                    },
-                    _ => CollisionType::GG,
-                };
-                
-                // Collision types list (Basically all possible collision types for the given route and direction)
-                let collision_types = match route {
-                    Route::Straight => {
-                        match dir {
-                            Direction::North => vec![CollisionType::NS, CollisionType::WS, CollisionType::ES, CollisionType::WL, CollisionType::SL],
-                            Direction::West => vec![CollisionType::WS, CollisionType::SS, CollisionType::NS, CollisionType::EL, CollisionType::SL],
-                            Direction::East => vec![CollisionType::ES, CollisionType::SS, CollisionType::NS, CollisionType::WL, CollisionType::NL],
-                            Direction::South => vec![CollisionType::SS, CollisionType::WS, CollisionType::ES, CollisionType::EL, CollisionType::NL],
-                        }
-                    },
-                    Route::Left => match dir {
-                        Direction::North => vec![CollisionType::NL, CollisionType::ES, CollisionType::SS],
-                        Direction::West => vec![CollisionType::WL, CollisionType::NS, CollisionType::ES],
-                        Direction::East => vec![CollisionType::EL, CollisionType::SS, CollisionType::WS],
-                        Direction::South => vec![CollisionType::SL, CollisionType::NS, CollisionType::ES],
-                    },
-                    _ => vec![CollisionType::GG],
-                };
-                
-                // Check for spawn collision
-                let car_data: Vec<(usize, f32, f32, Vec<CollisionType>, bool)> = self.cars
-                    .iter()
-                    .map(|car| (car.id, car.x, car.y, car.collision_types.clone(), car.rotated))
-                    .collect();
-                
-                let should_spawn = !check_spawn_collision(x, y, collision_type, &car_data);
-                
-                if should_spawn {
-                    // Initial rotation based on direction
-                    let rotation = match dir {
-                        Direction::North => 0.0,
-                        Direction::East => -PI / 2.0,
-                        Direction::South => PI,
-                        Direction::West => PI / 2.0,
-                    };
-                    
-                    let car = Car {
-                        x,
-                        y,
-                        speed,
-                        direction: dir,
-                        route,
-                        rotation,
-                        id: self.next_car_id,
-                        spawn_time: Instant::now(),
-                        moving: true,
-                        rotated: false,
-                        collision_types,
-                        max_speed: speed,
-                        min_speed: speed,
-                        entered: false,
-                    };
-                    
-                    self.cars.push(car);
-                    self.next_car_id += 1;
-                    self.stats.max_number_cars += 1;
-                    self.last_spawn_time = now; // Update last spawn time
-                    
-                    println!("Spawned car {} at ({}, {})", self.next_car_id - 1, x, y);
-                } else {
-                    println!("Spawn blocked due to collision.");
-                }
+            if self.spawn_car(dir.clone(), route.clone()) {
+                self.last_spawn_time = now; // Update last spawn time
+            } else {
+                self.log(None, EventKind::SpawnBlocked { direction: dir, route });
             }
         }
     }
+    
+    // U-turn from a random approach that allows one
+    fn spawn_random_u_turn(&mut self, now: Instant) {
+        let directions: Vec<Direction> = Direction::ALL
+            .into_iter()
+            .filter(|direction| self.scenario.lanes.has_movement(direction, &Route::UTurn))
+            .collect();
+        if directions.is_empty() {
+            return;
+        }
+        let dir = directions[self.rng.usize(..directions.len())].clone();
+        if self.spawn_car(dir.clone(), Route::UTurn) {
+            self.last_spawn_time = now;
+        } else {
+            self.log(None, EventKind::SpawnBlocked { direction: dir, route: Route::UTurn });
+        }
+    }
+    
+    // Spawn a car at the start of a lane its route is allowed from, returns false if every such
+    // lane entrance is occupied
+    pub fn spawn_car(&mut self, dir: Direction, route: Route) -> bool {
+        self.spawn_car_in_lane(dir, route, None)
+    }
+    
+    // A given lane that doesn't allow the route makes the car change lanes before the stop line
+    pub fn spawn_car_in_lane(&mut self, dir: Direction, route: Route, lane: Option<usize>) -> bool {
+        let lane_count = self.scenario.lanes.lanes(&dir).len();
+        let mut lanes = match lane {
+            Some(lane) if lane < lane_count => vec![lane],
+            _ => self.scenario.lanes.lanes_for(&dir, &route),
+        };
+        // Emptiest lane first so shared and double lanes fill evenly
+        lanes.sort_by_key(|&lane| {
+            self.cars.iter().filter(|car| car.direction == dir && car.lane == lane && !car.entered).count()
+        });
+        
+        let Some(mut car) = lanes.into_iter().find_map(|lane| {
+            let (x, y) = lane_start(&dir, lane);
+            let mut car = Car::new(self.next_car_id, dir.clone(), route.clone(), x, y, self.sim_time);
+            car.lane = lane;
+            let occupied = self.grid
+                .query(car_bounds(&car))
+                .into_iter()
+                .any(|i| footprints_overlap(&car, &self.cars[i]));
+            let blocked = occupied || check_spawn_collision(x, y, car.collision_types[0], &self.cars, &self.grid);
+            (!blocked).then_some(car)
+        }) else {
+            return false;
+        };
+        let (x, y) = (car.x, car.y);
+        car.driver = self.pick_driver();
+        
+        self.grid.insert(self.cars.len(), car_bounds(&car));
+        self.cars.push(car);
+        self.next_car_id += 1;
+        self.stats.max_number_cars += 1;
+        
+        self.log(Some(self.next_car_id - 1), EventKind::Spawned { direction: dir, route, x, y });
+        true
+    }
+    
+    // Connected with the scenario's penetration, otherwise human driven. A fully connected
+    // scenario doesn't draw, so runs without humans are the same as before.
+    fn pick_driver(&mut self) -> Driver {
+        if self.scenario.penetration < 1.0 && self.rng.f32() >= self.scenario.penetration {
+            Driver::Human
+        } else {
+            Driver::Autonomous
+        }
+    }
+    
+    // Spawn a cyclist at the start of the approach's bike lane, returns false if it is occupied
+    pub fn spawn_bicycle(&mut self, dir: Direction) -> bool {
+        let bicycle = Car::bicycle(self.next_car_id, dir.clone(), self.sim_time);
+        let occupied = self.grid
+            .query(car_bounds(&bicycle))
+            .into_iter()
+            .any(|i| footprints_overlap(&bicycle, &self.cars[i]));
+        let (x, y) = (bicycle.x, bicycle.y);
+        if occupied || check_spawn_collision(x, y, bicycle.collision_types[0], &self.cars, &self.grid) {
+            return false;
+        }
+        
+        self.grid.insert(self.cars.len(), car_bounds(&bicycle));
+        self.cars.push(bicycle);
+        self.next_car_id += 1;
+        self.log(Some(self.next_car_id - 1), EventKind::Spawned { direction: dir, route: Route::Straight, x, y });
+        true
+    }
 }
\ No newline at end of file
@@ -0,0 +1,277 @@
+use crate::types::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::f32::consts::PI;
+
+// The road drawn on the map has room for three lanes per approach, a hard limit: the turn
+// paths and the manager's reference points are laid out for these slots
+pub const MAX_LANES: usize = 3;
+
+// Edges of the crossing area between the four roads, kerb to kerb
+pub const CROSSING_X: (f32, f32) = (330.0, 685.0);
+pub const CROSSING_Y: (f32, f32) = (206.0, 457.5);
+
+// Slot a bike lane takes: the kerb one
+pub const BIKE_LANE: usize = MAX_LANES - 1;
+
+// Sideways speed while changing lanes, a lane is crossed in about half a second
+pub const LANE_CHANGE_SPEED: f32 = 120.0;
+
+// Cars stop changing lanes this far before the stop line
+const LANE_CHANGE_END: f32 = 10.0;
+
+// Lanes of every approach, innermost (next to the centre line) first. Each lane lists the
+// routes cars may take from it. Lane i of an approach drives on slot i of the road, and a
+// turning car leaves on the same slot of its exit road.
+// An approach without lanes is a road cars only leave by (or no road at all when its exit is
+// closed too), which is how T-junctions and one-way streets are laid out.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LaneLayout {
+    pub approaches: HashMap<Direction, Vec<Vec<Route>>>,
+    #[serde(default)]
+    pub exits: HashMap<Direction, usize>, // lanes of the road cars leave by with this heading, MAX_LANES unless listed
+}
+
+impl Default for LaneLayout {
+    // One lane per route: left innermost, right on the kerb, U-turns from the left lane
+    fn default() -> Self {
+        let lanes = vec![vec![Route::Left, Route::UTurn], vec![Route::Straight], vec![Route::Right]];
+        LaneLayout {
+            approaches: Direction::ALL.into_iter().map(|d| (d, lanes.clone())).collect(),
+            exits: HashMap::new(),
+        }
+    }
+}
+
+impl LaneLayout {
+    // Every movement to an open exit needs a lane, U-turns excepted, and a lane may only feed
+    // an exit that has a lane on its slot
+    pub fn validate(&self) -> Result<(), String> {
+        for direction in Direction::ALL {
+            let exit_lanes = self.exit_lanes(&direction);
+            if exit_lanes > MAX_LANES {
+                return Err(format!("{:?} exit has {} lanes, at most {} fit", direction, exit_lanes, MAX_LANES));
+            }
+            let lanes = self.lanes(&direction);
+            if lanes.len() > MAX_LANES {
+                return Err(format!("{:?} approach needs 0 to {} lanes, got {}", direction, MAX_LANES, lanes.len()));
+            }
+            if lanes.is_empty() {
+                continue;
+            }
+            for route in Route::ALL {
+                let exit = exit_direction(&direction, &route);
+                let allowed: Vec<usize> = (0..lanes.len()).filter(|&lane| self.allows(&direction, lane, &route)).collect();
+                if self.exit_lanes(&exit) == 0 {
+                    if !allowed.is_empty() {
+                        return Err(format!("{:?} {:?} from the {:?} approach leads to a closed exit", route, exit, direction));
+                    }
+                } else if allowed.is_empty() && route != Route::UTurn {
+                    return Err(format!("No lane of the {:?} approach allows {:?}", direction, route));
+                } else if let Some(lane) = allowed.into_iter().find(|&lane| lane >= self.exit_lanes(&exit)) {
+                    return Err(format!(
+                        "Lane {} of the {:?} approach turns {:?} but the {:?} exit has {} lanes",
+                        lane, direction, route, exit, self.exit_lanes(&exit)
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Lanes for an approach the scenario leaves out: every movement its exits allow on the
+    // route's usual slot, or the outermost slot a narrow exit has. Slots no route uses are left
+    // as lanes without routes.
+    pub fn default_lanes(&self, direction: &Direction) -> Vec<Vec<Route>> {
+        let mut lanes: Vec<Vec<Route>> = Vec::new();
+        for route in [Route::Left, Route::UTurn, Route::Straight, Route::Right] {
+            let exit_lanes = self.exit_lanes(&exit_direction(direction, &route));
+            if exit_lanes == 0 {
+                continue;
+            }
+            let lane = default_lane(&route).min(exit_lanes - 1);
+            if lanes.len() <= lane {
+                lanes.resize(lane + 1, Vec::new());
+            }
+            lanes[lane].push(route);
+        }
+        lanes
+    }
+
+    // Bike lanes take the kerb slot of every road, so exits lose it and the routes of an
+    // approach's kerb lane move to the lane beside it
+    pub fn add_bike_lanes(&mut self) {
+        for direction in Direction::ALL {
+            let exit_lanes = self.exit_lanes(&direction).min(BIKE_LANE);
+            self.exits.insert(direction.clone(), exit_lanes);
+            if let Some(lanes) = self.approaches.get_mut(&direction) {
+                while lanes.len() > BIKE_LANE {
+                    let kerb = lanes.pop().unwrap_or_default();
+                    if let Some(inner) = lanes.last_mut() {
+                        for route in kerb {
+                            if !inner.contains(&route) {
+                                inner.push(route);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn lanes(&self, direction: &Direction) -> &[Vec<Route>] {
+        self.approaches.get(direction).map_or(&[][..], |lanes| &lanes[..])
+    }
+
+    pub fn exit_lanes(&self, direction: &Direction) -> usize {
+        self.exits.get(direction).copied().unwrap_or(MAX_LANES)
+    }
+
+    // The junction has this movement: a lane of the approach allows it and cars can leave by
+    // the exit
+    pub fn has_movement(&self, direction: &Direction, route: &Route) -> bool {
+        self.lanes(direction).iter().any(|routes| routes.contains(route))
+            && self.exit_lanes(&exit_direction(direction, route)) > 0
+    }
+
+    pub fn allows(&self, direction: &Direction, lane: usize, route: &Route) -> bool {
+        self.lanes(direction).get(lane).is_some_and(|routes| routes.contains(route))
+    }
+
+    // Lanes a car may take its route from, closest to the route's usual slot first
+    pub fn lanes_for(&self, direction: &Direction, route: &Route) -> Vec<usize> {
+        let mut lanes: Vec<usize> = (0..self.lanes(direction).len())
+            .filter(|&lane| self.allows(direction, lane, route))
+            .collect();
+        lanes.sort_by_key(|&lane| lane.abs_diff(default_lane(route)));
+        lanes
+    }
+
+    // Nearest lane to `lane` the car may take its route from
+    pub fn target_lane(&self, direction: &Direction, lane: usize, route: &Route) -> Option<usize> {
+        self.lanes_for(direction, route).into_iter().min_by_key(|&target| target.abs_diff(lane))
+    }
+}
+
+// Slot each route uses on the standard road
+pub fn default_lane(route: &Route) -> usize {
+    match route {
+        Route::Left | Route::UTurn => 0,
+        Route::Straight => 1,
+        Route::Right => 2,
+    }
+}
+
+// Sideways coordinate of a slot: x for cars heading north or south, y for east or west
+pub fn lane_position(direction: &Direction, lane: usize) -> f32 {
+    let slots = match direction {
+        Direction::North => [535.0, 595.0, 655.0],
+        Direction::South => [480.0, 420.0, 360.0],
+        Direction::East => [315.0, 278.0, 230.0],
+        Direction::West => [350.0, 390.0, 435.0],
+    };
+    slots[lane.min(MAX_LANES - 1)]
+}
+
+// Where a car appears on a slot, the kerb lane starts right at the edge of the map
+pub fn lane_start(direction: &Direction, lane: usize) -> (f32, f32) {
+    let kerb = lane == MAX_LANES - 1;
+    let position = lane_position(direction, lane);
+    match direction {
+        Direction::North => (position, if kerb { 0.0 } else { 45.0 }),
+        Direction::South => (position, 682.0),
+        Direction::East => (0.0, position),
+        Direction::West => (if kerb { 1024.0 } else { 978.0 }, position),
+    }
+}
+
+// Heading of a car after its turn
+pub fn exit_direction(direction: &Direction, route: &Route) -> Direction {
+    match (route, direction) {
+        (Route::Straight, _) => direction.clone(),
+        (Route::UTurn, _) => direction.opposite(),
+        (Route::Left, Direction::North) | (Route::Right, Direction::South) => Direction::West,
+        (Route::Left, Direction::South) | (Route::Right, Direction::North) => Direction::East,
+        (Route::Left, Direction::East) | (Route::Right, Direction::West) => Direction::North,
+        (Route::Left, Direction::West) | (Route::Right, Direction::East) => Direction::South,
+    }
+}
+
+// Sprite rotation of a car driving with this heading
+pub fn heading_rotation(direction: &Direction) -> f32 {
+    match direction {
+        Direction::North => 0.0,
+        Direction::East => -PI / 2.0,
+        Direction::South => PI,
+        Direction::West => PI / 2.0,
+    }
+}
+
+// Turns happen where the approach meets the exit slot, this moves the turn points of the
+// route's usual slot to the car's own one
+pub fn turn_shift(car: &Car) -> f32 {
+    let exit = exit_direction(&car.direction, &car.route);
+    lane_position(&exit, car.lane) - lane_position(&exit, default_lane(&car.route))
+}
+
+// Distance left to the stop line of the approach, negative once past it
+pub fn distance_to_stop_line(car: &Car) -> f32 {
+    match car.direction {
+        Direction::North => 170.0 - car.y,
+        Direction::South => car.y - 540.0,
+        Direction::East => 273.0 - car.x,
+        Direction::West => car.x - 740.0,
+    }
+}
+
+// A car that has turned is out of the crossing once it is behind the stop line of the cars
+// coming in on its exit road
+pub fn left_crossing(car: &Car) -> bool {
+    if !car.rotated {
+        return false;
+    }
+    let oncoming = exit_direction(&car.direction, &car.route).opposite();
+    match oncoming {
+        Direction::North => car.y < 170.0,
+        Direction::South => car.y > 540.0,
+        Direction::East => car.x < 273.0,
+        Direction::West => car.x > 740.0,
+    }
+}
+
+// Slide towards a lane the car's route is allowed from. Returns true while the car has to wait
+// before the stop line because it couldn't get there in time.
+pub fn change_lane(car: &mut Car, delta_time: f32, layout: &LaneLayout) -> bool {
+    // Cyclists keep to the bike lane
+    if car.kind == VehicleKind::Bicycle || car.entered || car.rotated || distance_to_stop_line(car) < 0.0 {
+        return false;
+    }
+    let in_lane = lateral(car) == lane_position(&car.direction, car.lane);
+    if in_lane && layout.allows(&car.direction, car.lane, &car.route) {
+        return false;
+    }
+    let Some(target) = layout.target_lane(&car.direction, car.lane, &car.route) else {
+        return false;
+    };
+
+    let goal = lane_position(&car.direction, target);
+    let current = lateral(car);
+    let step = LANE_CHANGE_SPEED * delta_time;
+    let next = if (goal - current).abs() <= step { goal } else { current + step * (goal - current).signum() };
+    match car.direction {
+        Direction::North | Direction::South => car.x = next,
+        Direction::East | Direction::West => car.y = next,
+    }
+    if next == goal {
+        car.lane = target;
+    }
+    next != goal && distance_to_stop_line(car) < LANE_CHANGE_END
+}
+
+// Position across the car's road
+pub fn lateral(car: &Car) -> f32 {
+    match car.direction {
+        Direction::North | Direction::South => car.x,
+        Direction::East | Direction::West => car.y,
+    }
+}
@@ -0,0 +1,21 @@
+pub mod types;
+pub mod collision;
+pub mod movement;
+pub mod renderer;
+pub mod game;
+pub mod stats;
+pub mod export;
+pub mod scenario;
+pub mod safety;
+pub mod deadlock;
+pub mod events;
+pub mod cli;
+pub mod experiment;
+pub mod spatial;
+pub mod lanes;
+pub mod snapshot;
+pub mod network;
+pub mod routing;
+pub mod roundabout;
+pub mod crosswalk;
+pub mod driver;
@@ -1,14 +1,82 @@
-mod types;
-mod collision;
-mod movement;
-mod renderer;
-mod game;
-
-use game::Game;
-use renderer::{GameTextures, WINDOW_WIDTH, WINDOW_HEIGHT};
+use smart_road::cli::{Options, USAGE};
+use smart_road::experiment;
+use smart_road::game::Game;
+use smart_road::network::Network;
+use smart_road::renderer::{GameTextures, WINDOW_WIDTH, WINDOW_HEIGHT};
 use std::time::{Duration, Instant};
 
 fn main() -> Result<(), String> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("batch") {
+        return experiment::run_batch(&args[1..]);
+    }
+    
+    let options = match Options::parse(&args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+    if options.help {
+        println!("{}", USAGE);
+        return Ok(());
+    }
+    
+    let mut simulation = match &options.network {
+        Some(path) => Simulation::Network(Box::new(options.build_network(path)?)),
+        None => Simulation::Intersection(Box::new(options.build_game()?)),
+    };
+    if options.headless {
+        simulation.run_to_end()
+    } else {
+        run_window(simulation)
+    }
+}
+
+// A single intersection or a network of them, both run in the same window loop
+enum Simulation {
+    Intersection(Box<Game>),
+    Network(Box<Network>),
+}
+
+impl Simulation {
+    fn run_to_end(&mut self) -> Result<(), String> {
+        match self {
+            Simulation::Intersection(game) => game.run_to_end(),
+            Simulation::Network(network) => network.run_to_end(),
+        }
+    }
+
+    fn handle_events(&mut self, event_pump: &mut sdl2::EventPump) -> bool {
+        match self {
+            Simulation::Intersection(game) => game.handle_events(event_pump),
+            Simulation::Network(network) => network.handle_events(event_pump),
+        }
+    }
+
+    fn update(&mut self, delta_time: f32) {
+        match self {
+            Simulation::Intersection(game) => game.update(delta_time),
+            Simulation::Network(network) => network.update(delta_time),
+        }
+    }
+
+    fn render(
+        &self,
+        canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
+        textures: &GameTextures,
+        font: &sdl2::ttf::Font,
+        hud_font: &sdl2::ttf::Font,
+    ) -> Result<(), String> {
+        match self {
+            Simulation::Intersection(game) => game.render(canvas, textures, font, hud_font),
+            Simulation::Network(network) => network.render(canvas, textures, font, hud_font),
+        }
+    }
+}
+
+fn run_window(mut game: Simulation) -> Result<(), String> {
     // Initialize SDL2
     let sdl_context = sdl2::init()?;
     let video_subsystem = sdl_context.video()?;
@@ -22,6 +90,13 @@ fn main() -> Result<(), String> {
         .or_else(|_| ttf_context.load_font("/System/Library/Fonts/Arial.ttf", 24))
         .or_else(|_| ttf_context.load_font("/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf", 24))
         .map_err(|e| format!("Could not load font: {}", e))?;
+    
+    // Smaller font for the live HUD, the system fonts stand in when run outside the repo
+    let hud_font = ttf_context.load_font("fonts/roboto.ttf", 16)
+        .or_else(|_| ttf_context.load_font("C:/Windows/Fonts/arial.ttf", 16))
+        .or_else(|_| ttf_context.load_font("/System/Library/Fonts/Arial.ttf", 16))
+        .or_else(|_| ttf_context.load_font("/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf", 16))
+        .map_err(|e| format!("Could not load HUD font: {}", e))?;
 
     // Create window
     let window = video_subsystem
@@ -41,7 +116,6 @@ fn main() -> Result<(), String> {
     let textures = GameTextures::load(&texture_creator)?;
 
     let mut event_pump = sdl_context.event_pump()?;
-    let mut game = Game::new();
     
     let mut last_time = Instant::now();
 
@@ -60,7 +134,7 @@ fn main() -> Result<(), String> {
         game.update(delta_time);
 
         // Render
-        game.render(&mut canvas, &textures, &font)?;
+        game.render(&mut canvas, &textures, &font, &hud_font)?;
 
         // Cap frame rate to ~60 FPS
         std::thread::sleep(Duration::from_millis(16));
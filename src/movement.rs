@@ -1,7 +1,145 @@
 use crate::types::*;
+use crate::lanes::{distance_to_stop_line, exit_direction, heading_rotation, lane_position, lateral, turn_shift};
+use crate::roundabout::move_roundabout;
 use std::collections::HashMap;
 use std::f32::consts::PI;
 
+// What the move functions change, so a move can be taken back
+pub struct MoveSnapshot {
+    x: f32,
+    y: f32,
+    speed: f32,
+    rotation: f32,
+    rotated: bool,
+    entered: bool,
+    moving: bool,
+    lane: usize,
+    inside: Vec<CollisionType>, // intersection lists that already held the car
+}
+
+impl MoveSnapshot {
+    pub fn take(car: &Car, in_intersection: &HashMap<CollisionType, Vec<usize>>) -> Self {
+        MoveSnapshot {
+            x: car.x,
+            y: car.y,
+            speed: car.speed,
+            rotation: car.rotation,
+            rotated: car.rotated,
+            entered: car.entered,
+            moving: car.moving,
+            lane: car.lane,
+            inside: in_intersection
+                .iter()
+                .filter(|(_, ids)| ids.contains(&car.id))
+                .map(|(collision_type, _)| *collision_type)
+                .collect(),
+        }
+    }
+
+    // Undo only a turn made by the last move, returns false if it didn't turn
+    pub fn keep_heading(&self, car: &mut Car) -> bool {
+        if car.rotated == self.rotated && car.rotation == self.rotation {
+            return false;
+        }
+        car.rotation = self.rotation;
+        car.rotated = self.rotated;
+        true
+    }
+
+    // Undo only a lane change made by the last move, returns false if it didn't change lanes
+    pub fn keep_lane(&self, car: &mut Car) -> bool {
+        if self.entered || self.rotated {
+            return false;
+        }
+        let sideways = match car.direction {
+            Direction::North | Direction::South => &mut car.x,
+            Direction::East | Direction::West => &mut car.y,
+        };
+        let before = match car.direction {
+            Direction::North | Direction::South => self.x,
+            Direction::East | Direction::West => self.y,
+        };
+        if *sideways == before && car.lane == self.lane {
+            return false;
+        }
+        *sideways = before;
+        car.lane = self.lane;
+        true
+    }
+
+    pub fn restore(&self, car: &mut Car, in_intersection: &mut HashMap<CollisionType, Vec<usize>>) {
+        car.x = self.x;
+        car.y = self.y;
+        car.speed = self.speed;
+        car.rotation = self.rotation;
+        car.rotated = self.rotated;
+        car.entered = self.entered;
+        car.moving = self.moving;
+        car.lane = self.lane;
+        for (collision_type, ids) in in_intersection.iter_mut() {
+            if !self.inside.contains(collision_type) {
+                ids.retain(|&id| id != car.id);
+            }
+        }
+    }
+}
+
+// One step along the car's path through the junction
+pub fn move_car(car: &mut Car, delta_time: f32, in_intersection: &mut HashMap<CollisionType, Vec<usize>>, junction: Junction) {
+    match (junction, &car.route) {
+        (Junction::Roundabout, _) => move_roundabout(car, delta_time),
+        (Junction::Cross, _) if car.kind == VehicleKind::Bicycle => ride(car, delta_time),
+        (Junction::Cross, Route::Straight) => move_straight(car, delta_time, in_intersection),
+        (Junction::Cross, Route::Right) => move_right(car, delta_time, in_intersection),
+        (Junction::Cross, Route::Left) => move_left(car, delta_time, in_intersection),
+        (Junction::Cross, Route::UTurn) => move_uturn(car, delta_time, in_intersection),
+    }
+}
+
+// Cyclists ride straight along the bike lane and leave the gate to cars: a cyclist counted
+// inside could hold back the very car the manager let go before it
+fn ride(car: &mut Car, delta_time: f32) {
+    let step = car.speed * delta_time;
+    match car.direction {
+        Direction::North => car.y += step,
+        Direction::South => car.y -= step,
+        Direction::East => car.x += step,
+        Direction::West => car.x -= step,
+    }
+    if distance_to_stop_line(car) < 0.0 {
+        car.entered = true;
+    }
+    if car.y >= 682.0 || car.y <= 0.0 || car.x >= 1023.0 || car.x <= 0.0 {
+        car.moving = false;
+    }
+}
+
+// Cyclists set off slowly after standing still and pedal back up to cruising speed
+pub fn pedal(car: &mut Car, delta_time: f32, stood_still: bool) {
+    car.speed = if stood_still {
+        BICYCLE_START_SPEED
+    } else {
+        (car.speed + BICYCLE_ACCELERATION * delta_time).min(BICYCLE_SPEED)
+    };
+}
+
+const U_TURNS: [CollisionType; 4] = [CollisionType::NU, CollisionType::SU, CollisionType::EU, CollisionType::WU];
+
+// A U-turn in the crossing keeps left turns out like another left turn would
+fn u_turn_inside(in_intersection: &HashMap<CollisionType, Vec<usize>>, car_id: usize) -> bool {
+    U_TURNS
+        .into_iter()
+        .any(|t| in_intersection.get(&t).is_some_and(|ids| ids.iter().any(|&id| id != car_id)))
+}
+
+// Past the turn point the car faces its new way, even if it couldn't turn earlier
+fn finish_turn(car: &mut Car, rotation: f32) {
+    if !car.rotated {
+        car.rotation = rotation;
+        car.rotated = true;
+    }
+}
+
 pub fn move_straight(car: &mut Car, delta_time: f32, in_intersection: &mut HashMap<CollisionType, Vec<usize>>) {
     match car.direction {
         Direction::North => {
@@ -12,7 +150,7 @@ pub fn move_straight(car: &mut Car, delta_time: f32, in_intersection: &mut HashM
                         counter += 1;
                     }
                 }
-                let ns_cars = in_intersection.entry(CollisionType::NS).or_insert(Vec::new());
+                let ns_cars = in_intersection.entry(CollisionType::NS).or_default();
                 if !ns_cars.is_empty() {
                     counter -= 1;
                 }
@@ -21,7 +159,7 @@ pub fn move_straight(car: &mut Car, delta_time: f32, in_intersection: &mut HashM
                     car.y += car.speed * delta_time;
                 }
                 if car.y > 170.0 {
-                    let ns_cars = in_intersection.entry(CollisionType::NS).or_insert(Vec::new());
+                    let ns_cars = in_intersection.entry(CollisionType::NS).or_default();
                     if !ns_cars.contains(&car.id) {
                         car.entered = true;
                         if counter < 3 {
@@ -41,7 +179,7 @@ pub fn move_straight(car: &mut Car, delta_time: f32, in_intersection: &mut HashM
                         counter += 1;
                     }
                 }
-                let ss_cars = in_intersection.entry(CollisionType::SS).or_insert(Vec::new());
+                let ss_cars = in_intersection.entry(CollisionType::SS).or_default();
                 if !ss_cars.is_empty() {
                     counter -= 1;
                 }
@@ -50,7 +188,7 @@ pub fn move_straight(car: &mut Car, delta_time: f32, in_intersection: &mut HashM
                     car.y -= car.speed * delta_time;
                 }
                 if car.y < 540.0 {
-                    let ss_cars = in_intersection.entry(CollisionType::SS).or_insert(Vec::new());
+                    let ss_cars = in_intersection.entry(CollisionType::SS).or_default();
                     if !ss_cars.contains(&car.id) {
                         car.entered = true;
                         if counter < 3 {
@@ -70,7 +208,7 @@ pub fn move_straight(car: &mut Car, delta_time: f32, in_intersection: &mut HashM
                         counter += 1;
                     }
                 }
-                let es_cars = in_intersection.entry(CollisionType::ES).or_insert(Vec::new());
+                let es_cars = in_intersection.entry(CollisionType::ES).or_default();
                 if !es_cars.is_empty() {
                     counter -= 1;
                 }
@@ -79,7 +217,7 @@ pub fn move_straight(car: &mut Car, delta_time: f32, in_intersection: &mut HashM
                     car.x += car.speed * delta_time;
                 }
                 if car.x > 270.0 {
-                    let es_cars = in_intersection.entry(CollisionType::ES).or_insert(Vec::new());
+                    let es_cars = in_intersection.entry(CollisionType::ES).or_default();
                     if !es_cars.contains(&car.id) {
                         car.entered = true;
                         if counter < 3 {
@@ -99,7 +237,7 @@ pub fn move_straight(car: &mut Car, delta_time: f32, in_intersection: &mut HashM
                         counter += 1;
                     }
                 }
-                let ws_cars = in_intersection.entry(CollisionType::WS).or_insert(Vec::new());
+                let ws_cars = in_intersection.entry(CollisionType::WS).or_default();
                 if !ws_cars.is_empty() {
                     counter -= 1;
                 }
@@ -108,7 +246,7 @@ pub fn move_straight(car: &mut Car, delta_time: f32, in_intersection: &mut HashM
                     car.x -= car.speed * delta_time;
                 }
                 if car.x < 760.0 {
-                    let ws_cars = in_intersection.entry(CollisionType::WS).or_insert(Vec::new());
+                    let ws_cars = in_intersection.entry(CollisionType::WS).or_default();
                     if !ws_cars.contains(&car.id) {
                         car.entered = true;
                         if counter < 3 {
@@ -124,12 +262,13 @@ pub fn move_straight(car: &mut Car, delta_time: f32, in_intersection: &mut HashM
 }
 
 pub fn move_right(car: &mut Car, delta_time: f32, _in_intersection: &mut HashMap<CollisionType, Vec<usize>>) {
+    let shift = turn_shift(car);
     match car.direction {
         Direction::North => {
-            if car.y < 230.0 {
+            if car.y < 230.0 + shift {
                 car.y += car.speed * delta_time;
                 car.speed -= 0.2;
-                if car.y > 180.0 {
+                if car.y > 180.0 + shift {
                     car.x += 0.3;
                     if !car.rotated {
                         car.rotation = -PI / 2.0;
@@ -137,6 +276,7 @@ pub fn move_right(car: &mut Car, delta_time: f32, _in_intersection: &mut HashMap
                     }
                 }
             } else if car.x < 1200.0 {
+                finish_turn(car, -PI / 2.0);
                 car.speed += 2.0;
                 car.x += car.speed * delta_time;
             } else {
@@ -144,10 +284,10 @@ pub fn move_right(car: &mut Car, delta_time: f32, _in_intersection: &mut HashMap
             }
         }
         Direction::West => {
-            if car.x > 655.0 {
+            if car.x > 655.0 + shift {
                 car.x -= car.speed * delta_time;
                 car.speed -= 0.1;
-                if car.x < 730.0 {
+                if car.x < 730.0 + shift {
                     car.y += 0.3;
                     if !car.rotated {
                         car.rotation = 0.0;
@@ -155,6 +295,7 @@ pub fn move_right(car: &mut Car, delta_time: f32, _in_intersection: &mut HashMap
                     }
                 }
             } else if car.y < 720.0 {
+                finish_turn(car, 0.0);
                 car.speed += 2.0;
                 car.y += car.speed * delta_time;
             } else {
@@ -162,10 +303,10 @@ pub fn move_right(car: &mut Car, delta_time: f32, _in_intersection: &mut HashMap
             }
         }
         Direction::South => {
-            if car.y > 433.0 {
+            if car.y > 433.0 + shift {
                 car.y -= car.speed * delta_time;
                 car.speed -= 0.2;
-                if car.y < 540.0 {
+                if car.y < 540.0 + shift {
                     car.x -= 0.2;
                     if !car.rotated {
                         car.rotation = PI / 2.0;
@@ -173,6 +314,7 @@ pub fn move_right(car: &mut Car, delta_time: f32, _in_intersection: &mut HashMap
                     }
                 }
             } else if car.x > -50.0 {
+                finish_turn(car, PI / 2.0);
                 car.speed += 2.0;
                 car.x -= car.speed * delta_time;
             } else {
@@ -180,10 +322,10 @@ pub fn move_right(car: &mut Car, delta_time: f32, _in_intersection: &mut HashMap
             }
         }
         Direction::East => {
-            if car.x < 360.0 {
+            if car.x < 360.0 + shift {
                 car.x += car.speed * delta_time;
                 car.speed -= 0.1;
-                if car.x > 285.0 {
+                if car.x > 285.0 + shift {
                     car.y -= 0.3;
                     if !car.rotated {
                         car.rotation = PI;
@@ -191,6 +333,7 @@ pub fn move_right(car: &mut Car, delta_time: f32, _in_intersection: &mut HashMap
                     }
                 }
             } else if car.y > -50.0 {
+                finish_turn(car, PI);
                 car.speed += 2.0;
                 car.y -= car.speed * delta_time;
             } else {
@@ -201,9 +344,10 @@ pub fn move_right(car: &mut Car, delta_time: f32, _in_intersection: &mut HashMap
 }
 
 pub fn move_left(car: &mut Car, delta_time: f32, in_intersection: &mut HashMap<CollisionType, Vec<usize>>) {
+    let shift = turn_shift(car);
     match car.direction {
         Direction::North => {
-            if car.y < 350.0 {
+            if car.y < 350.0 + shift {
                 let mut counter = 0;
                 let mut counter_left = 0;
                 for cars_list in in_intersection.values() {
@@ -211,12 +355,13 @@ pub fn move_left(car: &mut Car, delta_time: f32, in_intersection: &mut HashMap<C
                         counter += 1;
                     }
                 }
-                if in_intersection.entry(CollisionType::WL).or_insert(Vec::new()).len() > 0 ||
-                   in_intersection.entry(CollisionType::SL).or_insert(Vec::new()).len() > 0 ||
-                   in_intersection.entry(CollisionType::EL).or_insert(Vec::new()).len() > 0 {
+                if !in_intersection.entry(CollisionType::WL).or_default().is_empty() ||
+                   !in_intersection.entry(CollisionType::SL).or_default().is_empty() ||
+                   !in_intersection.entry(CollisionType::EL).or_default().is_empty() ||
+                   u_turn_inside(in_intersection, car.id) {
                     counter_left += 1;
                 }
-                let nl_cars = in_intersection.entry(CollisionType::NL).or_insert(Vec::new());
+                let nl_cars = in_intersection.entry(CollisionType::NL).or_default();
                 if !nl_cars.is_empty() {
                     counter -= 1;
                 }
@@ -224,14 +369,12 @@ pub fn move_left(car: &mut Car, delta_time: f32, in_intersection: &mut HashMap<C
                 if !car.entered || (counter < 3 && counter_left < 1) {
                     car.y += car.speed * delta_time;
                 }
-                if car.y > 307.0 {
-                    if !car.rotated {
-                        car.rotation = PI / 2.0;
-                        car.rotated = true;
-                    }
+                if car.y > 307.0 + shift && !car.rotated {
+                    car.rotation = PI / 2.0;
+                    car.rotated = true;
                 }
                 if car.y > 170.0 {
-                    let nl_cars = in_intersection.entry(CollisionType::NL).or_insert(Vec::new());
+                    let nl_cars = in_intersection.entry(CollisionType::NL).or_default();
                     if !nl_cars.contains(&car.id) {
                         car.entered = true;
                         if counter < 3 && counter_left < 1 {
@@ -240,7 +383,7 @@ pub fn move_left(car: &mut Car, delta_time: f32, in_intersection: &mut HashMap<C
                     }
                 }
             } else if car.x > 0.0 {
-                car.rotated = true;
+                finish_turn(car, PI / 2.0);
                 car.speed += 2.0;
                 car.x -= car.speed * delta_time;
             } else {
@@ -248,7 +391,7 @@ pub fn move_left(car: &mut Car, delta_time: f32, in_intersection: &mut HashMap<C
             }
         }
         Direction::South => {
-            if car.y > 315.0 {
+            if car.y > 315.0 + shift {
                 let mut counter = 0;
                 let mut counter_left = 0;
                 for cars_list in in_intersection.values() {
@@ -256,12 +399,13 @@ pub fn move_left(car: &mut Car, delta_time: f32, in_intersection: &mut HashMap<C
                         counter += 1;
                     }
                 }
-                if in_intersection.entry(CollisionType::WL).or_insert(Vec::new()).len() > 0 ||
-                   in_intersection.entry(CollisionType::NL).or_insert(Vec::new()).len() > 0 ||
-                   in_intersection.entry(CollisionType::EL).or_insert(Vec::new()).len() > 0 {
+                if !in_intersection.entry(CollisionType::WL).or_default().is_empty() ||
+                   !in_intersection.entry(CollisionType::NL).or_default().is_empty() ||
+                   !in_intersection.entry(CollisionType::EL).or_default().is_empty() ||
+                   u_turn_inside(in_intersection, car.id) {
                     counter_left += 1;
                 }
-                let sl_cars = in_intersection.entry(CollisionType::SL).or_insert(Vec::new());
+                let sl_cars = in_intersection.entry(CollisionType::SL).or_default();
                 if !sl_cars.is_empty() {
                     counter -= 1;
                 }
@@ -269,14 +413,12 @@ pub fn move_left(car: &mut Car, delta_time: f32, in_intersection: &mut HashMap<C
                 if !car.entered || (counter < 3 && counter_left < 1) {
                     car.y -= car.speed * delta_time;
                 }
-                if car.y < 388.0 {
-                    if !car.rotated {
-                        car.rotation = -PI / 2.0;
-                        car.rotated = true;
-                    }
+                if car.y < 388.0 + shift && !car.rotated {
+                    car.rotation = -PI / 2.0;
+                    car.rotated = true;
                 }
                 if car.y < 540.0 {
-                    let sl_cars = in_intersection.entry(CollisionType::SL).or_insert(Vec::new());
+                    let sl_cars = in_intersection.entry(CollisionType::SL).or_default();
                     if !sl_cars.contains(&car.id) {
                         car.entered = true;
                         if counter < 3 && counter_left < 1 {
@@ -285,7 +427,7 @@ pub fn move_left(car: &mut Car, delta_time: f32, in_intersection: &mut HashMap<C
                     }
                 }
             } else if car.x < 1200.0 {
-                car.rotated = true;
+                finish_turn(car, -PI / 2.0);
                 car.speed += 2.0;
                 car.x += car.speed * delta_time;
             } else {
@@ -293,7 +435,7 @@ pub fn move_left(car: &mut Car, delta_time: f32, in_intersection: &mut HashMap<C
             }
         }
         Direction::East => {
-            if car.x < 538.0 {
+            if car.x < 538.0 + shift {
                 let mut counter = 0;
                 let mut counter_left = 0;
                 for cars_list in in_intersection.values() {
@@ -301,12 +443,13 @@ pub fn move_left(car: &mut Car, delta_time: f32, in_intersection: &mut HashMap<C
                         counter += 1;
                     }
                 }
-                if in_intersection.entry(CollisionType::WL).or_insert(Vec::new()).len() > 0 ||
-                   in_intersection.entry(CollisionType::SL).or_insert(Vec::new()).len() > 0 ||
-                   in_intersection.entry(CollisionType::NL).or_insert(Vec::new()).len() > 0 {
+                if !in_intersection.entry(CollisionType::WL).or_default().is_empty() ||
+                   !in_intersection.entry(CollisionType::SL).or_default().is_empty() ||
+                   !in_intersection.entry(CollisionType::NL).or_default().is_empty() ||
+                   u_turn_inside(in_intersection, car.id) {
                     counter_left += 1;
                 }
-                let el_cars = in_intersection.entry(CollisionType::EL).or_insert(Vec::new());
+                let el_cars = in_intersection.entry(CollisionType::EL).or_default();
                 if !el_cars.is_empty() {
                     counter -= 1;
                 }
@@ -314,14 +457,12 @@ pub fn move_left(car: &mut Car, delta_time: f32, in_intersection: &mut HashMap<C
                 if !car.entered || (counter < 3 && counter_left < 1) {
                     car.x += car.speed * delta_time;
                 }
-                if car.x > 490.0 {
-                    if !car.rotated {
-                        car.rotation = 0.0;
-                        car.rotated = true;
-                    }
+                if car.x > 490.0 + shift && !car.rotated {
+                    car.rotation = 0.0;
+                    car.rotated = true;
                 }
                 if car.x > 273.0 {
-                    let el_cars = in_intersection.entry(CollisionType::EL).or_insert(Vec::new());
+                    let el_cars = in_intersection.entry(CollisionType::EL).or_default();
                     if !el_cars.contains(&car.id) {
                         car.entered = true;
                         if counter < 3 && counter_left < 1 {
@@ -331,14 +472,14 @@ pub fn move_left(car: &mut Car, delta_time: f32, in_intersection: &mut HashMap<C
                 }
             } else if car.y < 700.0 {
                 car.speed += 2.0;
-                car.rotated = true;
+                finish_turn(car, 0.0);
                 car.y += car.speed * delta_time;
             } else {
                 car.moving = false;
             }
         }
         Direction::West => {
-            if car.x > 477.0 {
+            if car.x > 477.0 + shift {
                 let mut counter = 0;
                 let mut counter_left = 0;
                 for cars_list in in_intersection.values() {
@@ -346,12 +487,13 @@ pub fn move_left(car: &mut Car, delta_time: f32, in_intersection: &mut HashMap<C
                         counter += 1;
                     }
                 }
-                if in_intersection.entry(CollisionType::EL).or_insert(Vec::new()).len() > 0 ||
-                   in_intersection.entry(CollisionType::SL).or_insert(Vec::new()).len() > 0 ||
-                   in_intersection.entry(CollisionType::NL).or_insert(Vec::new()).len() > 0 {
+                if !in_intersection.entry(CollisionType::EL).or_default().is_empty() ||
+                   !in_intersection.entry(CollisionType::SL).or_default().is_empty() ||
+                   !in_intersection.entry(CollisionType::NL).or_default().is_empty() ||
+                   u_turn_inside(in_intersection, car.id) {
                     counter_left += 1;
                 }
-                let wl_cars = in_intersection.entry(CollisionType::WL).or_insert(Vec::new());
+                let wl_cars = in_intersection.entry(CollisionType::WL).or_default();
                 if !wl_cars.is_empty() {
                     counter -= 1;
                 }
@@ -359,14 +501,12 @@ pub fn move_left(car: &mut Car, delta_time: f32, in_intersection: &mut HashMap<C
                 if !car.entered || (counter < 3 && counter_left < 1) {
                     car.x -= car.speed * delta_time;
                 }
-                if car.x < 535.0 {
-                    if !car.rotated {
-                        car.rotation = PI;
-                        car.rotated = true;
-                    }
+                if car.x < 535.0 + shift && !car.rotated {
+                    car.rotation = PI;
+                    car.rotated = true;
                 }
                 if car.x < 740.0 {
-                    let wl_cars = in_intersection.entry(CollisionType::WL).or_insert(Vec::new());
+                    let wl_cars = in_intersection.entry(CollisionType::WL).or_default();
                     if !wl_cars.contains(&car.id) {
                         car.entered = true;
                         if counter < 3 && counter_left < 1 {
@@ -376,11 +516,94 @@ pub fn move_left(car: &mut Car, delta_time: f32, in_intersection: &mut HashMap<C
                 }
             } else if car.y > 0.0 {
                 car.speed += 2.0;
-                car.rotated = true;
+                finish_turn(car, PI);
                 car.y -= car.speed * delta_time;
             } else {
                 car.moving = false;
             }
         }
     }
-}
\ No newline at end of file
+}
+
+// Position along the car's road, `lateral` is across it
+fn along(car: &Car) -> f32 {
+    match car.direction {
+        Direction::North | Direction::South => car.y,
+        Direction::East | Direction::West => car.x,
+    }
+}
+
+fn place(car: &mut Car, along: f32, across: f32) {
+    match car.direction {
+        Direction::North | Direction::South => (car.x, car.y) = (across, along),
+        Direction::East | Direction::West => (car.x, car.y) = (along, across),
+    }
+}
+
+// Up the lane to the line left turns leave on, round a half circle onto the same slot of the
+// road back and out again. The gate treats it like a left turn. `rotated` is set once the car
+// faces back.
+pub fn move_uturn(car: &mut Car, delta_time: f32, in_intersection: &mut HashMap<CollisionType, Vec<usize>>) {
+    let back = car.direction.opposite();
+    let sign = match car.direction {
+        Direction::North | Direction::East => 1.0,
+        Direction::South | Direction::West => -1.0,
+    };
+    if car.rotated {
+        let next = along(car) - sign * car.speed * delta_time;
+        let across = lane_position(&back, car.lane);
+        place(car, next, across);
+        if car.x < -50.0 || car.x > 1074.0 || car.y < -50.0 || car.y > 732.0 {
+            car.moving = false;
+        }
+        return;
+    }
+
+    let from = lane_position(&car.direction, car.lane);
+    let to = lane_position(&back, car.lane);
+    let radius = (from - to).abs() / 2.0;
+    let turn_at = lane_position(&exit_direction(&car.direction, &Route::Left), 0) - sign * radius;
+    let start = heading_rotation(&car.direction);
+    let turned = car.rotation - start;
+
+    if turned == 0.0 && (turn_at - along(car)) * sign > 0.0 {
+        let own = car.collision_types[0];
+        let mut counter = in_intersection.values().filter(|ids| !ids.is_empty()).count();
+        // U-turns go one at a time, a queue of them doesn't follow each other in ahead of the
+        // turns waiting on other approaches
+        let lefts = [CollisionType::NL, CollisionType::SL, CollisionType::EL, CollisionType::WL];
+        let left_inside = lefts.iter().any(|t| in_intersection.get(t).is_some_and(|ids| !ids.is_empty()))
+            || u_turn_inside(in_intersection, car.id);
+        let own_cars = in_intersection.entry(own).or_default();
+        if !own_cars.is_empty() {
+            counter -= 1;
+        }
+
+        if !car.entered || (counter < 3 && !left_inside) {
+            let next = along(car) + sign * car.speed * delta_time;
+            let next = if (turn_at - next) * sign > 0.0 { next } else { turn_at };
+            let across = lateral(car);
+            place(car, next, across);
+        }
+        if distance_to_stop_line(car) < 0.0 {
+            let own_cars = in_intersection.entry(own).or_default();
+            if !own_cars.contains(&car.id) {
+                car.entered = true;
+                if counter < 3 && !left_inside {
+                    own_cars.push(car.id);
+                }
+            }
+        }
+        return;
+    }
+
+    // Round the half circle, the car turns as far as it has gone round
+    let turned = (turned + car.speed * delta_time / radius).min(PI);
+    let centre = (from + to) / 2.0;
+    place(car, turn_at + sign * radius * turned.sin(), centre + (from - centre) * turned.cos());
+    car.rotation = start + turned;
+    if turned >= PI {
+        car.rotation = heading_rotation(&back);
+        car.rotated = true;
+    }
+}
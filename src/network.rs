@@ -0,0 +1,1020 @@
+use crate::collision::{movements_conflict, Manager};
+use crate::export::{csv_field, write_file};
+use crate::game::{next_speed_multiplier, Game, HEADLESS_STEP};
+use crate::lanes::{default_lane, distance_to_stop_line, exit_direction, lane_start};
+use crate::renderer::*;
+use crate::routing::*;
+use crate::scenario::{exponential, Scenario};
+use crate::stats::Distribution;
+use crate::types::*;
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::Canvas;
+use sdl2::ttf::Font;
+use sdl2::video::Window;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Space between intersections in the network view, in pixels
+const NODE_GAP: f32 = 24.0;
+
+// A link is congested once getting through it takes this many times its free travel time
+pub const CONGESTION_FACTOR: f32 = 1.5;
+
+// Weight of the latest car in a link's recent travel time
+const LINK_TIME_SMOOTHING: f32 = 0.2;
+
+// Coordinated nodes keep expected arrivals this far apart, seconds
+const CONFLICT_HEADWAY: f32 = 1.5; // movements that cross or merge
+const LANE_HEADWAY: f32 = 1.0; // cars arriving in the same lane
+
+// A paced car may take up to this share of the link's travel time longer, searching in steps
+const MAX_PACING: f32 = 1.0;
+const PACING_STEP: f32 = 0.1;
+
+// Network file: intersections on a grid and the roads between them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkConfig {
+    pub name: String,
+    pub duration: Option<f32>,
+    pub seed: Option<u64>,
+    pub nodes: Vec<NodeConfig>,
+    pub links: Vec<LinkConfig>,
+    pub demand: Vec<OdDemand>, // origin-destination matrix, one entry per pair with traffic
+    pub reroute: bool, // replan the rest of the way when a link ahead is congested
+    pub coordinate: bool, // nodes share expected arrivals so cars are paced into free slots
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        NetworkConfig {
+            name: "network".to_string(),
+            duration: None,
+            seed: None,
+            nodes: Vec::new(),
+            links: Vec::new(),
+            demand: Vec::new(),
+            reroute: false,
+            coordinate: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeConfig {
+    pub name: String,
+    pub column: i32, // place in the network view
+    pub row: i32,
+    #[serde(default)]
+    pub scenario: Scenario, // demand, lanes and manager of this intersection
+}
+
+// Road from one intersection to another. Cars leave `from` driving the way they arrive at `to`
+// on its `approach`, so an East link leads to the node on the right and a North link to the one
+// above (the map is drawn upside down).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkConfig {
+    pub from: String,
+    pub to: String,
+    pub approach: Direction,
+    pub travel_time: f32, // seconds between leaving one map and entering the next
+}
+
+// Cars per minute entering at `origin` and leaving at `destination`, both on a side without a link
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OdDemand {
+    pub origin: String,
+    pub destination: String,
+    pub rate: f32,
+}
+
+impl NetworkConfig {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Could not read network {}: {}", path.display(), e))?;
+        let mut config: NetworkConfig = serde_json::from_str(&text)
+            .map_err(|e| format!("Invalid network {}: {}", path.display(), e))?;
+
+        // Unnamed networks are named after their file
+        if config.name == NetworkConfig::default().name {
+            if let Some(stem) = path.file_stem() {
+                config.name = stem.to_string_lossy().to_string();
+            }
+        }
+        config.validate().map_err(|e| format!("Invalid network {}: {}", path.display(), e))?;
+        Ok(config)
+    }
+
+    pub fn validate(&mut self) -> Result<(), String> {
+        if self.nodes.is_empty() {
+            return Err("A network needs at least one node".to_string());
+        }
+        let mut names = HashSet::new();
+        for node in &mut self.nodes {
+            if !names.insert(node.name.clone()) {
+                return Err(format!("Node '{}' is defined twice", node.name));
+            }
+            node.scenario.prepare().map_err(|e| format!("Node '{}': {}", node.name, e))?;
+        }
+
+        let mut exits = HashSet::new();
+        let mut entries = HashSet::new();
+        for link in &self.links {
+            for name in [&link.from, &link.to] {
+                if !names.contains(name) {
+                    return Err(format!("Link {} -> {} uses unknown node '{}'", link.from, link.to, name));
+                }
+            }
+            if link.from == link.to {
+                return Err(format!("Link {} -> {} leads back to its own node", link.from, link.to));
+            }
+            if link.travel_time <= 0.0 {
+                return Err(format!("Link {} -> {} needs a positive travel time", link.from, link.to));
+            }
+            // The road has to leave `from` and come into `to` on that side
+            let scenario = |name: &String| self.nodes.iter().find(|node| node.name == *name).map(|node| &node.scenario);
+            if scenario(&link.from).is_some_and(|s| s.lanes.exit_lanes(&link.approach) == 0) {
+                return Err(format!("Link {} -> {} leaves by the closed {:?} exit", link.from, link.to, link.approach));
+            }
+            if scenario(&link.to).is_some_and(|s| s.lanes.lanes(&link.approach).is_empty()) {
+                return Err(format!("Link {} -> {} arrives on the {:?} approach, which has no lanes", link.from, link.to, link.approach));
+            }
+            if scenario(&link.to).is_some_and(|s| !Route::ALL.iter().any(|route| s.lanes.has_movement(&link.approach, route))) {
+                return Err(format!("Link {} -> {} arrives on the {:?} approach, where no route leads on", link.from, link.to, link.approach));
+            }
+            // One road per side of every intersection
+            if !exits.insert((link.from.clone(), link.approach.clone())) {
+                return Err(format!("Node '{}' has two links leaving {:?}", link.from, link.approach));
+            }
+            if !entries.insert((link.to.clone(), link.approach.clone())) {
+                return Err(format!("Node '{}' has two links arriving on {:?}", link.to, link.approach));
+            }
+        }
+
+        for pair in &self.demand {
+            for name in [&pair.origin, &pair.destination] {
+                if !names.contains(name) {
+                    return Err(format!("Demand {} -> {} uses unknown node '{}'", pair.origin, pair.destination, name));
+                }
+            }
+            if pair.rate < 0.0 {
+                return Err(format!("Demand {} -> {} has a negative rate", pair.origin, pair.destination));
+            }
+        }
+        Ok(())
+    }
+}
+
+// Seconds from entering a map to its stop line at full speed
+fn time_to_stop_line(approach: &Direction, route: &Route, lane: usize) -> f32 {
+    let (x, y) = lane_start(approach, lane);
+    let car = Car::new(0, approach.clone(), route.clone(), x, y, 0.0);
+    distance_to_stop_line(&car) / car.max_speed
+}
+
+// One intersection of the network, simulated by its own game
+pub struct Node {
+    pub name: String,
+    pub column: i32,
+    pub row: i32,
+    pub game: Game,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Link {
+    pub from: usize, // node index
+    pub to: usize,
+    pub approach: Direction,
+    pub travel_time: f32,
+}
+
+// A car's trip through the network, it is a new car at every intersection it crosses
+#[derive(Debug, Clone)]
+pub struct Journey {
+    pub id: usize,
+    pub origin: usize,
+    pub start_time: f32,
+    pub hops: Vec<(usize, Route)>, // nodes crossed so far and the turn taken at each
+    pub plan: VecDeque<Route>, // turns to take at the next nodes, picked on the way when empty
+    pub destination: Option<usize>, // cars from the demand matrix know where they're going
+    pub rerouted: usize,
+    pub stops: usize, // at every node crossed and at the end of links
+}
+
+// A car from the demand matrix waiting to get onto its origin node
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub journey: usize,
+    pub node: usize,
+    pub approach: Direction,
+    pub route: Route,
+}
+
+// A car on a link between two intersections
+#[derive(Debug, Clone)]
+pub struct Transit {
+    pub journey: usize,
+    pub link: usize,
+    pub route: Route, // turn at the next node
+    pub lane: usize, // closest lane to the one it drove off on that its next turn allows
+    pub arrive_at: f32,
+    pub departed_at: f32,
+    pub waited: bool, // stood at the end of the link because the node couldn't take it
+    pub driver: Driver, // whoever drives keeps driving at the next node
+}
+
+// A car a node expects at its stop line, for pacing cars on the links into it
+#[derive(Debug, Clone)]
+pub struct ExpectedArrival {
+    pub at: f32,
+    pub approach: Direction,
+    pub route: Option<Route>, // not drawn yet for the next random car of an approach
+    pub lane: Option<usize>,
+}
+
+impl ExpectedArrival {
+    // Whether a car reaching the stop line at `at` would have to wait for this one
+    fn clashes(&self, at: f32, approach: &Direction, route: &Route, lane: usize) -> bool {
+        let gap = (self.at - at).abs();
+        let conflicts = match &self.route {
+            Some(other) => movements_conflict(approach, route, &self.approach, other),
+            None => Route::ALL.iter().any(|other| movements_conflict(approach, route, &self.approach, other)),
+        };
+        (self.approach == *approach && self.lane.is_none_or(|other| other == lane) && gap < LANE_HEADWAY)
+            || (conflicts && gap < CONFLICT_HEADWAY)
+    }
+}
+
+// Finished trip through the network
+#[derive(Debug, Clone, Serialize)]
+pub struct JourneyRecord {
+    pub id: usize,
+    pub origin: String,
+    pub destination: String,
+    pub start_time: f32,
+    pub end_time: f32,
+    pub hops: usize,
+    pub path: String, // node names joined by '>'
+    pub rerouted: usize,
+    pub stops: usize,
+}
+
+impl JourneyRecord {
+    pub fn travel_time(&self) -> f32 {
+        self.end_time - self.start_time
+    }
+}
+
+// Finished journeys of one origin-destination pair
+#[derive(Debug, Clone, Serialize)]
+pub struct OdSummary {
+    pub origin: String,
+    pub destination: String,
+    pub count: usize,
+    pub travel_time: Distribution,
+    pub mean_hops: f32,
+    pub mean_stops: f32,
+    pub rerouted: usize, // journeys that changed their plan at least once
+}
+
+pub struct Network {
+    pub name: String,
+    pub app_state: AppState,
+    pub sim_time: f32,
+    pub duration: Option<f32>,
+    pub seed: u64,
+    pub rng: fastrand::Rng,
+    pub nodes: Vec<Node>,
+    pub links: Vec<Link>,
+    pub journeys: HashMap<usize, Journey>,
+    pub next_journey_id: usize,
+    pub on_node: HashMap<(usize, usize), usize>, // (node, car id) -> journey
+    pub in_transit: Vec<Transit>, // in the order the cars drove off
+    pub finished: Vec<JourneyRecord>,
+    pub demand: Vec<(usize, usize, f32)>, // origin, destination, cars per minute
+    pub next_demand: Vec<f32>, // sim time of the next car of every pair
+    pub entering: Vec<Entry>, // in the order the cars were due
+    pub link_times: Vec<f32>, // recent time cars took to get through each link, waiting included
+    pub reroute: bool,
+    pub coordinate: bool,
+    pub focus: Option<usize>, // node the camera shows, None for the whole network
+    pub speed_multiplier: f32,
+    pub show_hud: bool,
+    pub verbose: bool,
+    pub export_dir: PathBuf,
+    pub auto_export: bool,
+}
+
+impl Network {
+    pub fn new(config: &NetworkConfig, seed: u64) -> Result<Self, String> {
+        let mut config = config.clone();
+        config.validate()?;
+        let index: HashMap<&str, usize> = config.nodes.iter().enumerate().map(|(i, n)| (n.name.as_str(), i)).collect();
+        let links: Vec<Link> = config.links
+            .iter()
+            .map(|link| Link {
+                from: index[link.from.as_str()],
+                to: index[link.to.as_str()],
+                approach: link.approach.clone(),
+                travel_time: link.travel_time,
+            })
+            .collect();
+
+        let mut nodes = Vec::new();
+        for (i, node) in config.nodes.iter().enumerate() {
+            let mut game = Game::with_seed(seed.wrapping_add(i as u64));
+            game.verbose = false;
+            game.departures = Some(Vec::new());
+            let mut scenario = node.scenario.clone();
+            scenario.name = format!("{}-{}", config.name, node.name);
+            scenario.duration = None; // the network decides when to stop
+            // Approaches fed by a link only get the cars coming down it
+            for link in links.iter().filter(|link| link.to == i) {
+                scenario.rates.remove(&link.approach);
+            }
+            if let Some(name) = &scenario.manager {
+                game.manager = Manager::parse(name)?;
+            }
+            game.scenario = scenario;
+            nodes.push(Node { name: node.name.clone(), column: node.column, row: node.row, game });
+        }
+
+        let mut rng = fastrand::Rng::with_seed(seed);
+        let demand: Vec<(usize, usize, f32)> = config.demand
+            .iter()
+            .map(|pair| (index[pair.origin.as_str()], index[pair.destination.as_str()], pair.rate))
+            .collect();
+        let next_demand = demand.iter().map(|&(_, _, rate)| exponential(&mut rng, rate / 60.0)).collect();
+        let link_times = links.iter().map(|link| link.travel_time).collect();
+
+        let network = Network {
+            name: config.name.clone(),
+            app_state: AppState::Running,
+            sim_time: 0.0,
+            duration: config.duration,
+            seed,
+            rng,
+            nodes,
+            links,
+            journeys: HashMap::new(),
+            next_journey_id: 1,
+            on_node: HashMap::new(),
+            in_transit: Vec::new(),
+            finished: Vec::new(),
+            demand,
+            next_demand,
+            entering: Vec::new(),
+            link_times,
+            reroute: config.reroute,
+            coordinate: config.coordinate,
+            focus: None,
+            speed_multiplier: 1.0,
+            show_hud: true,
+            verbose: true,
+            export_dir: PathBuf::from("results"),
+            auto_export: false,
+        };
+        for pair in &config.demand {
+            let (origin, destination) = (index[pair.origin.as_str()], index[pair.destination.as_str()]);
+            if network.entry_plan(origin, destination).is_none() {
+                return Err(format!("No way through the network from {} to {}", pair.origin, pair.destination));
+            }
+        }
+        Ok(network)
+    }
+
+    pub fn update(&mut self, delta_time: f32) {
+        if self.app_state != AppState::Running {
+            return;
+        }
+        let delta_time = delta_time * self.speed_multiplier;
+        self.sim_time += delta_time;
+        for node in &mut self.nodes {
+            node.game.update(delta_time);
+        }
+        self.spawn_demand();
+        self.hand_off();
+        self.deliver();
+
+        if self.duration.is_some_and(|duration| self.sim_time >= duration) {
+            self.finish();
+        }
+    }
+
+    // Run with a fixed time step until the network's duration is reached
+    pub fn run_to_end(&mut self) -> Result<(), String> {
+        if self.duration.is_none() {
+            return Err("The network has no duration to run to".to_string());
+        }
+        while self.app_state == AppState::Running {
+            self.update(HEADLESS_STEP);
+        }
+        Ok(())
+    }
+
+    // Cars that drove off an intersection go down the link on that side, or leave the network
+    fn hand_off(&mut self) {
+        for from in 0..self.nodes.len() {
+            let departures = self.nodes[from].game.departures.as_mut().map(std::mem::take).unwrap_or_default();
+            for car in departures {
+                let journey_id = match self.on_node.remove(&(from, car.id)) {
+                    Some(id) => id,
+                    None => self.start_journey(from, car.spawn_time),
+                };
+                let heading = exit_direction(&car.direction, &car.route);
+                let Some(journey) = self.journeys.get_mut(&journey_id) else {
+                    continue;
+                };
+                journey.hops.push((from, car.route.clone()));
+                journey.stops += car.stops;
+
+                match self.links.iter().position(|link| link.from == from && link.approach == heading) {
+                    Some(link) => {
+                        let to = self.links[link].to;
+                        if self.reroute {
+                            self.replan_if_congested(journey_id, (to, heading.clone()));
+                        }
+                        let Some(journey) = self.journeys.get_mut(&journey_id) else {
+                            continue;
+                        };
+                        let Some(route) = journey.plan
+                            .pop_front()
+                            .or_else(|| self.nodes[to].game.scenario.pick_route(&heading, &mut self.rng))
+                        else {
+                            // Nowhere to go from that approach, so the journey ends here
+                            self.end_journey(journey_id, from);
+                            continue;
+                        };
+                        // The link is long enough to get into the right lane on the way
+                        let lanes = &self.nodes[to].game.scenario.lanes;
+                        let lane = lanes.target_lane(&heading, car.lane, &route).unwrap_or(car.lane);
+                        let mut arrive_at = self.sim_time + self.links[link].travel_time;
+                        if self.coordinate {
+                            arrive_at = self.paced_arrival(link, &route, lane, arrive_at);
+                        }
+                        self.in_transit.push(Transit {
+                            journey: journey_id,
+                            link,
+                            route,
+                            lane,
+                            arrive_at,
+                            departed_at: self.sim_time,
+                            waited: false,
+                            driver: car.driver,
+                        });
+                    }
+                    None => self.end_journey(journey_id, from),
+                }
+            }
+        }
+    }
+
+    // A car heading for a destination looks for another way when a link it still has to take
+    // is congested
+    fn replan_if_congested(&mut self, journey_id: usize, next: Place) {
+        let Some(journey) = self.journeys.get(&journey_id) else {
+            return;
+        };
+        let Some(destination) = journey.destination else {
+            return;
+        };
+        let routes: Vec<Route> = journey.plan.iter().cloned().collect();
+        if !self.path_links(next.clone(), &routes).into_iter().any(|link| self.is_congested(link)) {
+            return;
+        }
+        let Some(path) = self.plan(&[next], destination) else {
+            return;
+        };
+        if let Some(journey) = self.journeys.get_mut(&journey_id) {
+            if path.routes != routes {
+                journey.plan = path.routes.into();
+                journey.rerouted += 1;
+            }
+        }
+    }
+
+    // New cars of the demand matrix, each with the cheapest way to its destination right now
+    fn spawn_demand(&mut self) {
+        for pair in 0..self.demand.len() {
+            let (origin, destination, rate) = self.demand[pair];
+            if rate <= 0.0 {
+                continue;
+            }
+            while self.next_demand[pair] <= self.sim_time {
+                self.next_demand[pair] += exponential(&mut self.rng, rate / 60.0);
+                let Some(path) = self.entry_plan(origin, destination) else {
+                    continue;
+                };
+                let id = self.start_journey(origin, self.sim_time);
+                let mut routes: VecDeque<Route> = path.routes.into();
+                let Some(route) = routes.pop_front() else {
+                    continue;
+                };
+                if let Some(journey) = self.journeys.get_mut(&id) {
+                    journey.plan = routes;
+                    journey.destination = Some(destination);
+                }
+                self.entering.push(Entry { journey: id, node: origin, approach: path.start.1, route });
+            }
+        }
+    }
+
+    // When the cars a node knows about reach its stop line: those on its approaches, those
+    // coming down its links and its own next arrivals
+    pub fn expected_arrivals(&self, node: usize) -> Vec<ExpectedArrival> {
+        let game = &self.nodes[node].game;
+        let mut expected: Vec<ExpectedArrival> = game.cars
+            .iter()
+            .filter(|car| !car.entered && !car.rotated)
+            .map(|car| ExpectedArrival {
+                at: self.sim_time + distance_to_stop_line(car).max(0.0) / car.max_speed,
+                approach: car.direction.clone(),
+                route: Some(car.route.clone()),
+                lane: Some(car.lane),
+            })
+            .collect();
+
+        for transit in self.in_transit.iter().filter(|transit| self.links[transit.link].to == node) {
+            let approach = self.links[transit.link].approach.clone();
+            expected.push(ExpectedArrival {
+                at: transit.arrive_at.max(self.sim_time) + time_to_stop_line(&approach, &transit.route, transit.lane),
+                approach,
+                route: Some(transit.route.clone()),
+                lane: Some(transit.lane),
+            });
+        }
+
+        for spawn in &game.scenario.spawns[game.traffic.next_spawn.min(game.scenario.spawns.len())..] {
+            let lane = spawn.lane.unwrap_or_else(|| default_lane(&spawn.route));
+            expected.push(ExpectedArrival {
+                at: spawn.time + time_to_stop_line(&spawn.direction, &spawn.route, lane),
+                approach: spawn.direction.clone(),
+                route: Some(spawn.route.clone()),
+                lane: Some(lane),
+            });
+        }
+
+        // The route of a random car is drawn when it appears, straight stands in for the timing
+        for (direction, &time) in &game.traffic.next_arrival {
+            expected.push(ExpectedArrival {
+                at: time + time_to_stop_line(direction, &Route::Straight, default_lane(&Route::Straight)),
+                approach: direction.clone(),
+                route: None,
+                lane: None,
+            });
+        }
+        expected
+    }
+
+    // Earliest time from `earliest` on when the car reaches the next stop line clear of every
+    // car the node expects, so it drives a little slower instead of stopping there. Without a
+    // free slot in reach it arrives as usual and the node's manager sorts it out.
+    fn paced_arrival(&self, link: usize, route: &Route, lane: usize, earliest: f32) -> f32 {
+        let approach = &self.links[link].approach;
+        let expected = self.expected_arrivals(self.links[link].to);
+        let to_stop_line = time_to_stop_line(approach, route, lane);
+
+        let latest = earliest + self.links[link].travel_time * MAX_PACING;
+        let mut time = earliest;
+        while time <= latest {
+            let at = time + to_stop_line;
+            if !expected.iter().any(|arrival| arrival.clashes(at, approach, route, lane)) {
+                return time;
+            }
+            time += PACING_STEP;
+        }
+        earliest
+    }
+
+    // Seconds to get through a link now: what recent cars took, or more if the car at its end
+    // has been waiting to get in for longer than that
+    pub fn link_cost(&self, link: usize) -> f32 {
+        let waiting = self.in_transit
+            .iter()
+            .find(|transit| transit.link == link && transit.arrive_at <= self.sim_time)
+            .map_or(0.0, |transit| self.links[link].travel_time + self.sim_time - transit.arrive_at);
+        self.link_times[link].max(waiting)
+    }
+
+    pub fn is_congested(&self, link: usize) -> bool {
+        self.link_cost(link) > self.links[link].travel_time * CONGESTION_FACTOR
+    }
+
+    // Free flow time of a turn, lanes aside. Turns the node doesn't have can't be taken.
+    fn turn_cost(&self, node: usize, approach: &Direction, route: &Route) -> f32 {
+        let game = &self.nodes[node].game;
+        if !game.scenario.lanes.has_movement(approach, route) {
+            return f32::INFINITY;
+        }
+        game.free_flow_time(VehicleKind::Car, approach, route, default_lane(route))
+    }
+
+    pub fn plan(&self, starts: &[Place], destination: usize) -> Option<PlannedPath> {
+        shortest_path(&self.links, starts, destination, |n, d, r| self.turn_cost(n, d, r), |l| self.link_cost(l))
+    }
+
+    // Cheapest way from any entry side of `origin`
+    pub fn entry_plan(&self, origin: usize, destination: usize) -> Option<PlannedPath> {
+        let starts: Vec<Place> = entry_approaches(&self.links, origin)
+            .into_iter()
+            .filter(|d| !self.nodes[origin].game.scenario.lanes.lanes(d).is_empty())
+            .map(|d| (origin, d))
+            .collect();
+        self.plan(&starts, destination)
+    }
+
+    // Links a car takes from `start` following `routes`
+    pub fn path_links(&self, start: Place, routes: &[Route]) -> Vec<usize> {
+        let (mut node, mut approach) = start;
+        let mut links = Vec::new();
+        for route in routes {
+            let heading = exit_direction(&approach, route);
+            let Some(link) = self.links.iter().position(|link| link.from == node && link.approach == heading) else {
+                break;
+            };
+            links.push(link);
+            (node, approach) = (self.links[link].to, heading);
+        }
+        links
+    }
+
+    // Cars at the end of their link enter the next intersection. One that can't get in yet
+    // holds up the cars behind it on the same link, demand cars wait the same way at their origin.
+    fn deliver(&mut self) {
+        let mut blocked_entries = HashSet::new();
+        let mut i = 0;
+        while i < self.entering.len() {
+            let entry = &self.entering[i];
+            let place = (entry.node, entry.approach.clone());
+            if blocked_entries.contains(&place) {
+                i += 1;
+                continue;
+            }
+            let game = &mut self.nodes[entry.node].game;
+            if game.spawn_car_in_lane(entry.approach.clone(), entry.route.clone(), None) {
+                self.on_node.insert((entry.node, game.next_car_id - 1), entry.journey);
+                self.entering.remove(i);
+            } else {
+                blocked_entries.insert(place);
+                i += 1;
+            }
+        }
+
+        let mut blocked_links = HashSet::new();
+        let mut i = 0;
+        while i < self.in_transit.len() {
+            let transit = &self.in_transit[i];
+            if transit.arrive_at > self.sim_time || blocked_links.contains(&transit.link) {
+                i += 1;
+                continue;
+            }
+            let link = &self.links[transit.link];
+            let game = &mut self.nodes[link.to].game;
+            if game.spawn_car_in_lane(link.approach.clone(), transit.route.clone(), Some(transit.lane)) {
+                if let Some(car) = game.cars.last_mut() {
+                    car.driver = transit.driver;
+                }
+                self.on_node.insert((link.to, game.next_car_id - 1), transit.journey);
+                let taken = self.sim_time - transit.departed_at;
+                let recent = &mut self.link_times[transit.link];
+                *recent += (taken - *recent) * LINK_TIME_SMOOTHING;
+                if transit.waited {
+                    if let Some(journey) = self.journeys.get_mut(&transit.journey) {
+                        journey.stops += 1;
+                    }
+                }
+                self.in_transit.remove(i);
+            } else {
+                self.in_transit[i].waited = true;
+                blocked_links.insert(self.in_transit[i].link);
+                i += 1;
+            }
+        }
+    }
+
+    fn start_journey(&mut self, origin: usize, start_time: f32) -> usize {
+        let id = self.next_journey_id;
+        self.next_journey_id += 1;
+        self.journeys.insert(id, Journey {
+            id,
+            origin,
+            start_time,
+            hops: Vec::new(),
+            plan: VecDeque::new(),
+            destination: None,
+            rerouted: 0,
+            stops: 0,
+        });
+        id
+    }
+
+    fn end_journey(&mut self, journey_id: usize, destination: usize) {
+        let Some(journey) = self.journeys.remove(&journey_id) else {
+            return;
+        };
+        let path: Vec<&str> = journey.hops.iter().map(|&(node, _)| self.nodes[node].name.as_str()).collect();
+        self.finished.push(JourneyRecord {
+            id: journey.id,
+            origin: self.nodes[journey.origin].name.clone(),
+            destination: self.nodes[destination].name.clone(),
+            start_time: journey.start_time,
+            end_time: self.sim_time,
+            hops: journey.hops.len(),
+            path: path.join(">"),
+            rerouted: journey.rerouted,
+            stops: journey.stops,
+        });
+    }
+
+    pub fn finish(&mut self) {
+        self.app_state = AppState::StatsDisplay;
+        for node in &mut self.nodes {
+            node.game.finish();
+        }
+        if self.verbose {
+            for line in self.summary_lines() {
+                println!("{}", line);
+            }
+        }
+        if self.auto_export {
+            match self.export() {
+                Ok(paths) => {
+                    for path in paths {
+                        println!("Exported {}", path.display());
+                    }
+                }
+                Err(e) => println!("Export failed: {}", e),
+            }
+        }
+    }
+
+    // Per-node exports go to the same directory, named after the network and the node
+    pub fn set_export(&mut self, dir: &Path) {
+        self.export_dir = dir.to_path_buf();
+        self.auto_export = true;
+        for node in &mut self.nodes {
+            node.game.export_dir = dir.to_path_buf();
+            node.game.auto_export = true;
+        }
+    }
+
+    pub fn journey_times(&self) -> Distribution {
+        let times: Vec<f32> = self.finished.iter().map(JourneyRecord::travel_time).collect();
+        Distribution::from_values(&times)
+    }
+
+    // Sorted by origin then destination
+    pub fn od_summaries(&self) -> Vec<OdSummary> {
+        let mut pairs: Vec<(&str, &str)> = self.finished.iter().map(|j| (j.origin.as_str(), j.destination.as_str())).collect();
+        pairs.sort_unstable();
+        pairs.dedup();
+        pairs
+            .into_iter()
+            .map(|(origin, destination)| {
+                let journeys: Vec<&JourneyRecord> = self.finished
+                    .iter()
+                    .filter(|j| j.origin == origin && j.destination == destination)
+                    .collect();
+                let times: Vec<f32> = journeys.iter().map(|j| j.travel_time()).collect();
+                OdSummary {
+                    origin: origin.to_string(),
+                    destination: destination.to_string(),
+                    count: journeys.len(),
+                    travel_time: Distribution::from_values(&times),
+                    mean_hops: journeys.iter().map(|j| j.hops).sum::<usize>() as f32 / journeys.len() as f32,
+                    mean_stops: journeys.iter().map(|j| j.stops).sum::<usize>() as f32 / journeys.len() as f32,
+                    rerouted: journeys.iter().filter(|j| j.rerouted > 0).count(),
+                }
+            })
+            .collect()
+    }
+
+    pub fn summary_lines(&self) -> Vec<String> {
+        let times = self.journey_times();
+        let count = self.finished.len().max(1) as f32;
+        let hops = self.finished.iter().map(|j| j.hops).sum::<usize>() as f32 / count;
+        let stops = self.finished.iter().map(|j| j.stops).sum::<usize>() as f32 / count;
+        let on_nodes: usize = self.nodes.iter().map(|node| node.game.cars.len()).sum();
+        let mut lines = vec![
+            format!("Network {} after {:.1} s", self.name, self.sim_time),
+            format!("Journeys finished: {} ({} on links, {} on intersections)", self.finished.len(), self.in_transit.len(), on_nodes),
+            format!("Journey time: mean {:.2} s, median {:.2} s, p95 {:.2} s", times.mean, times.median, times.p95),
+            format!("Intersections per journey: {:.2}, stops per journey: {:.2}", hops, stops),
+            format!("Coordination: {}", if self.coordinate { "arrivals paced by the next node" } else { "independent nodes" }),
+        ];
+        for node in &self.nodes {
+            let stats = &node.game.stats;
+            lines.push(format!(
+                "  {}: {} cars passed, mean delay {:.2} s, {} crashes, {} deadlocks ({})",
+                node.name,
+                stats.cars_passed,
+                stats.delay_distribution().mean,
+                stats.crashes,
+                stats.deadlocks,
+                node.game.control_name(),
+            ));
+        }
+        for od in self.od_summaries() {
+            lines.push(format!(
+                "  {} -> {}: {} journeys, mean {:.2} s, p95 {:.2} s, {:.2} stops, {} rerouted",
+                od.origin,
+                od.destination,
+                od.count,
+                od.travel_time.mean,
+                od.travel_time.p95,
+                od.mean_stops,
+                od.rerouted,
+            ));
+        }
+        lines
+    }
+
+    // Journeys and their per-pair summary next to the nodes' own exports
+    pub fn export(&self) -> Result<Vec<PathBuf>, String> {
+        fs::create_dir_all(&self.export_dir).map_err(|e| format!("Could not create {}: {}", self.export_dir.display(), e))?;
+        let prefix = format!("{}-{}", self.name, self.seed);
+
+        let journeys_path = self.export_dir.join(format!("{}_journeys.csv", prefix));
+        let mut csv = String::from("seed,network,id,origin,destination,start_time,end_time,travel_time,hops,path,rerouted,stops\n");
+        for j in &self.finished {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{:.3},{:.3},{:.3},{},{},{},{}\n",
+                self.seed,
+                csv_field(&self.name),
+                j.id,
+                csv_field(&j.origin),
+                csv_field(&j.destination),
+                j.start_time,
+                j.end_time,
+                j.travel_time(),
+                j.hops,
+                csv_field(&j.path),
+                j.rerouted,
+                j.stops,
+            ));
+        }
+        write_file(&journeys_path, &csv)?;
+
+        let od_path = self.export_dir.join(format!("{}_od.csv", prefix));
+        let mut csv = String::from("seed,network,origin,destination,count,mean_travel_time,median_travel_time,p95_travel_time,mean_hops,mean_stops,rerouted\n");
+        for od in self.od_summaries() {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{:.3},{:.3},{:.3},{:.2},{:.2},{}\n",
+                self.seed,
+                csv_field(&self.name),
+                csv_field(&od.origin),
+                csv_field(&od.destination),
+                od.count,
+                od.travel_time.mean,
+                od.travel_time.median,
+                od.travel_time.p95,
+                od.mean_hops,
+                od.mean_stops,
+                od.rerouted,
+            ));
+        }
+        write_file(&od_path, &csv)?;
+        Ok(vec![journeys_path, od_path])
+    }
+
+    pub fn handle_events(&mut self, event_pump: &mut sdl2::EventPump) -> bool {
+        for event in event_pump.poll_iter() {
+            match event {
+                Event::Quit { .. } => return false,
+                Event::KeyDown { keycode: Some(Keycode::Escape), .. } => match self.app_state {
+                    AppState::Running => self.finish(),
+                    AppState::StatsDisplay => self.app_state = AppState::Exit,
+                    _ => {}
+                },
+                // Camera: whole network, then every node in turn
+                Event::KeyDown { keycode: Some(Keycode::Tab), .. } => {
+                    self.focus = match self.focus {
+                        None => Some(0),
+                        Some(i) if i + 1 < self.nodes.len() => Some(i + 1),
+                        Some(_) => None,
+                    };
+                }
+                Event::KeyDown { keycode: Some(Keycode::H), .. } => self.show_hud = !self.show_hud,
+                Event::KeyDown { keycode: Some(Keycode::Equals | Keycode::KpPlus), .. } => {
+                    self.speed_multiplier = next_speed_multiplier(self.speed_multiplier, 1);
+                }
+                Event::KeyDown { keycode: Some(Keycode::Minus | Keycode::KpMinus), .. } => {
+                    self.speed_multiplier = next_speed_multiplier(self.speed_multiplier, -1);
+                }
+                // Spawn keys go to the node in focus
+                Event::KeyDown { keycode: Some(keycode), .. } if self.app_state == AppState::Running => {
+                    if let Some(i) = self.focus {
+                        self.nodes[i].game.handle_car_spawn_input(keycode);
+                    }
+                }
+                _ => {}
+            }
+        }
+        self.app_state != AppState::Exit
+    }
+
+    pub fn render(&self, canvas: &mut Canvas<Window>, textures: &GameTextures, font: &Font, hud_font: &Font) -> Result<(), String> {
+        match (&self.app_state, self.focus) {
+            (AppState::Running, Some(i)) => {
+                let node = &self.nodes[i];
+                render_game(canvas, textures, &node.game.cars, &node.game.pedestrians, &node.game.scenario)?;
+                if self.show_hud {
+                    render_hud(canvas, hud_font, &self.hud_lines())?;
+                }
+                canvas.present();
+            }
+            (AppState::Running, None) => {
+                self.render_overview(canvas, textures, hud_font)?;
+                if self.show_hud {
+                    render_hud(canvas, hud_font, &self.hud_lines())?;
+                }
+                canvas.present();
+            }
+            (AppState::StatsDisplay, Some(i)) => {
+                render_stats(canvas, font, hud_font, &self.nodes[i].game.stats, self.sim_time, 0)?;
+            }
+            (AppState::StatsDisplay, None) => {
+                canvas.set_draw_color(Color::RGB(0, 0, 0));
+                canvas.clear();
+                draw_text(canvas, font, "Network Statistics", 300, 20, Color::WHITE)?;
+                for (row, line) in self.summary_lines().iter().enumerate() {
+                    draw_text(canvas, hud_font, line, 40, 80 + row as i32 * 24, Color::WHITE)?;
+                }
+                draw_text(canvas, hud_font, "Tab: node statistics    ESC: exit", 300, 650, Color::WHITE)?;
+                canvas.present();
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn hud_lines(&self) -> Vec<String> {
+        let view = match self.focus {
+            Some(i) => format!("Node {} ({})", self.nodes[i].name, self.nodes[i].game.control_name()),
+            None => "Whole network".to_string(),
+        };
+        vec![
+            view,
+            format!("Journeys finished: {}", self.finished.len()),
+            format!("Cars on links: {}", self.in_transit.len()),
+            format!("Sim time: {:.1} s", self.sim_time),
+            format!("Speed: x{}", self.speed_multiplier),
+            "Tab: change view".to_string(),
+        ]
+    }
+
+    // Screen rectangle of every node when the whole network is shown
+    fn node_rects(&self) -> Vec<Rect> {
+        let min_column = self.nodes.iter().map(|n| n.column).min().unwrap_or(0);
+        let min_row = self.nodes.iter().map(|n| n.row).min().unwrap_or(0);
+        let columns = (self.nodes.iter().map(|n| n.column).max().unwrap_or(0) - min_column + 1) as f32;
+        let rows = (self.nodes.iter().map(|n| n.row).max().unwrap_or(0) - min_row + 1) as f32;
+
+        let cell_width = WINDOW_WIDTH as f32 / columns;
+        let cell_height = WINDOW_HEIGHT as f32 / rows;
+        let scale = ((cell_width - NODE_GAP) / WINDOW_WIDTH as f32).min((cell_height - NODE_GAP) / WINDOW_HEIGHT as f32);
+        let (width, height) = (WINDOW_WIDTH as f32 * scale, WINDOW_HEIGHT as f32 * scale);
+
+        self.nodes
+            .iter()
+            .map(|node| {
+                let x = (node.column - min_column) as f32 * cell_width + (cell_width - width) / 2.0;
+                let y = (node.row - min_row) as f32 * cell_height + (cell_height - height) / 2.0;
+                Rect::new(x as i32, y as i32, width as u32, height as u32)
+            })
+            .collect()
+    }
+
+    fn render_overview(&self, canvas: &mut Canvas<Window>, textures: &GameTextures, font: &Font) -> Result<(), String> {
+        canvas.set_draw_color(Color::RGB(30, 30, 30));
+        canvas.clear();
+
+        let rects = self.node_rects();
+        for (node, rect) in self.nodes.iter().zip(&rects) {
+            draw_intersection(canvas, textures, &node.game.cars, &node.game.pedestrians, &node.game.scenario, Some(*rect))?;
+            draw_text(canvas, font, &node.name, rect.x() + 4, rect.y() + 4, Color::WHITE)?;
+        }
+
+        // Links as lines between node centres, cars on them as dots
+        for link in &self.links {
+            let (from, to) = (rects[link.from].center(), rects[link.to].center());
+            canvas.set_draw_color(Color::RGB(200, 200, 80));
+            canvas.draw_line(from, to)?;
+        }
+        canvas.set_draw_color(Color::RGB(255, 90, 60));
+        for transit in &self.in_transit {
+            let link = &self.links[transit.link];
+            let (from, to) = (rects[link.from].center(), rects[link.to].center());
+            let progress = (1.0 - (transit.arrive_at - self.sim_time) / link.travel_time).clamp(0.0, 1.0);
+            let x = from.x() as f32 + (to.x() - from.x()) as f32 * progress;
+            let y = from.y() as f32 + (to.y() - from.y()) as f32 * progress;
+            canvas.fill_rect(Rect::new(x as i32 - 3, y as i32 - 3, 6, 6))?;
+        }
+        Ok(())
+    }
+}
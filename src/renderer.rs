@@ -1,6 +1,13 @@
+use crate::crosswalk::{crosswalk_band, crosswalk_span, Pedestrian, PEDESTRIAN_SIZE};
+use crate::collision::car_footprint;
+use crate::lanes::{lane_position, LaneLayout, BIKE_LANE, CROSSING_X, CROSSING_Y, MAX_LANES};
+use crate::roundabout;
+use crate::scenario::Scenario;
+use crate::stats::Histogram;
 use crate::types::*;
 use sdl2::image::LoadTexture;
 use sdl2::pixels::Color;
+use sdl2::rect::{Point, Rect};
 use sdl2::render::{Canvas, Texture, TextureCreator, BlendMode};
 use sdl2::video::{Window, WindowContext};
 use sdl2::ttf::Font;
@@ -27,7 +34,44 @@ impl<'a> GameTextures<'a> {
     }
 }
 
-pub fn render_game(canvas: &mut Canvas<Window>, textures: &GameTextures, cars: &[Car]) -> Result<(), String> {
+// Verge and kerb colours of the background image
+const VERGE: Color = Color::RGB(232, 120, 180);
+const KERB: Color = Color::RGB(233, 214, 150);
+const KERB_WIDTH: f32 = 8.0;
+
+// Crosswalk stripes and the people on them
+const STRIPE: Color = Color::RGB(240, 240, 240);
+const STRIPE_WIDTH: f32 = 8.0;
+const PEDESTRIAN: Color = Color::RGB(40, 90, 220);
+
+// Bike lanes and the cyclists on them
+const BIKE_LANE_COLOR: Color = Color::RGB(70, 150, 90);
+const BICYCLE: Color = Color::RGB(250, 150, 30);
+
+// Dot on the roof of a human-driven car
+const HUMAN_DRIVER: Color = Color::RGB(220, 40, 40);
+
+pub fn render_game(
+    canvas: &mut Canvas<Window>,
+    textures: &GameTextures,
+    cars: &[Car],
+    pedestrians: &[Pedestrian],
+    scenario: &Scenario,
+) -> Result<(), String> {
+    canvas.set_draw_color(Color::RGB(0, 0, 0));
+    canvas.clear();
+    draw_intersection(canvas, textures, cars, pedestrians, scenario, None)
+}
+
+// Intersection with its cars and pedestrians scaled into `dst`, the whole window when None
+pub fn draw_intersection(
+    canvas: &mut Canvas<Window>,
+    textures: &GameTextures,
+    cars: &[Car],
+    pedestrians: &[Pedestrian],
+    scenario: &Scenario,
+    dst: Option<Rect>,
+) -> Result<(), String> {
     // Create a render target texture for off-screen rendering
     let texture_creator = canvas.texture_creator();
     let mut target_texture = texture_creator.create_texture_target(
@@ -50,16 +94,38 @@ pub fn render_game(canvas: &mut Canvas<Window>, textures: &GameTextures, cars: &
         if let Err(e) = texture_canvas.copy(&textures.background, None, Some(dst_rect)) {
             println!("Background copy error: {}", e);
         }
+        if let Err(e) = draw_closed_lanes(texture_canvas, &scenario.lanes) {
+            println!("Road draw error: {}", e);
+        }
+        if !scenario.bike_lanes().is_empty() {
+            if let Err(e) = draw_bike_lanes(texture_canvas, &scenario.lanes) {
+                println!("Bike lane draw error: {}", e);
+            }
+        }
+        for leg in scenario.crosswalks() {
+            if let Err(e) = draw_crosswalk(texture_canvas, &leg) {
+                println!("Crosswalk draw error: {}", e);
+            }
+        }
+        if scenario.junction == Junction::Roundabout {
+            if let Err(e) = draw_roundabout_island(texture_canvas) {
+                println!("Roundabout draw error: {}", e);
+            }
+        }
 
-        // Draw cars with rotation
+        // Draw cars with rotation, bicycles as a plain block
         for car in cars {
-            let car_width = 32;
-            let car_height = 60;
+            if car.kind == VehicleKind::Bicycle {
+                if let Err(e) = draw_bicycle(texture_canvas, car) {
+                    println!("Bicycle draw error: {}", e);
+                }
+                continue;
+            }
             let dst_rect = sdl2::rect::Rect::new(
-                (car.x - car_width as f32 / 2.0) as i32,
-                (car.y - car_height as f32 / 2.0) as i32,
-                car_width,
-                car_height,
+                (car.x - CAR_WIDTH / 2.0) as i32,
+                (car.y - CAR_LENGTH / 2.0) as i32,
+                CAR_WIDTH as u32,
+                CAR_LENGTH as u32,
             );
             
             let angle_degrees = car.rotation * 180.0 / PI;
@@ -76,88 +142,463 @@ pub fn render_game(canvas: &mut Canvas<Window>, textures: &GameTextures, cars: &
             ) {
                 println!("Car copy error: {}", e);
             }
+            if car.driver == Driver::Human {
+                if let Err(e) = fill_circle(texture_canvas, car.x, car.y, CAR_WIDTH / 4.0, HUMAN_DRIVER) {
+                    println!("Driver draw error: {}", e);
+                }
+            }
+        }
+
+        for pedestrian in pedestrians {
+            let (x, y) = pedestrian.position();
+            if let Err(e) = fill_circle(texture_canvas, x, y, PEDESTRIAN_SIZE / 2.0, PEDESTRIAN) {
+                println!("Pedestrian draw error: {}", e);
+            }
         }
     }).map_err(|e| e.to_string())?;
     
-    canvas.set_draw_color(Color::RGB(0, 0, 0));
-    canvas.clear();
-    
     canvas.copy_ex(
         &target_texture,
         None,
-        None,
+        dst,
         0.0,
         None,
         false,
         true,
     )?;
 
-    canvas.present();
     Ok(())
 }
 
-pub fn render_stats(canvas: &mut Canvas<Window>, font: &Font, stats: &Stats) -> Result<(), String> {
+// Sideways coordinate where the first `lanes` slots of a road end, the centre line for none
+fn lane_edge(direction: &Direction, lanes: usize) -> f32 {
+    let centre = match direction {
+        Direction::North | Direction::South => roundabout::CENTRE.0,
+        Direction::East | Direction::West => roundabout::CENTRE.1,
+    };
+    match lanes {
+        0 => centre,
+        n if n < MAX_LANES => (lane_position(direction, n - 1) + lane_position(direction, n)) / 2.0,
+        _ => {
+            let last = lane_position(direction, MAX_LANES - 1);
+            last + (last - lane_position(direction, MAX_LANES - 2)) / 2.0
+        }
+    }
+}
+
+// Road outside the crossing on the side cars heading `side` leave by: along the road, then
+// across it
+fn leg_span(side: &Direction) -> ((f32, f32), (f32, f32)) {
+    let (width, height) = (WINDOW_WIDTH as f32, WINDOW_HEIGHT as f32);
+    match side {
+        Direction::North => ((CROSSING_Y.1, height), CROSSING_X),
+        Direction::South => ((0.0, CROSSING_Y.0), CROSSING_X),
+        Direction::East => ((CROSSING_X.1, width), CROSSING_Y),
+        Direction::West => ((0.0, CROSSING_X.0), CROSSING_Y),
+    }
+}
+
+fn fill_leg_rect(canvas: &mut Canvas<Window>, side: &Direction, across: (f32, f32), color: Color) -> Result<(), String> {
+    let ((along_from, along_to), _) = leg_span(side);
+    let (across_from, across_to) = (across.0.min(across.1), across.0.max(across.1));
+    let (x, y, w, h) = match side {
+        Direction::North | Direction::South => (across_from, along_from, across_to - across_from, along_to - along_from),
+        Direction::East | Direction::West => (along_from, across_from, along_to - along_from, across_to - across_from),
+    };
+    if w < 1.0 || h < 1.0 {
+        return Ok(());
+    }
+    canvas.set_draw_color(color);
+    canvas.fill_rect(Rect::new(x as i32, y as i32, w as u32, h as u32))
+}
+
+// Lanes the layout doesn't have are grassed over with a kerb along what is left of the road, a
+// side without any lanes loses its whole road
+fn draw_closed_lanes(canvas: &mut Canvas<Window>, layout: &LaneLayout) -> Result<(), String> {
+    for side in Direction::ALL {
+        // Cars leave by this side heading `side` and arrive on it heading the other way
+        let halves = [(side.clone(), layout.exit_lanes(&side)), (side.opposite(), layout.lanes(&side.opposite()).len())];
+        if halves.iter().all(|(_, lanes)| *lanes == 0) {
+            let (_, across) = leg_span(&side);
+            fill_leg_rect(canvas, &side, across, VERGE)?;
+            // Kerb across the mouth of the missing road
+            let ((along_from, along_to), _) = leg_span(&side);
+            let mouth = match side {
+                Direction::North | Direction::East => along_from,
+                Direction::South | Direction::West => along_to - KERB_WIDTH,
+            };
+            let (x, y, w, h) = match side {
+                Direction::North | Direction::South => (across.0, mouth, across.1 - across.0, KERB_WIDTH),
+                Direction::East | Direction::West => (mouth, across.0, KERB_WIDTH, across.1 - across.0),
+            };
+            canvas.set_draw_color(KERB);
+            canvas.fill_rect(Rect::new(x as i32, y as i32, w as u32, h as u32))?;
+            continue;
+        }
+        for (heading, lanes) in halves {
+            if lanes >= MAX_LANES {
+                continue;
+            }
+            let edge = lane_edge(&heading, lanes);
+            let outer = lane_edge(&heading, MAX_LANES);
+            fill_leg_rect(canvas, &side, (edge, outer), VERGE)?;
+            let kerb = edge + KERB_WIDTH * (outer - edge).signum();
+            fill_leg_rect(canvas, &side, (edge, kerb), KERB)?;
+        }
+    }
+    Ok(())
+}
+
+// Kerb slot of every road that has one, outside the crossing
+fn draw_bike_lanes(canvas: &mut Canvas<Window>, layout: &LaneLayout) -> Result<(), String> {
+    for side in Direction::ALL {
+        for heading in [side.clone(), side.opposite()] {
+            let open = if heading == side { layout.exit_lanes(&side) > 0 } else { !layout.lanes(&heading).is_empty() };
+            if open {
+                let across = (lane_edge(&heading, BIKE_LANE), lane_edge(&heading, MAX_LANES));
+                fill_leg_rect(canvas, &side, across, BIKE_LANE_COLOR)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn draw_bicycle(canvas: &mut Canvas<Window>, bicycle: &Car) -> Result<(), String> {
+    let corners = car_footprint(bicycle);
+    let (min_x, max_x) = corners.iter().fold((f32::MAX, f32::MIN), |(lo, hi), c| (lo.min(c.x), hi.max(c.x)));
+    let (min_y, max_y) = corners.iter().fold((f32::MAX, f32::MIN), |(lo, hi), c| (lo.min(c.y), hi.max(c.y)));
+    canvas.set_draw_color(BICYCLE);
+    canvas.fill_rect(Rect::new(min_x as i32, min_y as i32, (max_x - min_x) as u32, (max_y - min_y) as u32))
+}
+
+// Zebra stripes along the road, kerb to kerb
+fn draw_crosswalk(canvas: &mut Canvas<Window>, leg: &Direction) -> Result<(), String> {
+    let (band_from, band_to) = crosswalk_band(leg);
+    let (span_from, span_to) = crosswalk_span(leg);
+    canvas.set_draw_color(STRIPE);
+    let mut across = span_from + STRIPE_WIDTH / 2.0;
+    while across + STRIPE_WIDTH <= span_to {
+        let (x, y, w, h) = match leg {
+            Direction::North | Direction::South => (across, band_from, STRIPE_WIDTH, band_to - band_from),
+            Direction::East | Direction::West => (band_from, across, band_to - band_from, STRIPE_WIDTH),
+        };
+        canvas.fill_rect(Rect::new(x as i32, y as i32, w as u32, h as u32))?;
+        across += STRIPE_WIDTH * 2.0;
+    }
+    Ok(())
+}
+
+// Central island inside the circulating lane, drawn over the middle of the crossing. Scene
+// coordinates are the simulation's, the scene is flipped when copied to the window.
+fn draw_roundabout_island(canvas: &mut Canvas<Window>) -> Result<(), String> {
+    let (cx, cy) = roundabout::CENTRE;
+    let kerb = roundabout::RADIUS - CAR_WIDTH;
+    fill_circle(canvas, cx, cy, kerb + 4.0, KERB)?;
+    fill_circle(canvas, cx, cy, kerb - 6.0, VERGE)
+}
+
+fn fill_circle(canvas: &mut Canvas<Window>, cx: f32, cy: f32, radius: f32, color: Color) -> Result<(), String> {
+    canvas.set_draw_color(color);
+    let r = radius as i32;
+    for dy in -r..=r {
+        let half = ((radius * radius - (dy * dy) as f32).max(0.0)).sqrt() as i32;
+        let y = cy as i32 + dy;
+        canvas.draw_line(Point::new(cx as i32 - half, y), Point::new(cx as i32 + half, y))?;
+    }
+    Ok(())
+}
+
+pub fn render_hud(canvas: &mut Canvas<Window>, font: &Font, lines: &[String]) -> Result<(), String> {
+    let line_height = font.recommended_line_spacing();
+    
+    // Translucent panel behind the text
+    let panel_height = (lines.len() as i32 * line_height + 16) as u32;
+    canvas.set_blend_mode(BlendMode::Blend);
+    canvas.set_draw_color(Color::RGBA(0, 0, 0, 170));
+    canvas.fill_rect(Rect::new(8, 8, 240, panel_height))?;
+    canvas.set_blend_mode(BlendMode::None);
+
+    for (i, line) in lines.iter().enumerate() {
+        draw_text(canvas, font, line, 16, 16 + (i as i32) * line_height, Color::WHITE)?;
+    }
+
+    Ok(())
+}
+
+pub const STATS_PAGES: usize = 2;
+
+pub fn render_stats(
+    canvas: &mut Canvas<Window>,
+    font: &Font,
+    small_font: &Font,
+    stats: &Stats,
+    sim_time: f32,
+    page: usize,
+) -> Result<(), String> {
     // Clear with black background
     canvas.set_draw_color(Color::RGB(0, 0, 0));
     canvas.clear();
 
-    let texture_creator = canvas.texture_creator();
-    
     // Title
-    let title_surface = font.render("Simulation Statistics")
-        .blended(Color::WHITE)
-        .map_err(|e| e.to_string())?;
-    let title_texture = texture_creator.create_texture_from_surface(&title_surface)
-        .map_err(|e| e.to_string())?;
-    let title_rect = sdl2::rect::Rect::new(300, 50, title_surface.width(), title_surface.height());
-    canvas.copy(&title_texture, None, Some(title_rect))?;
+    let title = format!("Simulation Statistics ({}/{})", page + 1, STATS_PAGES);
+    draw_text(canvas, font, &title, 300, 20, Color::WHITE)?;
+
+    match page {
+        0 => render_stats_overview(canvas, small_font, stats, sim_time)?,
+        _ => render_movement_table(canvas, small_font, stats, sim_time)?,
+    }
 
+    // Exit instruction
+    draw_text(
+        canvas,
+        small_font,
+        "Left/Right: change page    E: export    F9: load snapshot    ESC: exit",
+        300,
+        650,
+        Color::WHITE,
+    )?;
+
+    canvas.present();
+    Ok(())
+}
+
+fn render_stats_overview(canvas: &mut Canvas<Window>, font: &Font, stats: &Stats, sim_time: f32) -> Result<(), String> {
     // Stats text
     let min_travel_time = if stats.min_time.as_secs_f32() == 1000.0 {
         0.0
     } else {
         stats.min_time.as_secs_f32()
     };
+    let delay = stats.delay_distribution();
+    let pedestrian_wait = stats.pedestrian_wait_distribution();
+    let cyclist_delay = stats.cyclist_delay_distribution();
+    let autonomous_delay = stats.driver_delay_distribution(Driver::Autonomous);
+    let human_delay = stats.driver_delay_distribution(Driver::Human);
     
-    let stats_lines = vec![
+    let stats_lines = [
         format!("Total Cars: {}", stats.max_number_cars),
         format!("Maximum Speed: {:.2} units/s", stats.max_velocity),
         format!("Minimum Speed: {:.2} units/s", stats.min_velocity),
         format!("Maximum Travel Time: {:.2} seconds", stats.max_time.as_secs_f32()),
         format!("Minimum Travel Time: {:.2} seconds", min_travel_time),
-        format!("Close Calls: {}", stats.close_call),
+        format!("Close Calls: {} (min TTC {:.2} s, mean PET {:.2} s)", stats.close_call, stats.ttc_distribution().min, stats.pet_distribution().mean),
+        format!("Throughput: {:.0} vehicles/hour", stats.throughput_per_hour(sim_time)),
+        format!("Delay: mean {:.2} s, median {:.2} s, p95 {:.2} s", delay.mean, delay.median, delay.p95),
+        format!("Crashes: {}, deadlocks resolved: {}, unresolved: {}", stats.crashes, stats.deadlocks, stats.unresolved_deadlocks),
+        format!("Pedestrians across: {} (wait mean {:.2} s, max {:.2} s)", stats.pedestrian_waits.len(), pedestrian_wait.mean, pedestrian_wait.max),
+        format!("Cyclists through: {} (delay mean {:.2} s), right hooks: {}", cyclist_delay.count, cyclist_delay.mean, stats.right_hooks),
+        format!(
+            "Connected: {} (delay {:.2} s), human: {} (delay {:.2} s, {} close calls)",
+            autonomous_delay.count, autonomous_delay.mean, human_delay.count, human_delay.mean, stats.human_close_calls
+        ),
     ];
 
-    // Render each stat line
+    // Two columns of text above the charts
+    let rows = stats_lines.len().div_ceil(2) as i32;
     for (i, line) in stats_lines.iter().enumerate() {
-        let surface = font.render(line)
-            .blended(Color::WHITE)
-            .map_err(|e| e.to_string())?;
-        let texture = texture_creator.create_texture_from_surface(&surface)
-            .map_err(|e| e.to_string())?;
-        let rect = sdl2::rect::Rect::new(
-            200, 
-            150 + (i as i32) * 50, 
-            surface.width(), 
-            surface.height()
-        );
-        canvas.copy(&texture, None, Some(rect))?;
+        let x = 40 + (i as i32 / rows) * 480;
+        let y = 66 + (i as i32 % rows) * (120 / rows);
+        draw_text(canvas, font, line, x, y, Color::WHITE)?;
     }
 
-    // Exit instruction
-    let exit_surface = font.render("Press ESC again to exit")
-        .blended(Color::WHITE)
+    let travel_times = stats.travel_time_histogram();
+    draw_histogram(
+        canvas,
+        font,
+        Rect::new(40, 190, 450, 200),
+        "Travel time (s)",
+        &travel_times,
+        Color::RGB(80, 160, 255),
+    )?;
+
+    let throughput = stats.throughput_series(10.0, sim_time);
+    draw_line_chart(
+        canvas,
+        font,
+        Rect::new(530, 190, 450, 200),
+        "Throughput over time (veh/h)",
+        &throughput,
+        Color::RGB(120, 220, 120),
+    )?;
+
+    let delays: Vec<(String, f32)> = stats
+        .approach_delays()
+        .into_iter()
+        .map(|(direction, delay)| (format!("{:?}", direction), delay))
+        .collect();
+    draw_bar_chart(
+        canvas,
+        font,
+        Rect::new(40, 430, 450, 200),
+        "Average delay per approach (s)",
+        &delays,
+        Color::RGB(255, 170, 60),
+    )?;
+
+    let speeds = stats.speed_histogram();
+    draw_histogram(
+        canvas,
+        font,
+        Rect::new(530, 430, 450, 200),
+        "Average trip speed (units/s)",
+        &speeds,
+        Color::RGB(220, 100, 220),
+    )?;
+
+    Ok(())
+}
+
+fn render_movement_table(canvas: &mut Canvas<Window>, font: &Font, stats: &Stats, sim_time: f32) -> Result<(), String> {
+    let columns = ["Direction", "Route", "Cars", "Veh/h", "Travel", "Delay", "Median", "P95", "Stopped"];
+    let column_x = [40, 150, 250, 330, 420, 520, 620, 720, 820];
+    let row_height = 28;
+
+    for (title, &x) in columns.iter().zip(column_x.iter()) {
+        draw_text(canvas, font, title, x, 80, Color::RGB(255, 220, 120))?;
+    }
+    canvas.set_draw_color(Color::RGB(120, 120, 120));
+    canvas.draw_line((40, 108), (900, 108))?;
+
+    let summaries = stats.movement_summaries(sim_time);
+    if summaries.is_empty() {
+        draw_text(canvas, font, "No car has finished its trip yet", 40, 120, Color::WHITE)?;
+    }
+
+    for (row, summary) in summaries.iter().enumerate() {
+        let cells = [
+            format!("{:?}", summary.direction),
+            format!("{:?}", summary.route),
+            summary.count.to_string(),
+            format!("{:.0}", summary.throughput_per_hour),
+            format!("{:.2} s", summary.travel_time.mean),
+            format!("{:.2} s", summary.delay.mean),
+            format!("{:.2} s", summary.delay.median),
+            format!("{:.2} s", summary.delay.p95),
+            format!("{:.2} s", summary.stopped_time.mean),
+        ];
+        let y = 120 + row as i32 * row_height;
+        for (cell, &x) in cells.iter().zip(column_x.iter()) {
+            draw_text(canvas, font, cell, x, y, Color::WHITE)?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn draw_text(canvas: &mut Canvas<Window>, font: &Font, text: &str, x: i32, y: i32, color: Color) -> Result<(), String> {
+    let texture_creator = canvas.texture_creator();
+    let surface = font.render(text)
+        .blended(color)
         .map_err(|e| e.to_string())?;
-    let exit_texture = texture_creator.create_texture_from_surface(&exit_surface)
+    let texture = texture_creator.create_texture_from_surface(&surface)
         .map_err(|e| e.to_string())?;
-    let exit_rect = sdl2::rect::Rect::new(
-        350, 
-        550, 
-        exit_surface.width(), 
-        exit_surface.height()
-    );
-    canvas.copy(&exit_texture, None, Some(exit_rect))?;
+    let rect = Rect::new(x, y, surface.width(), surface.height());
+    canvas.copy(&texture, None, Some(rect))
+}
 
-    canvas.present();
+// Frame and title shared by all charts, returns the plotting area
+fn draw_chart_frame(canvas: &mut Canvas<Window>, font: &Font, rect: Rect, title: &str, max_value: f32) -> Result<Rect, String> {
+    draw_text(canvas, font, title, rect.x(), rect.y(), Color::WHITE)?;
+
+    let plot = Rect::new(rect.x() + 50, rect.y() + 24, rect.width() - 60, rect.height() - 48);
+    canvas.set_draw_color(Color::RGB(120, 120, 120));
+    canvas.draw_line((plot.left(), plot.top()), (plot.left(), plot.bottom()))?;
+    canvas.draw_line((plot.left(), plot.bottom()), (plot.right(), plot.bottom()))?;
+
+    // Y axis scale
+    draw_text(canvas, font, &format!("{:.0}", max_value), rect.x(), plot.top(), Color::GREY)?;
+    draw_text(canvas, font, "0", rect.x(), plot.bottom() - 16, Color::GREY)?;
+
+    Ok(plot)
+}
+
+fn draw_bars(canvas: &mut Canvas<Window>, plot: Rect, values: &[f32], max_value: f32, color: Color) -> Result<(), String> {
+    if values.is_empty() || max_value <= 0.0 {
+        return Ok(());
+    }
+
+    let slot = plot.width() as f32 / values.len() as f32;
+    canvas.set_draw_color(color);
+    for (i, &value) in values.iter().enumerate() {
+        let height = (value / max_value * plot.height() as f32) as u32;
+        if height == 0 {
+            continue;
+        }
+        let x = plot.left() + (i as f32 * slot) as i32 + 2;
+        let bar = Rect::new(x, plot.bottom() - height as i32, (slot as u32).saturating_sub(4).max(1), height);
+        canvas.fill_rect(bar)?;
+    }
     Ok(())
-}
\ No newline at end of file
+}
+
+pub fn draw_histogram(
+    canvas: &mut Canvas<Window>,
+    font: &Font,
+    rect: Rect,
+    title: &str,
+    histogram: &Histogram,
+    color: Color,
+) -> Result<(), String> {
+    let values: Vec<f32> = histogram.counts.iter().map(|&c| c as f32).collect();
+    let max_value = values.iter().copied().fold(0.0, f32::max);
+    let plot = draw_chart_frame(canvas, font, rect, title, max_value)?;
+    draw_bars(canvas, plot, &values, max_value, color)?;
+
+    // X axis range
+    let max_x = histogram.min + histogram.bin_width * histogram.counts.len() as f32;
+    draw_text(canvas, font, &format!("{:.1}", histogram.min), plot.left(), plot.bottom() + 2, Color::GREY)?;
+    draw_text(canvas, font, &format!("{:.1}", max_x), plot.right() - 40, plot.bottom() + 2, Color::GREY)?;
+    Ok(())
+}
+
+pub fn draw_bar_chart(
+    canvas: &mut Canvas<Window>,
+    font: &Font,
+    rect: Rect,
+    title: &str,
+    bars: &[(String, f32)],
+    color: Color,
+) -> Result<(), String> {
+    let values: Vec<f32> = bars.iter().map(|(_, v)| *v).collect();
+    let max_value = values.iter().copied().fold(0.0, f32::max);
+    let plot = draw_chart_frame(canvas, font, rect, title, max_value)?;
+    draw_bars(canvas, plot, &values, max_value, color)?;
+
+    // Label under each bar
+    let slot = plot.width() as f32 / bars.len().max(1) as f32;
+    for (i, (label, _)) in bars.iter().enumerate() {
+        let x = plot.left() + (i as f32 * slot) as i32 + 4;
+        draw_text(canvas, font, label, x, plot.bottom() + 2, Color::GREY)?;
+    }
+    Ok(())
+}
+
+pub fn draw_line_chart(
+    canvas: &mut Canvas<Window>,
+    font: &Font,
+    rect: Rect,
+    title: &str,
+    points: &[(f32, f32)],
+    color: Color,
+) -> Result<(), String> {
+    let max_value = points.iter().map(|&(_, y)| y).fold(0.0, f32::max);
+    let max_x = points.iter().map(|&(x, _)| x).fold(0.0, f32::max);
+    let plot = draw_chart_frame(canvas, font, rect, title, max_value)?;
+
+    if points.len() > 1 && max_value > 0.0 && max_x > 0.0 {
+        let to_screen = |(x, y): (f32, f32)| {
+            (
+                plot.left() + (x / max_x * plot.width() as f32) as i32,
+                plot.bottom() - (y / max_value * plot.height() as f32) as i32,
+            )
+        };
+        canvas.set_draw_color(color);
+        for pair in points.windows(2) {
+            canvas.draw_line(to_screen(pair[0]), to_screen(pair[1]))?;
+        }
+    }
+
+    draw_text(canvas, font, "0 s", plot.left(), plot.bottom() + 2, Color::GREY)?;
+    draw_text(canvas, font, &format!("{:.0} s", max_x), plot.right() - 40, plot.bottom() + 2, Color::GREY)?;
+    Ok(())
+}
@@ -0,0 +1,196 @@
+use crate::lanes::{exit_direction, heading_rotation, lane_position, lateral};
+use crate::types::*;
+use std::f32::consts::{PI, TAU};
+
+// Where the centre lines of the two roads cross
+pub const CENTRE: (f32, f32) = (507.5, 332.5);
+
+// Cars circulate on this radius, counter-clockwise on screen like right-hand traffic
+pub const RADIUS: f32 = 190.0;
+
+// Speed on the ring, every car slows down to it
+pub const CIRCULATING_SPEED: f32 = MEDIUM;
+
+// Circulating cars' corners stay inside this radius, entering cars wait outside it
+const CLEAR_RADIUS: f32 = RADIUS + 25.0;
+
+// Space circulating cars keep to the car ahead of them on the ring
+pub const RING_GAP: f32 = 15.0;
+
+// Seconds a circulating car has to be away from an entry for a car to pull out in front of it
+pub const CRITICAL_GAP: f32 = 1.5;
+
+// Leaving the map at any edge
+const MAP_EDGE: (f32, f32, f32, f32) = (-50.0, -50.0, 1074.0, 732.0);
+
+// Angle on the ring where a road crosses it. Roads are straight lines at `lateral` across their
+// heading, `arriving` picks the side a car comes in from, otherwise the side it leaves by.
+pub fn crossing_angle(heading: &Direction, lateral: f32, arriving: bool) -> f32 {
+    let (cx, cy) = CENTRE;
+    match heading {
+        Direction::North | Direction::South => {
+            let angle = ((lateral - cx) / RADIUS).clamp(-1.0, 1.0).acos();
+            // Cars heading north come in at the bottom and leave at the top
+            if (*heading == Direction::North) == arriving { -angle } else { angle }
+        }
+        Direction::East | Direction::West => {
+            let angle = ((lateral - cy) / RADIUS).clamp(-1.0, 1.0).asin();
+            // Cars heading east come in on the left and leave on the right
+            if (*heading == Direction::East) == arriving { PI - angle } else { angle }
+        }
+    }
+}
+
+pub fn ring_point(angle: f32) -> (f32, f32) {
+    (CENTRE.0 + RADIUS * angle.cos(), CENTRE.1 + RADIUS * angle.sin())
+}
+
+// Angle of a point around the centre
+fn angle_of(x: f32, y: f32) -> f32 {
+    (y - CENTRE.1).atan2(x - CENTRE.0)
+}
+
+// Ring distance from angle `from` counter-clockwise to `to`
+fn arc_between(from: f32, to: f32) -> f32 {
+    (to - from).rem_euclid(TAU) * RADIUS
+}
+
+pub fn entry_angle(car: &Car) -> f32 {
+    crossing_angle(&car.direction, lateral(car), true)
+}
+
+// Turns map to exits around the ring, the car leaves on its own lane's slot of the exit road.
+// A U-turn's exit is its own road, so it goes nearly all the way round past the other three.
+pub fn exit_angle(car: &Car) -> f32 {
+    let exit = exit_direction(&car.direction, &car.route);
+    crossing_angle(&exit, lane_position(&exit, car.lane), false)
+}
+
+// Distance left on the approach before the car joins the ring
+pub fn distance_to_entry(car: &Car) -> f32 {
+    let (x, y) = ring_point(entry_angle(car));
+    match car.direction {
+        Direction::North => y - car.y,
+        Direction::South => car.y - y,
+        Direction::East => x - car.x,
+        Direction::West => car.x - x,
+    }
+}
+
+// How far before the point where it joins the ring a car waits: its front corners just clear
+// of circulating cars. Lanes that meet the ring at a shallow angle wait further back.
+pub fn yield_setback(car: &Car) -> f32 {
+    let centre_line = match car.direction {
+        Direction::North | Direction::South => CENTRE.0,
+        Direction::East | Direction::West => CENTRE.1,
+    };
+    let offset = (lateral(car) - centre_line).abs().min(RADIUS);
+    let inner_corner = (offset - CAR_WIDTH / 2.0).max(0.0);
+    let front_clear = (CLEAR_RADIUS * CLEAR_RADIUS - inner_corner * inner_corner).sqrt();
+    let entry = (RADIUS * RADIUS - offset * offset).sqrt();
+    front_clear + CAR_LENGTH / 2.0 - entry
+}
+
+fn step_along(x: &mut f32, y: &mut f32, heading: &Direction, distance: f32) {
+    match heading {
+        Direction::North => *y += distance,
+        Direction::South => *y -= distance,
+        Direction::East => *x += distance,
+        Direction::West => *x -= distance,
+    }
+}
+
+// Drive up to the ring, around it to the exit and out. `entered` is set on the ring and
+// `rotated` once the car has left it.
+pub fn move_roundabout(car: &mut Car, delta_time: f32) {
+    if !car.entered {
+        let step = car.speed * delta_time;
+        let left = distance_to_entry(car);
+        if step < left {
+            step_along(&mut car.x, &mut car.y, &car.direction, step);
+        } else {
+            let angle = entry_angle(car);
+            (car.x, car.y) = ring_point(angle);
+            car.rotation = angle; // the sprite faces north at angle 0, where the ring heads north
+            car.speed = CIRCULATING_SPEED;
+            car.entered = true;
+        }
+    } else if !car.rotated {
+        let angle = angle_of(car.x, car.y);
+        let exit = exit_angle(car);
+        let step = car.speed * delta_time;
+        if step < arc_between(angle, exit) {
+            let next = angle + step / RADIUS;
+            (car.x, car.y) = ring_point(next);
+            car.rotation = next;
+        } else {
+            (car.x, car.y) = ring_point(exit);
+            let heading = exit_direction(&car.direction, &car.route);
+            car.rotation = heading_rotation(&heading);
+            car.rotated = true;
+            car.speed = car.max_speed;
+        }
+    } else {
+        let heading = exit_direction(&car.direction, &car.route);
+        step_along(&mut car.x, &mut car.y, &heading, car.speed * delta_time);
+        let (min_x, min_y, max_x, max_y) = MAP_EDGE;
+        if car.x < min_x || car.x > max_x || car.y < min_y || car.y > max_y {
+            car.moving = false;
+        }
+    }
+}
+
+// Where a car is or will be on the ring: angle and the ring distance it still has to go.
+// Cars past the yield line count as already on the ring at their entry.
+fn ring_position(car: &Car) -> Option<(f32, f32)> {
+    if car.rotated {
+        return None;
+    }
+    let angle = if car.entered {
+        angle_of(car.x, car.y)
+    } else if distance_to_entry(car) < yield_setback(car) {
+        entry_angle(car)
+    } else {
+        return None;
+    };
+    Some((angle, arc_between(angle, exit_angle(car))))
+}
+
+// Car the car at `index` has to wait for this step. On the ring that is the car just ahead.
+// Before the yield line it is circulating traffic arriving at its entry within the critical
+// gap, or a car that just went past it.
+pub fn give_way_to(cars: &[Car], index: usize, delta_time: f32) -> Option<usize> {
+    let car = &cars[index];
+    let on_ring = cars
+        .iter()
+        .enumerate()
+        .filter(|&(i, _)| i != index)
+        .filter_map(|(_, other)| ring_position(other).map(|(angle, to_exit)| (other, angle, to_exit)));
+
+    if car.rotated {
+        return None;
+    }
+    if car.entered {
+        let angle = angle_of(car.x, car.y);
+        return on_ring
+            .filter(|&(_, other, _)| arc_between(angle, other) < CAR_LENGTH + RING_GAP)
+            .map(|(other, _, _)| other.id)
+            .next();
+    }
+
+    let left = distance_to_entry(car) - yield_setback(car);
+    if left < 0.0 || left > car.speed * delta_time {
+        return None;
+    }
+    let entry = entry_angle(car);
+    on_ring
+        .filter(|&(_, angle, to_exit)| {
+            let coming = arc_between(angle, entry);
+            let ahead = arc_between(entry, angle);
+            // A car that leaves a car length before it gets here doesn't matter
+            let leaves_first = to_exit + CAR_LENGTH + RING_GAP < coming;
+            (coming < CIRCULATING_SPEED * CRITICAL_GAP && !leaves_first) || ahead < CAR_LENGTH + RING_GAP
+        })
+        .map(|(other, _, _)| other.id)
+        .next()
+}
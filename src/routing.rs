@@ -0,0 +1,85 @@
+use crate::lanes::exit_direction;
+use crate::network::Link;
+use crate::types::*;
+use std::collections::{HashMap, HashSet};
+
+// A car in the network before it crosses a node: the node and the approach it arrives on
+pub type Place = (usize, Direction);
+
+// Turns to take from the first place until the car leaves the network at its destination
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlannedPath {
+    pub start: Place,
+    pub routes: Vec<Route>, // one per node crossed
+    pub cost: f32, // seconds, at the costs it was planned with
+}
+
+// Approaches of a node no link arrives on, where cars can enter the network
+pub fn entry_approaches(links: &[Link], node: usize) -> Vec<Direction> {
+    Direction::ALL
+        .into_iter()
+        .filter(|direction| !links.iter().any(|link| link.to == node && link.approach == *direction))
+        .collect()
+}
+
+// Cheapest way from any of `starts` to leaving the network at `destination` (Dijkstra). The
+// costs are seconds for a turn at a node and for driving down a link.
+pub fn shortest_path(
+    links: &[Link],
+    starts: &[Place],
+    destination: usize,
+    turn_cost: impl Fn(usize, &Direction, &Route) -> f32,
+    link_cost: impl Fn(usize) -> f32,
+) -> Option<PlannedPath> {
+    let mut cost: HashMap<Place, f32> = starts.iter().map(|place| (place.clone(), 0.0)).collect();
+    let mut came_from: HashMap<Place, (Place, Route)> = HashMap::new();
+    let mut done: HashSet<Place> = HashSet::new();
+    let mut best: Option<(f32, Place, Route)> = None; // cheapest exit at the destination so far
+
+    // Graphs are small, a linear scan for the closest open place is enough
+    while let Some((place, place_cost)) = cost
+        .iter()
+        .filter(|(place, _)| !done.contains(*place))
+        .min_by(|a, b| a.1.total_cmp(b.1))
+        .map(|(place, &c)| (place.clone(), c))
+    {
+        if best.as_ref().is_some_and(|(best_cost, _, _)| *best_cost <= place_cost) {
+            break;
+        }
+        done.insert(place.clone());
+
+        let (node, approach) = &place;
+        for route in Route::ALL {
+            let heading = exit_direction(approach, &route);
+            let after_turn = place_cost + turn_cost(*node, approach, &route);
+            // Turns a node doesn't have cost infinity
+            if !after_turn.is_finite() {
+                continue;
+            }
+            match links.iter().position(|link| link.from == *node && link.approach == heading) {
+                Some(l) => {
+                    let next = (links[l].to, heading);
+                    let next_cost = after_turn + link_cost(l);
+                    if !done.contains(&next) && cost.get(&next).is_none_or(|&c| next_cost < c) {
+                        cost.insert(next.clone(), next_cost);
+                        came_from.insert(next, (place.clone(), route));
+                    }
+                }
+                None if *node == destination && best.as_ref().is_none_or(|(best_cost, _, _)| after_turn < *best_cost) => {
+                    best = Some((after_turn, place.clone(), route));
+                }
+                None => {}
+            }
+        }
+    }
+
+    // Walk back from the exit to the start
+    let (total, mut place, last_route) = best?;
+    let mut routes = vec![last_route];
+    while let Some((previous, route)) = came_from.get(&place) {
+        routes.push(route.clone());
+        place = previous.clone();
+    }
+    routes.reverse();
+    Some(PlannedPath { start: place, routes, cost: total })
+}
@@ -0,0 +1,177 @@
+use crate::collision::*;
+use crate::spatial::*;
+use crate::types::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// Default time-to-collision under which an encounter counts as a close call
+pub const TTC_THRESHOLD: f32 = 1.0;
+
+// One close call between two cars, open until both have left the map
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConflictEvent {
+    pub car_a: usize,
+    pub car_b: usize,
+    pub time: f32, // when the TTC first fell under the threshold
+    pub min_ttc: f32,
+    pub pet: Option<f32>, // post-encroachment time, None if a car never crossed the conflict point
+    #[serde(skip)]
+    pub progress: ConflictProgress,
+}
+
+// What an open event still watches for its PET, left out of exports
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConflictProgress {
+    point: Option<(f32, f32)>,
+    occupied: [Option<(f32, f32)>; 2], // first and last time each car covered the point
+    left: [bool; 2],
+}
+
+impl ConflictEvent {
+    fn new(a: &Car, b: &Car, time: f32, ttc: f32) -> Self {
+        let point = match (a.collision_types.first(), b.collision_types.first()) {
+            (Some(&type_a), Some(&type_b)) => conflict_point(type_a, type_b),
+            _ => None,
+        };
+        ConflictEvent {
+            car_a: a.id.min(b.id),
+            car_b: a.id.max(b.id),
+            time,
+            min_ttc: ttc,
+            pet: None,
+            progress: ConflictProgress {
+                point,
+                occupied: [None, None],
+                left: [false, false],
+            },
+        }
+    }
+
+    // Time from the first car clearing the conflict point to the second one reaching it
+    fn finish(&mut self) {
+        self.pet = match self.progress.occupied {
+            [Some(a), Some(b)] => {
+                let (first, second) = if a.0 <= b.0 { (a, b) } else { (b, a) };
+                Some((second.0 - first.1).max(0.0))
+            }
+            _ => None,
+        };
+    }
+}
+
+// Cars from different approaches whose paths cross, following cars are not conflicts. A car
+// turning right across a cyclist beside it is one.
+pub fn conflicting(a: &Car, b: &Car) -> bool {
+    (a.direction != b.direction || right_hook(a, b))
+        && a.collision_types
+            .iter()
+            .any(|t| *t != CollisionType::GG && b.collision_types.contains(t))
+}
+
+// Open or update close calls for every conflicting pair heading for each other, then watch
+// the conflict points of open events for PET. Runs after each step.
+pub fn measure_conflicts(
+    cars: &[Car],
+    threshold: f32,
+    now: f32,
+    open: &mut HashMap<(usize, usize), ConflictEvent>,
+    stats: &mut Stats,
+) {
+    // Two cars can only touch within the threshold if the areas they sweep through meet
+    let mut swept = SpatialGrid::new(CELL_SIZE);
+    for (i, car) in cars.iter().enumerate() {
+        swept.insert(i, swept_bounds(car, threshold));
+    }
+
+    for (i, car) in cars.iter().enumerate() {
+        if !car.moving {
+            continue;
+        }
+        for j in swept.query(swept_bounds(car, threshold)) {
+            let other = &cars[j];
+            if j <= i || !other.moving || !conflicting(car, other) {
+                continue;
+            }
+            if let Some(ttc) = time_to_collision(car, other, threshold) {
+                let pair = (car.id.min(other.id), car.id.max(other.id));
+                let event = open.entry(pair).or_insert_with(|| {
+                    stats.close_call += 1;
+                    if right_hook(car, other) {
+                        stats.right_hooks += 1;
+                    }
+                    if car.driver == Driver::Human || other.driver == Driver::Human {
+                        stats.human_close_calls += 1;
+                    }
+                    ConflictEvent::new(car, other, now, ttc)
+                });
+                event.min_ttc = event.min_ttc.min(ttc);
+            }
+        }
+    }
+
+    if open.is_empty() {
+        return;
+    }
+    let by_id: HashMap<usize, &Car> = cars.iter().map(|car| (car.id, car)).collect();
+    for event in open.values_mut() {
+        let Some((px, py)) = event.progress.point else {
+            continue;
+        };
+        for (k, id) in [event.car_a, event.car_b].into_iter().enumerate() {
+            if let Some(car) = by_id.get(&id) {
+                if contains_point(&car_footprint(car), px, py) {
+                    let occupied = event.progress.occupied[k].get_or_insert((now, now));
+                    occupied.1 = now;
+                }
+            }
+        }
+    }
+}
+
+// Everywhere the car's body can be within `threshold` seconds at its current velocity
+fn swept_bounds(car: &Car, threshold: f32) -> Aabb {
+    let now = car_bounds(car);
+    let dx = car.velocity.x * threshold;
+    let dy = car.velocity.y * threshold;
+    now.union(Aabb {
+        min_x: now.min_x + dx,
+        min_y: now.min_y + dy,
+        max_x: now.max_x + dx,
+        max_y: now.max_y + dy,
+    })
+}
+
+// A car left the map, events are closed once both of their cars are gone
+pub fn car_left(car_id: usize, open: &mut HashMap<(usize, usize), ConflictEvent>, stats: &mut Stats) {
+    let mut closed = Vec::new();
+    for (pair, event) in open.iter_mut() {
+        if event.car_a == car_id {
+            event.progress.left[0] = true;
+        } else if event.car_b == car_id {
+            event.progress.left[1] = true;
+        }
+        if event.progress.left == [true, true] {
+            closed.push(*pair);
+        }
+    }
+    closed.sort_unstable();
+    for pair in closed {
+        if let Some(event) = open.remove(&pair) {
+            close(event, stats);
+        }
+    }
+}
+
+// End of the run, whatever is still open is recorded as it stands
+pub fn close_all(open: &mut HashMap<(usize, usize), ConflictEvent>, stats: &mut Stats) {
+    let mut events: Vec<ConflictEvent> = open.drain().map(|(_, event)| event).collect();
+    events.sort_by(|a, b| a.time.total_cmp(&b.time).then((a.car_a, a.car_b).cmp(&(b.car_a, b.car_b))));
+    for event in events {
+        close(event, stats);
+    }
+}
+
+fn close(mut event: ConflictEvent, stats: &mut Stats) {
+    event.finish();
+    stats.conflicts.push(event);
+}
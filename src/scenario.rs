@@ -0,0 +1,311 @@
+use crate::crosswalk::has_road;
+use crate::lanes::LaneLayout;
+use crate::types::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::Path;
+
+// A car that appears at a fixed moment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledSpawn {
+    pub time: f32,
+    pub direction: Direction,
+    pub route: Route,
+    #[serde(default)]
+    pub lane: Option<usize>, // lane to appear in, a lane the route isn't allowed from means a lane change
+}
+
+// A car due to appear, the lane is picked at spawn time when None
+pub type Arrival = (Direction, Route, Option<usize>);
+
+// Traffic to run, loaded from a JSON file or built from command line flags
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Scenario {
+    pub name: String,
+    pub duration: Option<f32>, // simulated seconds, None runs until ESC
+    pub seed: Option<u64>,
+    pub manager: Option<String>,
+    pub rates: HashMap<Direction, f32>, // cars per minute per approach
+    pub route_weights: HashMap<Route, f32>,
+    pub spawns: Vec<ScheduledSpawn>,
+    pub lanes: LaneLayout,
+    pub lane_change_share: f32, // random arrivals that appear in any lane and change lanes to their own
+    pub junction: Junction,
+    pub pedestrian_rates: HashMap<Direction, f32>, // people per minute crossing each leg, keyed like exits
+    pub cyclist_rates: HashMap<Direction, f32>, // cyclists per minute riding straight through from each approach
+    pub penetration: f32, // share of cars that are connected and follow the manager, the rest are driven by humans
+}
+
+impl Default for Scenario {
+    fn default() -> Self {
+        Scenario {
+            name: "interactive".to_string(),
+            duration: None,
+            seed: None,
+            manager: None,
+            rates: HashMap::new(),
+            route_weights: HashMap::new(),
+            spawns: Vec::new(),
+            lanes: LaneLayout::default(),
+            lane_change_share: 0.0,
+            junction: Junction::Cross,
+            pedestrian_rates: HashMap::new(),
+            cyclist_rates: HashMap::new(),
+            penetration: 1.0,
+        }
+    }
+}
+
+impl Scenario {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Could not read scenario {}: {}", path.display(), e))?;
+        let mut scenario: Scenario = serde_json::from_str(&text).map_err(|e| {
+            // A network file has nodes and links instead of traffic
+            let value: serde_json::Value = serde_json::from_str(&text).unwrap_or_default();
+            if value.get("nodes").is_some() || value.get("links").is_some() {
+                format!("{} is a network file, run it with --network", path.display())
+            } else {
+                format!("Invalid scenario {}: {}", path.display(), e)
+            }
+        })?;
+
+        // Unnamed scenarios are named after their file
+        if scenario.name == Scenario::default().name {
+            if let Some(stem) = path.file_stem() {
+                scenario.name = stem.to_string_lossy().to_string();
+            }
+        }
+        scenario
+            .prepare()
+            .map_err(|e| format!("Invalid scenario {}: {}", path.display(), e))?;
+        Ok(scenario)
+    }
+
+    // Sort the spawns and check the lanes of a scenario read from a file or changed on the command line
+    pub fn prepare(&mut self) -> Result<(), String> {
+        self.spawns.sort_by(|a, b| a.time.total_cmp(&b.time));
+        if !(0.0..=1.0).contains(&self.penetration) {
+            return Err(format!("Penetration {} is not a share between 0 and 1", self.penetration));
+        }
+        if self.has_cyclists() {
+            if self.junction != Junction::Cross {
+                return Err("Bike lanes are only laid out on the cross junction".to_string());
+            }
+            self.lanes.add_bike_lanes();
+        }
+        // Approaches the file leaves out get a lane per movement their exits allow
+        for direction in Direction::ALL {
+            if !self.lanes.approaches.contains_key(&direction) {
+                let lanes = self.lanes.default_lanes(&direction);
+                self.lanes.approaches.insert(direction, lanes);
+            }
+        }
+        self.lanes.validate()?;
+        for spawn in &self.spawns {
+            if !self.lanes.has_movement(&spawn.direction, &spawn.route) {
+                return Err(format!("The junction has no {:?} {:?} movement", spawn.direction, spawn.route));
+            }
+            if spawn.lane.is_some_and(|lane| lane >= self.lanes.lanes(&spawn.direction).len()) {
+                return Err(format!("{:?} approach has no lane {:?}", spawn.direction, spawn.lane));
+            }
+        }
+        for (leg, &rate) in &self.pedestrian_rates {
+            if rate > 0.0 && self.junction != Junction::Cross {
+                return Err("Crosswalks are only laid out on the cross junction".to_string());
+            }
+            if rate > 0.0 && !has_road(&self.lanes, leg) {
+                return Err(format!("The junction has no {:?} leg to cross", leg));
+            }
+        }
+        for (direction, &rate) in &self.cyclist_rates {
+            if rate > 0.0 && !self.has_bike_lane(direction) {
+                return Err(format!("The junction has no {:?} road to ride through", direction));
+            }
+        }
+        Ok(())
+    }
+
+    // Same rate on every approach
+    pub fn set_rate(&mut self, cars_per_minute: f32) {
+        for direction in Direction::ALL {
+            self.rates.insert(direction, cars_per_minute);
+        }
+    }
+
+    // Same pedestrian demand on every leg the junction has
+    pub fn set_pedestrian_rate(&mut self, people_per_minute: f32) {
+        for leg in Direction::ALL {
+            if has_road(&self.lanes, &leg) {
+                self.pedestrian_rates.insert(leg, people_per_minute);
+            }
+        }
+    }
+
+    // Same cyclist demand on every road through the junction. `prepare` turns the kerb slots into
+    // bike lanes, which a layout loaded without cyclists didn't leave room for.
+    pub fn set_cyclist_rate(&mut self, cyclists_per_minute: f32) {
+        for direction in Direction::ALL {
+            if self.has_bike_lane(&direction) {
+                self.cyclist_rates.insert(direction, cyclists_per_minute);
+            }
+        }
+    }
+
+    fn has_cyclists(&self) -> bool {
+        self.cyclist_rates.values().any(|&rate| rate > 0.0)
+    }
+
+    // Cyclists ride in from an approach with lanes and out on the same road. An approach `prepare`
+    // hasn't laid out yet gets its default lanes.
+    fn has_bike_lane(&self, direction: &Direction) -> bool {
+        let has_lanes = self.lanes.approaches.get(direction).is_none_or(|lanes| !lanes.is_empty());
+        has_lanes && self.lanes.exit_lanes(direction) > 0
+    }
+
+    // Approaches cyclists come in on, in Direction::ALL order. The roundabout has no bike lanes.
+    pub fn bike_lanes(&self) -> Vec<Direction> {
+        if self.junction != Junction::Cross {
+            return Vec::new();
+        }
+        Direction::ALL
+            .into_iter()
+            .filter(|direction| self.cyclist_rates.get(direction).is_some_and(|&rate| rate > 0.0) && self.has_bike_lane(direction))
+            .collect()
+    }
+
+    // Legs people cross, in Direction::ALL order. The roundabout and missing roads have none.
+    pub fn crosswalks(&self) -> Vec<Direction> {
+        if self.junction != Junction::Cross {
+            return Vec::new();
+        }
+        Direction::ALL
+            .into_iter()
+            .filter(|leg| self.pedestrian_rates.get(leg).is_some_and(|&rate| rate > 0.0) && has_road(&self.lanes, leg))
+            .collect()
+    }
+
+    // U-turns only happen when the scenario gives them a weight
+    fn route_weight(&self, route: &Route) -> f32 {
+        let default = if *route == Route::UTurn { 0.0 } else { 1.0 };
+        self.route_weights.get(route).copied().unwrap_or(default)
+    }
+
+    // Pick a route the approach has using the scenario weights (uniform by default), None when
+    // cars can't come in that way
+    pub fn pick_route(&self, direction: &Direction, rng: &mut fastrand::Rng) -> Option<Route> {
+        let routes: Vec<Route> = Route::ALL
+            .into_iter()
+            .filter(|route| self.lanes.has_movement(direction, route))
+            .collect();
+        let total: f32 = routes.iter().map(|r| self.route_weight(r)).sum();
+        let mut pick = rng.f32() * total;
+        for route in &routes {
+            pick -= self.route_weight(route);
+            if pick < 0.0 {
+                return Some(route.clone());
+            }
+        }
+        routes.last().cloned()
+    }
+}
+
+// Progress through a scenario's demand while the simulation runs
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Traffic {
+    pub next_arrival: HashMap<Direction, f32>,
+    pub next_spawn: usize, // index of the next scheduled spawn
+    pub backlog: VecDeque<Arrival>, // cars waiting for their lane entrance to clear
+    pub next_pedestrian: HashMap<Direction, f32>,
+    pub next_cyclist: HashMap<Direction, f32>,
+    pub cyclist_backlog: VecDeque<Direction>, // cyclists waiting for the start of their bike lane to clear
+}
+
+impl Traffic {
+    // Cars that should appear by `sim_time`, the backlog first so arrival order is kept
+    pub fn due_cars(&mut self, scenario: &Scenario, sim_time: f32, rng: &mut fastrand::Rng) -> Vec<Arrival> {
+        let mut due: Vec<Arrival> = self.backlog.drain(..).collect();
+
+        while let Some(spawn) = scenario.spawns.get(self.next_spawn) {
+            if spawn.time > sim_time {
+                break;
+            }
+            due.push((spawn.direction.clone(), spawn.route.clone(), spawn.lane));
+            self.next_spawn += 1;
+        }
+
+        // Poisson arrivals: exponential gaps between cars on each approach
+        for direction in Direction::ALL {
+            let rate = scenario.rates.get(&direction).copied().unwrap_or(0.0) / 60.0;
+            if rate <= 0.0 || scenario.lanes.lanes(&direction).is_empty() {
+                continue;
+            }
+            let next = self.next_arrival.entry(direction.clone()).or_insert_with(|| exponential(rng, rate));
+            while *next <= sim_time {
+                let Some(route) = scenario.pick_route(&direction, rng) else {
+                    break;
+                };
+                let lanes = scenario.lanes.lanes(&direction).len();
+                let lane = (scenario.lane_change_share > 0.0 && rng.f32() < scenario.lane_change_share)
+                    .then(|| rng.usize(..lanes));
+                due.push((direction.clone(), route, lane));
+                *next += exponential(rng, rate);
+            }
+        }
+        due
+    }
+
+    // Pedestrians that should appear by `sim_time`: the leg and whether they walk towards
+    // higher x or y. Poisson arrivals like the cars, from either kerb.
+    pub fn due_pedestrians(&mut self, scenario: &Scenario, sim_time: f32, rng: &mut fastrand::Rng) -> Vec<(Direction, bool)> {
+        let mut due = Vec::new();
+        for leg in scenario.crosswalks() {
+            let rate = scenario.pedestrian_rates[&leg] / 60.0;
+            let next = self.next_pedestrian.entry(leg.clone()).or_insert_with(|| exponential(rng, rate));
+            while *next <= sim_time {
+                due.push((leg.clone(), rng.bool()));
+                *next += exponential(rng, rate);
+            }
+        }
+        due
+    }
+
+    // Cyclists that should appear by `sim_time` on each bike lane, the backlog first
+    pub fn due_cyclists(&mut self, scenario: &Scenario, sim_time: f32, rng: &mut fastrand::Rng) -> Vec<Direction> {
+        let mut due: Vec<Direction> = self.cyclist_backlog.drain(..).collect();
+        for direction in scenario.bike_lanes() {
+            let rate = scenario.cyclist_rates[&direction] / 60.0;
+            let next = self.next_cyclist.entry(direction.clone()).or_insert_with(|| exponential(rng, rate));
+            while *next <= sim_time {
+                due.push(direction.clone());
+                *next += exponential(rng, rate);
+            }
+        }
+        due
+    }
+
+    // Arrivals that were dropped because their demand changed, drawn again from `sim_time` on.
+    // `due_*` would draw them from zero and bring every car since then in one frame.
+    pub fn resume_arrivals(&mut self, scenario: &Scenario, sim_time: f32, rng: &mut fastrand::Rng) {
+        for direction in Direction::ALL {
+            let rate = scenario.rates.get(&direction).copied().unwrap_or(0.0) / 60.0;
+            if rate > 0.0 {
+                self.next_arrival.entry(direction).or_insert_with(|| sim_time + exponential(rng, rate));
+            }
+        }
+        for leg in scenario.crosswalks() {
+            let rate = scenario.pedestrian_rates[&leg] / 60.0;
+            self.next_pedestrian.entry(leg).or_insert_with(|| sim_time + exponential(rng, rate));
+        }
+        for direction in scenario.bike_lanes() {
+            let rate = scenario.cyclist_rates[&direction] / 60.0;
+            self.next_cyclist.entry(direction).or_insert_with(|| sim_time + exponential(rng, rate));
+        }
+    }
+}
+
+pub fn exponential(rng: &mut fastrand::Rng, rate: f32) -> f32 {
+    -(1.0 - rng.f32()).ln() / rate
+}
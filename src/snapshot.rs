@@ -0,0 +1,135 @@
+use crate::collision::Manager;
+use crate::crosswalk::Pedestrian;
+use crate::deadlock::DeadlockDetector;
+use crate::events::EventLog;
+use crate::game::Game;
+use crate::safety::{ConflictEvent, ConflictProgress};
+use crate::scenario::{Scenario, Traffic};
+use crate::types::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+// Bumped whenever a field is added or changes meaning, old files are refused instead of misread
+pub const SNAPSHOT_VERSION: u32 = 5;
+
+// Everything the simulation needs to carry on exactly where it was. Maps keyed by car pairs
+// are stored as lists since JSON keys have to be strings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub version: u32,
+    pub sim_time: f32,
+    pub seed: u64,
+    pub rng_state: u64,
+    pub manager: String,
+    pub scenario: Scenario,
+    pub traffic: Traffic,
+    pub next_car_id: usize,
+    pub cars: Vec<Car>,
+    pub next_pedestrian_id: usize,
+    pub pedestrians: Vec<Pedestrian>,
+    pub priority_map: Vec<((usize, usize), usize)>,
+    pub priority_ref: Vec<((usize, usize), usize)>,
+    pub in_intersection: HashMap<CollisionType, Vec<usize>>,
+    pub crash_pairs: HashSet<(usize, usize)>,
+    pub ttc_threshold: f32,
+    pub open_conflicts: Vec<(ConflictEvent, ConflictProgress)>,
+    pub deadlocks: DeadlockDetector,
+    pub events: EventLog,
+    pub stats: Stats,
+    pub sample_interval: f32,
+    pub next_sample_time: f32,
+}
+
+impl Snapshot {
+    pub fn take(game: &Game) -> Self {
+        let mut open_conflicts: Vec<(ConflictEvent, ConflictProgress)> = game.open_conflicts
+            .values()
+            .map(|event| (event.clone(), event.progress.clone()))
+            .collect();
+        open_conflicts.sort_by_key(|(event, _)| (event.car_a, event.car_b));
+
+        Snapshot {
+            version: SNAPSHOT_VERSION,
+            sim_time: game.sim_time,
+            seed: game.seed,
+            rng_state: game.rng.get_seed(),
+            manager: game.manager.name().to_string(),
+            scenario: game.scenario.clone(),
+            traffic: game.traffic.clone(),
+            next_car_id: game.next_car_id,
+            cars: game.cars.clone(),
+            next_pedestrian_id: game.next_pedestrian_id,
+            pedestrians: game.pedestrians.clone(),
+            priority_map: sorted_pairs(&game.priority_map),
+            priority_ref: sorted_pairs(&game.priority_ref),
+            in_intersection: game.in_intersection.clone(),
+            crash_pairs: game.crash_pairs.clone(),
+            ttc_threshold: game.ttc_threshold,
+            open_conflicts,
+            deadlocks: game.deadlocks.clone(),
+            events: game.events.clone(),
+            stats: game.stats.clone(),
+            sample_interval: game.sample_interval,
+            next_sample_time: game.next_sample_time,
+        }
+    }
+
+    // Put the saved state into `game`, settings that aren't simulation state (window, export,
+    // verbosity) are left as they are
+    pub fn restore(self, game: &mut Game) -> Result<(), String> {
+        if self.version != SNAPSHOT_VERSION {
+            return Err(format!("Snapshot version {} is not supported (expected {})", self.version, SNAPSHOT_VERSION));
+        }
+        game.manager = Manager::parse(&self.manager)?;
+        game.sim_time = self.sim_time;
+        game.seed = self.seed;
+        game.rng = fastrand::Rng::with_seed(self.rng_state);
+        game.scenario = self.scenario;
+        game.traffic = self.traffic;
+        game.next_car_id = self.next_car_id;
+        game.cars = self.cars;
+        game.next_pedestrian_id = self.next_pedestrian_id;
+        game.pedestrians = self.pedestrians;
+        game.priority_map = self.priority_map.into_iter().collect();
+        game.priority_ref = self.priority_ref.into_iter().collect();
+        game.in_intersection = self.in_intersection;
+        game.crash_pairs = self.crash_pairs;
+        game.ttc_threshold = self.ttc_threshold;
+        game.open_conflicts = self.open_conflicts
+            .into_iter()
+            .map(|(mut event, progress)| {
+                event.progress = progress;
+                ((event.car_a, event.car_b), event)
+            })
+            .collect();
+        game.deadlocks = self.deadlocks;
+        game.events = self.events;
+        game.stats = self.stats;
+        game.sample_interval = self.sample_interval;
+        game.next_sample_time = self.next_sample_time;
+        game.app_state = AppState::Running;
+        Ok(())
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).map_err(|e| format!("Could not create {}: {}", dir.display(), e))?;
+        }
+        let json = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        fs::write(path, json).map_err(|e| format!("Could not write {}: {}", path.display(), e))
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Could not read snapshot {}: {}", path.display(), e))?;
+        serde_json::from_str(&text).map_err(|e| format!("Invalid snapshot {}: {}", path.display(), e))
+    }
+}
+
+fn sorted_pairs(map: &HashMap<(usize, usize), usize>) -> Vec<((usize, usize), usize)> {
+    let mut pairs: Vec<((usize, usize), usize)> = map.iter().map(|(&pair, &id)| (pair, id)).collect();
+    pairs.sort_unstable();
+    pairs
+}
@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+
+// Side of a grid cell, a bit more than a car hitbox so most cars touch few cells
+pub const CELL_SIZE: f32 = 128.0;
+
+// Axis-aligned bounding box
+#[derive(Debug, Clone, Copy)]
+pub struct Aabb {
+    pub min_x: f32,
+    pub min_y: f32,
+    pub max_x: f32,
+    pub max_y: f32,
+}
+
+impl Aabb {
+    pub fn union(self, other: Aabb) -> Aabb {
+        Aabb {
+            min_x: self.min_x.min(other.min_x),
+            min_y: self.min_y.min(other.min_y),
+            max_x: self.max_x.max(other.max_x),
+            max_y: self.max_y.max(other.max_y),
+        }
+    }
+}
+
+// Uniform grid over the map, each cell lists the indices of the cars touching it
+#[derive(Debug, Clone)]
+pub struct SpatialGrid {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+}
+
+impl SpatialGrid {
+    pub fn new(cell_size: f32) -> Self {
+        SpatialGrid {
+            cell_size,
+            cells: HashMap::new(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+    }
+
+    pub fn insert(&mut self, index: usize, bounds: Aabb) {
+        let (min, max) = self.cell_range(bounds);
+        for cx in min.0..=max.0 {
+            for cy in min.1..=max.1 {
+                self.cells.entry((cx, cy)).or_default().push(index);
+            }
+        }
+    }
+
+    // Indices of everything sharing a cell with `bounds`, sorted and without duplicates
+    pub fn query(&self, bounds: Aabb) -> Vec<usize> {
+        let (min, max) = self.cell_range(bounds);
+        let mut found = Vec::new();
+        for cx in min.0..=max.0 {
+            for cy in min.1..=max.1 {
+                if let Some(indices) = self.cells.get(&(cx, cy)) {
+                    found.extend_from_slice(indices);
+                }
+            }
+        }
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+
+    fn cell_range(&self, bounds: Aabb) -> ((i32, i32), (i32, i32)) {
+        let cell = |v: f32| (v / self.cell_size).floor() as i32;
+        (
+            (cell(bounds.min_x), cell(bounds.min_y)),
+            (cell(bounds.max_x), cell(bounds.max_y)),
+        )
+    }
+}
@@ -0,0 +1,383 @@
+use crate::lanes::*;
+use crate::movement::*;
+use crate::types::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+pub const HISTOGRAM_BINS: usize = 10;
+
+// Simulated seconds between two time-series samples
+pub const SAMPLE_INTERVAL: f32 = 1.0;
+
+// Cars slower than this count as queued
+const QUEUE_SPEED: f32 = 5.0;
+
+// Time step used when measuring free-flow travel times
+const FREE_FLOW_STEP: f32 = 1.0 / 60.0;
+
+// One car's journey through the map, all times are simulation seconds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TripRecord {
+    pub id: usize,
+    pub direction: Direction,
+    pub route: Route,
+    pub spawn_time: f32,
+    pub entry_time: Option<f32>,
+    pub exit_time: f32,
+    pub stopped_time: f32,
+    pub free_flow_time: f32,
+    pub distance: f32,
+    pub stops: usize,
+    pub driver: Driver,
+}
+
+impl TripRecord {
+    pub fn from_car(car: &Car, exit_time: f32, free_flow_time: f32) -> Self {
+        TripRecord {
+            id: car.id,
+            direction: car.direction.clone(),
+            route: car.route.clone(),
+            spawn_time: car.spawn_time,
+            entry_time: car.entry_time,
+            exit_time,
+            stopped_time: car.wait_time,
+            free_flow_time,
+            distance: car.distance,
+            stops: car.stops,
+            driver: car.driver,
+        }
+    }
+
+    pub fn travel_time(&self) -> f32 {
+        self.exit_time - self.spawn_time
+    }
+
+    // Extra time compared to driving alone through the intersection
+    pub fn delay(&self) -> f32 {
+        (self.travel_time() - self.free_flow_time).max(0.0)
+    }
+
+    pub fn average_speed(&self) -> f32 {
+        let travel_time = self.travel_time();
+        if travel_time > 0.0 {
+            self.distance / travel_time
+        } else {
+            0.0
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Distribution {
+    pub count: usize,
+    pub mean: f32,
+    pub median: f32,
+    pub p95: f32,
+    pub min: f32,
+    pub max: f32,
+}
+
+impl Distribution {
+    pub fn from_values(values: &[f32]) -> Self {
+        if values.is_empty() {
+            return Distribution::default();
+        }
+
+        let mut sorted = values.to_vec();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+
+        Distribution {
+            count: sorted.len(),
+            mean: sorted.iter().sum::<f32>() / sorted.len() as f32,
+            median: percentile(&sorted, 50.0),
+            p95: percentile(&sorted, 95.0),
+            min: sorted[0],
+            max: sorted[sorted.len() - 1],
+        }
+    }
+}
+
+// Percentile of already sorted values, interpolating between neighbours
+pub fn percentile(sorted: &[f32], p: f32) -> f32 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+
+    let rank = (p / 100.0).clamp(0.0, 1.0) * (sorted.len() - 1) as f32;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    let fraction = rank - lower as f32;
+
+    sorted[lower] + (sorted[upper] - sorted[lower]) * fraction
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Histogram {
+    pub min: f32,
+    pub bin_width: f32,
+    pub counts: Vec<usize>,
+}
+
+impl Histogram {
+    pub fn from_values(values: &[f32], bins: usize) -> Self {
+        let mut counts = vec![0; bins];
+        if values.is_empty() {
+            return Histogram { min: 0.0, bin_width: 0.0, counts };
+        }
+
+        let min = values.iter().copied().fold(f32::INFINITY, f32::min);
+        let max = values.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        let bin_width = if max > min { (max - min) / bins as f32 } else { 1.0 };
+
+        for &value in values {
+            let bin = (((value - min) / bin_width) as usize).min(bins - 1);
+            counts[bin] += 1;
+        }
+
+        Histogram { min, bin_width, counts }
+    }
+}
+
+// Aggregates for one (Direction, Route) movement
+#[derive(Debug, Clone, Serialize)]
+pub struct MovementSummary {
+    pub direction: Direction,
+    pub route: Route,
+    pub count: usize,
+    pub travel_time: Distribution,
+    pub delay: Distribution,
+    pub stopped_time: Distribution,
+    pub delay_histogram: Histogram,
+    pub throughput_per_hour: f32,
+}
+
+impl MovementSummary {
+    pub fn from_trips(direction: Direction, route: Route, trips: &[&TripRecord], sim_time: f32) -> Self {
+        let travel_times: Vec<f32> = trips.iter().map(|t| t.travel_time()).collect();
+        let delays: Vec<f32> = trips.iter().map(|t| t.delay()).collect();
+        let stopped_times: Vec<f32> = trips.iter().map(|t| t.stopped_time).collect();
+
+        MovementSummary {
+            direction,
+            route,
+            count: trips.len(),
+            travel_time: Distribution::from_values(&travel_times),
+            delay: Distribution::from_values(&delays),
+            stopped_time: Distribution::from_values(&stopped_times),
+            delay_histogram: Histogram::from_values(&delays, HISTOGRAM_BINS),
+            throughput_per_hour: if sim_time > 0.0 { trips.len() as f32 / sim_time * 3600.0 } else { 0.0 },
+        }
+    }
+}
+
+impl Stats {
+    // One summary per movement that had at least one car, in direction/route order
+    pub fn movement_summaries(&self, sim_time: f32) -> Vec<MovementSummary> {
+        let mut summaries = Vec::new();
+        for direction in Direction::ALL {
+            for route in Route::ALL {
+                let trips: Vec<&TripRecord> = self.trips
+                    .iter()
+                    .filter(|t| t.direction == direction && t.route == route)
+                    .collect();
+                if !trips.is_empty() {
+                    summaries.push(MovementSummary::from_trips(direction.clone(), route, &trips, sim_time));
+                }
+            }
+        }
+        summaries
+    }
+
+    pub fn delay_distribution(&self) -> Distribution {
+        let delays: Vec<f32> = self.trips.iter().map(|t| t.delay()).collect();
+        Distribution::from_values(&delays)
+    }
+
+    pub fn travel_time_distribution(&self) -> Distribution {
+        let travel_times: Vec<f32> = self.trips.iter().map(|t| t.travel_time()).collect();
+        Distribution::from_values(&travel_times)
+    }
+
+    pub fn travel_time_histogram(&self) -> Histogram {
+        let travel_times: Vec<f32> = self.trips.iter().map(|t| t.travel_time()).collect();
+        Histogram::from_values(&travel_times, HISTOGRAM_BINS)
+    }
+
+    // Average speed of each trip, not instantaneous speeds
+    pub fn speed_histogram(&self) -> Histogram {
+        let speeds: Vec<f32> = self.trips.iter().map(|t| t.average_speed()).collect();
+        Histogram::from_values(&speeds, HISTOGRAM_BINS)
+    }
+
+    // Cars leaving the map per hour, counted in windows of `window` seconds
+    pub fn throughput_series(&self, window: f32, sim_time: f32) -> Vec<(f32, f32)> {
+        let windows = (sim_time / window).ceil().max(1.0) as usize;
+        let mut counts = vec![0usize; windows];
+        for trip in &self.trips {
+            let i = ((trip.exit_time / window) as usize).min(windows - 1);
+            counts[i] += 1;
+        }
+
+        counts
+            .iter()
+            .enumerate()
+            .map(|(i, &count)| ((i + 1) as f32 * window, count as f32 / window * 3600.0))
+            .collect()
+    }
+
+    // Mean delay per approach, approaches without traffic get 0
+    pub fn approach_delays(&self) -> Vec<(Direction, f32)> {
+        Direction::ALL
+            .iter()
+            .map(|direction| {
+                let delays: Vec<f32> = self.trips
+                    .iter()
+                    .filter(|t| t.direction == *direction)
+                    .map(|t| t.delay())
+                    .collect();
+                (direction.clone(), Distribution::from_values(&delays).mean)
+            })
+            .collect()
+    }
+
+    // Lowest time-to-collision reached in each close call
+    pub fn ttc_distribution(&self) -> Distribution {
+        let ttcs: Vec<f32> = self.conflicts.iter().map(|c| c.min_ttc).collect();
+        Distribution::from_values(&ttcs)
+    }
+
+    pub fn pet_distribution(&self) -> Distribution {
+        let pets: Vec<f32> = self.conflicts.iter().filter_map(|c| c.pet).collect();
+        Distribution::from_values(&pets)
+    }
+
+    // Waiting time of the pedestrians that got across, at the kerb and on the road
+    pub fn pedestrian_wait_distribution(&self) -> Distribution {
+        Distribution::from_values(&self.pedestrian_waits)
+    }
+
+    // Delays of the cars one kind of driver drove
+    pub fn driver_delay_distribution(&self, driver: Driver) -> Distribution {
+        let delays: Vec<f32> = self.trips.iter().filter(|t| t.driver == driver).map(|t| t.delay()).collect();
+        Distribution::from_values(&delays)
+    }
+
+    // Delay of the cyclists that rode through, against riding alone at cruising speed
+    pub fn cyclist_delay_distribution(&self) -> Distribution {
+        let delays: Vec<f32> = self.cyclist_trips.iter().map(|t| t.delay()).collect();
+        Distribution::from_values(&delays)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LaneSample {
+    pub direction: Direction,
+    pub lane: usize, // slot of the approach, innermost first
+    pub cars: usize,
+    pub queue: usize, // stopped cars that haven't reached the intersection yet
+}
+
+// State of the whole simulation at one moment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sample {
+    pub time: f32,
+    pub cars: usize,
+    pub lanes: Vec<LaneSample>, // every lane slot, in direction/lane order
+    pub intersection_occupancy: usize,
+    pub mean_speed: f32,
+    pub close_calls: usize,
+}
+
+impl Sample {
+    pub fn take(
+        time: f32,
+        cars: &[Car],
+        in_intersection: &HashMap<CollisionType, Vec<usize>>,
+        stats: &Stats,
+    ) -> Self {
+        let mut lanes = Vec::new();
+        // By the lane cars are in, several routes can share one and a route can have several
+        for direction in Direction::ALL {
+            for lane in 0..MAX_LANES {
+                let lane_cars: Vec<&Car> = cars
+                    .iter()
+                    .filter(|c| c.direction == direction && c.lane == lane)
+                    .collect();
+                let queue = lane_cars
+                    .iter()
+                    .filter(|c| c.entry_time.is_none() && c.velocity.length() < QUEUE_SPEED)
+                    .count();
+                lanes.push(LaneSample { direction: direction.clone(), lane, cars: lane_cars.len(), queue });
+            }
+        }
+
+        let mean_speed = if cars.is_empty() {
+            0.0
+        } else {
+            cars.iter().map(|c| c.velocity.length()).sum::<f32>() / cars.len() as f32
+        };
+
+        Sample {
+            time,
+            cars: cars.len(),
+            lanes,
+            intersection_occupancy: in_intersection.values().map(|ids| ids.len()).sum(),
+            mean_speed,
+            close_calls: stats.close_call,
+        }
+    }
+}
+
+// Drive a lone car through the empty junction from its lane and time it, a bicycle rides its
+// bike lane whatever the route and lane
+pub fn free_flow_time(junction: Junction, kind: VehicleKind, direction: Direction, route: Route, lane: usize) -> f32 {
+    let mut car = match kind {
+        VehicleKind::Car => {
+            let (x, y) = lane_start(&direction, lane);
+            let mut car = Car::new(0, direction, route, x, y, 0.0);
+            car.lane = lane;
+            car
+        }
+        VehicleKind::Bicycle => Car::bicycle(0, direction, 0.0),
+    };
+    let mut in_intersection = HashMap::new();
+    let mut time = 0.0;
+
+    // Hard cap so a broken path can't hang the game
+    while car.moving && time < 120.0 {
+        move_car(&mut car, FREE_FLOW_STEP, &mut in_intersection, junction);
+        time += FREE_FLOW_STEP;
+    }
+    time
+}
+
+// Free-flow seconds by junction, vehicle, approach, route and the lane left from
+pub type FreeFlowTimes = HashMap<(Junction, VehicleKind, Direction, Route, usize), f32>;
+
+static FREE_FLOW_TIMES: OnceLock<FreeFlowTimes> = OnceLock::new();
+
+// Every route from every lane of both junctions, so any scenario can look its cars up, and
+// the bike lanes of the cross junction. Built on first use and shared by every game
+pub fn free_flow_times() -> &'static FreeFlowTimes {
+    FREE_FLOW_TIMES.get_or_init(build_free_flow_times)
+}
+
+fn build_free_flow_times() -> FreeFlowTimes {
+    let mut times = HashMap::new();
+    for junction in Junction::ALL {
+        for direction in Direction::ALL {
+            for route in Route::ALL {
+                for lane in 0..MAX_LANES {
+                    let time = free_flow_time(junction, VehicleKind::Car, direction.clone(), route.clone(), lane);
+                    times.insert((junction, VehicleKind::Car, direction.clone(), route.clone(), lane), time);
+                }
+            }
+        }
+    }
+    for direction in Direction::ALL {
+        let time = free_flow_time(Junction::Cross, VehicleKind::Bicycle, direction.clone(), Route::Straight, BIKE_LANE);
+        times.insert((Junction::Cross, VehicleKind::Bicycle, direction, Route::Straight, BIKE_LANE), time);
+    }
+    times
+}
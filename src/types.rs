@@ -1,4 +1,10 @@
-use std::time::{Duration, Instant};
+use crate::collision::{bicycle_collision_types, collision_types_for};
+use crate::lanes::{default_lane, lane_start, BIKE_LANE};
+use crate::safety::ConflictEvent;
+use crate::stats::{Sample, TripRecord};
+use serde::{Deserialize, Serialize};
+use std::f32::consts::PI;
+use std::time::Duration;
 
 // Game state and app states
 #[derive(Debug, Clone, PartialEq)]
@@ -15,8 +21,22 @@ pub const MEDIUM: f32 = 100.0;
 pub const FAST: f32 = 150.0;
 pub const SUPER: f32 = 200.0;
 
+// Speed multiplier steps for the simulation clock
+pub const SPEED_MULTIPLIERS: [f32; 6] = [0.25, 0.5, 1.0, 2.0, 4.0, 8.0];
+
+// Car body size (same as the rendered sprite)
+pub const CAR_WIDTH: f32 = 32.0;
+pub const CAR_LENGTH: f32 = 60.0;
+
+// Bicycle body and speed profile: about 5 m/s cruising, and a slow start after every stop
+pub const BICYCLE_WIDTH: f32 = 10.0;
+pub const BICYCLE_LENGTH: f32 = 30.0;
+pub const BICYCLE_SPEED: f32 = 80.0;
+pub const BICYCLE_START_SPEED: f32 = 20.0;
+pub const BICYCLE_ACCELERATION: f32 = 40.0;
+
 // Car directions and routes
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Direction {
     North,
     South,
@@ -24,15 +44,100 @@ pub enum Direction {
     West,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Route {
     Right,
     Straight,
     Left,
+    UTurn, // back the way it came, from the innermost lane unless the layout says otherwise
+}
+
+// What a road user drives or rides, every kind moves as a `Car`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum VehicleKind {
+    #[default]
+    Car,
+    Bicycle, // rides straight through on the bike lane
+}
+
+impl VehicleKind {
+    // Width and length of the body
+    pub fn size(self) -> (f32, f32) {
+        match self {
+            VehicleKind::Car => (CAR_WIDTH, CAR_LENGTH),
+            VehicleKind::Bicycle => (BICYCLE_WIDTH, BICYCLE_LENGTH),
+        }
+    }
+}
+
+// Who is at the wheel of a car. Connected cars follow the manager, human drivers don't hear
+// from it and decide for themselves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Driver {
+    #[default]
+    Autonomous,
+    Human,
+}
+
+impl Driver {
+    pub const ALL: [Driver; 2] = [Driver::Autonomous, Driver::Human];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Driver::Autonomous => "autonomous",
+            Driver::Human => "human",
+        }
+    }
+}
+
+// Layout of the junction the four approaches meet at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Junction {
+    #[default]
+    Cross, // the crossing drawn in map.png, run by the manager
+    Roundabout, // one circulating lane, entering cars give way to it
+}
+
+impl Junction {
+    pub const ALL: [Junction; 2] = [Junction::Cross, Junction::Roundabout];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Junction::Cross => "cross",
+            Junction::Roundabout => "roundabout",
+        }
+    }
+
+    pub fn parse(name: &str) -> Result<Self, String> {
+        Junction::ALL
+            .into_iter()
+            .find(|j| j.name() == name)
+            .ok_or_else(|| format!("Unknown junction '{}' (expected cross or roundabout)", name))
+    }
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [Direction::North, Direction::South, Direction::East, Direction::West];
+
+    pub fn opposite(&self) -> Direction {
+        match self {
+            Direction::North => Direction::South,
+            Direction::South => Direction::North,
+            Direction::East => Direction::West,
+            Direction::West => Direction::East,
+        }
+    }
+}
+
+impl Route {
+    pub const ALL: [Route; 4] = [Route::Right, Route::Straight, Route::Left, Route::UTurn];
 }
 
 // Collision types from Bevy version
-#[derive(Hash, Eq, PartialEq, Debug, Clone, Copy)]
+#[derive(Hash, Eq, PartialEq, Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum CollisionType {
     NS, // North-South straight
     WS, // West-South straight  
@@ -42,11 +147,19 @@ pub enum CollisionType {
     WL, // West-Left
     EL, // East-Left  
     SL, // South-Left
+    NU, // North U-turn
+    WU, // West U-turn
+    EU, // East U-turn
+    SU, // South U-turn
+    NR, // North-Right, only bicycles share it
+    WR, // West-Right
+    ER, // East-Right
+    SR, // South-Right
     GG, // Generic/Right turns
 }
 
 // 2D Vector for collision detection
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct Vec2 {
     pub x: f32,
     pub y: f32,
@@ -61,6 +174,10 @@ impl Vec2 {
         self.x * other.x + self.y * other.y
     }
     
+    pub fn length(self) -> f32 {
+        (self.x * self.x + self.y * self.y).sqrt()
+    }
+    
     pub fn normalize(self) -> Vec2 {
         let len = (self.x * self.x + self.y * self.y).sqrt();
         if len > 0.0 {
@@ -72,7 +189,7 @@ impl Vec2 {
 }
 
 // Car structure
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Car {
     pub x: f32,
     pub y: f32,
@@ -81,24 +198,103 @@ pub struct Car {
     pub route: Route,
     pub rotation: f32, // in radians
     pub id: usize,
-    pub spawn_time: Instant,
+    pub spawn_time: f32, // simulation time in seconds
     pub moving: bool,
     pub rotated: bool, // for tracking if car has rotated during turn
     pub collision_types: Vec<CollisionType>,
     pub max_speed: f32,
     pub min_speed: f32,
     pub entered: bool, // has entered the intersection
+    pub wait_time: f32, // seconds spent stopped
+    pub entry_time: Option<f32>, // when the car reached the intersection
+    pub distance: f32, // distance travelled so far
+    pub velocity: Vec2, // actual displacement per second over the last tick
+    pub lane: usize, // lane of the approach, innermost first
+    pub stops: usize, // times the car came to a standstill
+    pub kind: VehicleKind,
+    pub driver: Driver,
+    pub reaction: f32, // seconds a stopped human driver has had a clear way, it moves off after REACTION_TIME
+}
+
+impl Car {
+    pub fn new(id: usize, direction: Direction, route: Route, x: f32, y: f32, spawn_time: f32) -> Self {
+        let speed = if route == Route::Straight { SUPER } else { FAST };
+        let lane = default_lane(&route);
+        
+        // Initial rotation based on direction
+        let rotation = match direction {
+            Direction::North => 0.0,
+            Direction::East => -PI / 2.0,
+            Direction::South => PI,
+            Direction::West => PI / 2.0,
+        };
+        
+        Car {
+            x,
+            y,
+            speed,
+            collision_types: collision_types_for(&route, &direction),
+            direction,
+            route,
+            rotation,
+            id,
+            spawn_time,
+            moving: true,
+            rotated: false,
+            max_speed: speed,
+            min_speed: speed,
+            entered: false,
+            wait_time: 0.0,
+            entry_time: None,
+            distance: 0.0,
+            velocity: Vec2::new(0.0, 0.0),
+            lane,
+            stops: 0,
+            kind: VehicleKind::Car,
+            driver: Driver::Autonomous,
+            reaction: 0.0,
+        }
+    }
+
+    // A cyclist at the start of its approach's bike lane
+    pub fn bicycle(id: usize, direction: Direction, spawn_time: f32) -> Self {
+        let (x, y) = lane_start(&direction, BIKE_LANE);
+        let mut car = Car::new(id, direction.clone(), Route::Straight, x, y, spawn_time);
+        car.kind = VehicleKind::Bicycle;
+        car.speed = BICYCLE_SPEED;
+        car.max_speed = BICYCLE_SPEED;
+        car.min_speed = BICYCLE_SPEED;
+        car.lane = BIKE_LANE;
+        car.collision_types = bicycle_collision_types(&direction);
+        car
+    }
 }
 
 // Game statistics
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Stats {
     pub max_number_cars: usize,
     pub max_velocity: f32,
     pub min_velocity: f32,
     pub max_time: Duration,
     pub min_time: Duration,
-    pub close_call: usize,
+    pub close_call: usize, // conflicting pairs whose TTC fell under the threshold
+    pub yield_time: f32, // seconds cars spent yielding to another, summed over cars
+    pub cars_passed: usize,
+    pub crashes: usize,
+    pub deadlocks: usize, // wait cycles and timeouts that needed a car released
+    pub unresolved_deadlocks: usize, // wait cycles and timeouts no car could be released from
+    pub total_wait: Duration, // summed over cars that passed
+    pub max_wait: Duration,
+    pub trips: Vec<TripRecord>, // one record per car that left the map
+    pub samples: Vec<Sample>, // periodic snapshots for plotting over time
+    pub conflicts: Vec<ConflictEvent>, // closed close-call events
+    pub pedestrian_waits: Vec<f32>, // seconds each pedestrian that got across stood waiting
+    pub pedestrian_yield_time: f32, // seconds cars spent stopped for a pedestrian, summed over cars
+    pub cyclist_trips: Vec<TripRecord>, // kept apart from the cars' trips
+    pub right_hooks: usize, // close calls between a cyclist and a car turning right across its lane
+    pub human_close_calls: usize, // close calls with a human driver in them
+    pub footprint_hold_time: f32, // seconds of moves the footprint guard took back, summed over cars
 }
 
 impl Default for Stats {
@@ -110,6 +306,45 @@ impl Default for Stats {
             max_time: Duration::from_secs(0),
             min_time: Duration::from_secs(1000),
             close_call: 0,
+            yield_time: 0.0,
+            cars_passed: 0,
+            crashes: 0,
+            deadlocks: 0,
+            unresolved_deadlocks: 0,
+            total_wait: Duration::from_secs(0),
+            max_wait: Duration::from_secs(0),
+            trips: Vec::new(),
+            samples: Vec::new(),
+            conflicts: Vec::new(),
+            pedestrian_waits: Vec::new(),
+            pedestrian_yield_time: 0.0,
+            cyclist_trips: Vec::new(),
+            right_hooks: 0,
+            human_close_calls: 0,
+            footprint_hold_time: 0.0,
         }
     }
-}
\ No newline at end of file
+}
+
+impl Stats {
+    pub fn average_wait(&self) -> Duration {
+        if self.cars_passed == 0 {
+            Duration::from_secs(0)
+        } else {
+            self.total_wait / self.cars_passed as u32
+        }
+    }
+
+    // Cars that left the map per minute of simulated time
+    pub fn throughput_per_minute(&self, sim_time: f32) -> f32 {
+        if sim_time > 0.0 {
+            self.cars_passed as f32 / sim_time * 60.0
+        } else {
+            0.0
+        }
+    }
+
+    pub fn throughput_per_hour(&self, sim_time: f32) -> f32 {
+        self.throughput_per_minute(sim_time) * 60.0
+    }
+}
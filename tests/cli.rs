@@ -0,0 +1,160 @@
+// Command line options: what they set, and the combinations that are refused.
+
+use smart_road::cli::Options;
+use smart_road::game::{Game, HEADLESS_STEP};
+use smart_road::types::Direction;
+
+fn args(line: &str) -> Vec<String> {
+    line.split_whitespace().map(|s| s.to_string()).collect()
+}
+
+#[test]
+fn speed_is_for_window_runs_only() {
+    let error = Options::parse(&args("--headless --duration 60 --speed 4")).unwrap_err();
+    assert!(error.contains("--speed"), "{}", error);
+
+    let options = Options::parse(&args("--duration 60 --speed 4")).unwrap();
+    let game = options.build_game().unwrap();
+    assert_eq!(game.speed_multiplier, 4.0);
+}
+
+#[test]
+fn load_takes_the_scenario_from_the_snapshot() {
+    for line in ["--load a.json --scenario b.json", "--load a.json --network n.json", "--load a.json --junction roundabout"] {
+        assert!(Options::parse(&args(line)).is_err(), "{}", line);
+    }
+}
+
+#[test]
+fn load_applies_seed_and_demand() {
+    let path = std::env::temp_dir().join(format!("smart-road-cli-{}.json", std::process::id()));
+    let mut game = Game::with_seed(5);
+    game.verbose = false;
+    game.scenario.set_rate(10.0);
+    while game.sim_time < 10.0 {
+        game.update(HEADLESS_STEP);
+    }
+    game.save_snapshot(&path).unwrap();
+
+    let resume = |extra: &str| {
+        let mut options = Options::parse(&args(extra)).unwrap();
+        options.load = Some(path.clone());
+        let mut game = options.build_game().unwrap();
+        game.verbose = false;
+        game.auto_export = false;
+        let spawned_at = game.next_car_id;
+        while game.sim_time < 40.0 {
+            game.update(HEADLESS_STEP);
+        }
+        let spawns: Vec<f32> = game.stats.trips.iter().filter(|t| t.id >= spawned_at).map(|t| t.spawn_time).collect();
+        (game, spawns)
+    };
+    let (same, same_spawns) = resume("");
+    let (reseeded, reseeded_spawns) = resume("--seed 6 --rate 30");
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(same.seed, 5);
+    assert_eq!(reseeded.seed, 6);
+    assert_eq!(reseeded.scenario.rates[&Direction::North], 30.0);
+    assert_ne!(same_spawns, reseeded_spawns);
+    assert!(reseeded_spawns.len() > same_spawns.len(), "three times the demand");
+}
+
+#[test]
+fn load_with_new_demand_starts_from_the_snapshot_time() {
+    let path = std::env::temp_dir().join(format!("smart-road-cli-burst-{}.json", std::process::id()));
+    let mut game = Game::with_seed(8);
+    game.verbose = false;
+    game.scenario.set_rate(5.0);
+    while game.sim_time < 60.0 {
+        game.update(HEADLESS_STEP);
+    }
+    game.save_snapshot(&path).unwrap();
+
+    let mut options = Options::parse(&args("--rate 30 --pedestrians 20 --cyclists 20")).unwrap();
+    options.load = Some(path.clone());
+    let mut game = options.build_game().unwrap();
+    std::fs::remove_file(&path).unwrap();
+    game.verbose = false;
+    game.auto_export = false;
+    let cars_before = game.cars.len() + game.traffic.backlog.len();
+    let pedestrians_before = game.pedestrians.len();
+    game.update(HEADLESS_STEP);
+
+    // A minute of demand at the new rates would be dozens of each in one frame
+    let cars = (game.cars.len() + game.traffic.backlog.len()).saturating_sub(cars_before);
+    assert!(cars <= 4, "{} cars appeared in one frame", cars);
+    assert!(game.pedestrians.len().saturating_sub(pedestrians_before) <= 4);
+    assert!(game.traffic.cyclist_backlog.len() <= 4);
+    assert!(game.traffic.next_arrival.values().all(|&time| time > 60.0));
+    assert!(game.traffic.next_pedestrian.values().all(|&time| time > 60.0));
+    assert!(game.traffic.next_cyclist.values().all(|&time| time > 60.0));
+}
+
+#[test]
+fn numbers_out_of_range_are_refused() {
+    for line in [
+        "--speed -1",
+        "--speed 0",
+        "--duration 0",
+        "--duration -30",
+        "--ttc 0",
+        "--ttc -1.5",
+        "--deadlock-timeout 0",
+        "--deadlock-timeout -5",
+        "--rate -10",
+        "--rate 10,10,-1,10",
+        "--pedestrians -1",
+        "--cyclists -2",
+        "--speed NaN",
+        "--duration inf",
+    ] {
+        assert!(Options::parse(&args(line)).is_err(), "{}", line);
+    }
+
+    let options = Options::parse(&args("--rate 0,10,0,5 --pedestrians 0 --ttc 0.5 --deadlock-timeout 3 --duration 1")).unwrap();
+    assert_eq!(options.rates, Some(vec![0.0, 10.0, 0.0, 5.0]));
+    assert_eq!(options.ttc, Some(0.5));
+}
+
+#[test]
+fn network_runs_refuse_what_they_cant_apply() {
+    for flag in ["--scenario s.json", "--rate 10", "--events e.jsonl", "--events-car 1,2", "--save s.json", "--save-at 30"] {
+        let line = format!("--network n.json {}", flag);
+        let error = Options::parse(&args(&line)).unwrap_err();
+        assert!(error.contains(flag.split(' ').next().unwrap()), "{}", error);
+    }
+
+    let options = Options::parse(&args("--network scenarios/corridor_network.json --ttc 2.5 --deadlock-timeout 7")).unwrap();
+    let network = options.build_network(options.network.as_ref().unwrap()).unwrap();
+    for node in &network.nodes {
+        assert_eq!(node.game.ttc_threshold, 2.5);
+        assert_eq!(node.game.deadlocks.timeout, 7.0);
+    }
+}
+
+#[test]
+fn overrides_are_checked_like_a_scenario_file() {
+    for line in ["--junction roundabout --cyclists 2", "--junction roundabout --pedestrians 2"] {
+        let options = Options::parse(&args(line)).unwrap();
+        assert!(options.build_game().is_err(), "{}", line);
+    }
+
+    // Demand for every leg only goes where the t-junction has a road
+    let options = Options::parse(&args("--scenario scenarios/t_junction.json --pedestrians 2 --cyclists 2")).unwrap();
+    let game = options.build_game().unwrap();
+    assert_eq!(game.scenario.crosswalks(), [Direction::South, Direction::East, Direction::West]);
+    assert_eq!(game.scenario.bike_lanes(), [Direction::East, Direction::West]);
+}
+
+#[test]
+fn running_to_the_end_needs_a_duration() {
+    let mut game = Game::with_seed(1);
+    game.verbose = false;
+    assert!(game.run_to_end().is_err());
+    assert_eq!(game.sim_time, 0.0);
+
+    game.scenario.duration = Some(1.0);
+    game.run_to_end().unwrap();
+    assert!(game.sim_time >= 1.0);
+}
@@ -0,0 +1,118 @@
+// Crosswalks on the legs of the cross junction: cars stop for people on them, people wait at the
+// kerb while a car is on them.
+
+use smart_road::collision::car_footprint;
+use smart_road::deadlock::{pedestrian_node, WaitReason};
+use smart_road::crosswalk::{Pedestrian, PEDESTRIAN_SIZE};
+use smart_road::game::{Game, HEADLESS_STEP};
+use smart_road::lanes::lane_position;
+use smart_road::scenario::{ScheduledSpawn, Scenario};
+use smart_road::types::*;
+use std::path::Path;
+
+// Some car body covers the square a pedestrian takes up
+fn touches_a_car(game: &Game) -> bool {
+    game.pedestrians.iter().any(|pedestrian| {
+        let (x, y) = pedestrian.position();
+        let half = PEDESTRIAN_SIZE / 2.0;
+        game.cars.iter().any(|car| {
+            let corners = car_footprint(car);
+            let min = |f: fn(&Vec2) -> f32| corners.iter().map(f).fold(f32::MAX, f32::min);
+            let max = |f: fn(&Vec2) -> f32| corners.iter().map(f).fold(f32::MIN, f32::max);
+            x + half > min(|c| c.x) && x - half < max(|c| c.x) && y + half > min(|c| c.y) && y - half < max(|c| c.y)
+        })
+    })
+}
+
+#[test]
+fn cars_give_way_and_never_touch_pedestrians() {
+    let mut game = Game::with_seed(3);
+    game.verbose = false;
+    game.scenario = Scenario::load(Path::new("scenarios/crosswalks.json")).unwrap();
+
+    while game.app_state == AppState::Running {
+        game.update(HEADLESS_STEP);
+        assert!(!touches_a_car(&game), "a car ran into a pedestrian at {:.2} s", game.sim_time);
+    }
+    assert_eq!(game.stats.crashes, 0);
+    assert!(game.stats.pedestrian_waits.len() > 40, "{} pedestrians got across", game.stats.pedestrian_waits.len());
+    assert!(game.stats.pedestrian_yield_time > 0.0);
+    assert!(game.stats.cars_passed > 120, "{} cars passed", game.stats.cars_passed);
+}
+
+#[test]
+fn a_turning_car_stops_for_someone_on_its_exit() {
+    let mut game = Game::with_seed(0);
+    game.verbose = false;
+    game.scenario.spawns = vec![ScheduledSpawn { time: 0.0, direction: Direction::North, route: Route::Right, lane: None }];
+    game.scenario.duration = Some(60.0);
+
+    // Someone steps onto the east crosswalk in the car's exit lane as it comes round the corner
+    while !game.cars.first().is_some_and(|car| car.rotated) {
+        game.update(HEADLESS_STEP);
+    }
+    let mut pedestrian = Pedestrian::new(1, Direction::East, true, game.sim_time);
+    pedestrian.position = lane_position(&Direction::East, game.cars[0].lane);
+    pedestrian.crossing = true;
+    game.pedestrians.push(pedestrian);
+
+    while game.app_state == AppState::Running && (game.stats.cars_passed == 0 || !game.pedestrians.is_empty()) {
+        game.update(HEADLESS_STEP);
+        assert!(!touches_a_car(&game), "the car ran into the pedestrian at {:.2} s", game.sim_time);
+    }
+    assert_eq!(game.stats.cars_passed, 1);
+    assert_eq!(game.stats.pedestrian_waits, [0.0]);
+    assert!(game.stats.pedestrian_yield_time > 0.0);
+}
+
+#[test]
+fn crosswalks_need_a_cross_junction_and_a_road() {
+    let mut roundabout = Scenario { junction: Junction::Roundabout, ..Scenario::default() };
+    roundabout.set_pedestrian_rate(2.0);
+    assert!(roundabout.prepare().is_err());
+
+    // The t-junction has no road to the north
+    let mut t_junction = Scenario::load(Path::new("scenarios/t_junction.json")).unwrap();
+    t_junction.pedestrian_rates.insert(Direction::North, 2.0);
+    assert!(t_junction.prepare().is_err());
+    t_junction.pedestrian_rates.remove(&Direction::North);
+    t_junction.pedestrian_rates.insert(Direction::South, 2.0);
+    assert!(t_junction.prepare().is_ok());
+    assert_eq!(t_junction.crosswalks(), [Direction::South]);
+}
+
+#[test]
+fn waits_for_and_on_pedestrians_are_in_the_wait_for_graph() {
+    let mut game = Game::with_seed(0);
+    game.verbose = false;
+    game.scenario.spawns = vec![ScheduledSpawn { time: 0.0, direction: Direction::North, route: Route::Straight, lane: None }];
+    game.scenario.duration = Some(60.0);
+
+    // Someone starts across the car's crosswalk just before it gets there
+    game.update(HEADLESS_STEP);
+    let lane = game.cars[0].lane;
+    let mut walker = Pedestrian::new(1, Direction::South, true, game.sim_time);
+    walker.position = lane_position(&Direction::North, lane);
+    walker.crossing = true;
+    game.pedestrians.push(walker);
+    let car_id = game.cars[0].id;
+    while !game.deadlocks.waits_for.contains_key(&car_id) {
+        assert!(game.sim_time < 10.0, "the car never stopped for the pedestrian");
+        game.update(HEADLESS_STEP);
+    }
+    let waits = &game.deadlocks.waits_for[&car_id];
+    assert!(waits.iter().any(|w| w.on == pedestrian_node(1) && w.reason == WaitReason::Crossing));
+
+    // Someone at the kerb of the crosswalk the car is on waits for it
+    while game.pedestrians.iter().any(|p| p.moving) {
+        game.update(HEADLESS_STEP);
+    }
+    game.pedestrians.clear();
+    while !game.cars[0].rotated && game.cars[0].y < 560.0 {
+        game.update(HEADLESS_STEP);
+    }
+    game.pedestrians.push(Pedestrian::new(2, Direction::North, true, game.sim_time));
+    game.update(HEADLESS_STEP);
+    let waits = &game.deadlocks.waits_for[&pedestrian_node(2)];
+    assert!(waits.iter().any(|w| w.on == car_id && w.reason == WaitReason::Crossing));
+}
@@ -0,0 +1,95 @@
+// Cyclists on bike lanes in the kerb slot of the cross junction: they ride straight through and
+// the manager decides between them and the right turns that cut across their lane.
+
+use smart_road::collision::{build_car_tracking, car_bounds};
+use smart_road::game::{Game, HEADLESS_STEP};
+use smart_road::lanes::{lane_position, lane_start, BIKE_LANE};
+use smart_road::scenario::{ScheduledSpawn, Scenario};
+use smart_road::spatial::{SpatialGrid, CELL_SIZE};
+use smart_road::types::*;
+use std::path::Path;
+
+#[test]
+fn cyclists_keep_to_their_lane_without_a_crash() {
+    let mut game = Game::with_seed(5);
+    game.verbose = false;
+    game.scenario = Scenario::load(Path::new("scenarios/cyclists.json")).unwrap();
+
+    while game.app_state == AppState::Running {
+        game.update(HEADLESS_STEP);
+        for bicycle in game.cars.iter().filter(|car| car.kind == VehicleKind::Bicycle) {
+            let across = match bicycle.direction {
+                Direction::North | Direction::South => bicycle.x,
+                Direction::East | Direction::West => bicycle.y,
+            };
+            assert_eq!(across, lane_position(&bicycle.direction, BIKE_LANE), "cyclist {} left its lane", bicycle.id);
+            assert!(bicycle.speed <= BICYCLE_SPEED);
+        }
+    }
+    assert_eq!(game.stats.crashes, 0);
+    assert_eq!(game.stats.deadlocks, 0);
+    assert!(game.stats.cyclist_trips.len() > 50, "{} cyclists rode through", game.stats.cyclist_trips.len());
+    assert!(game.stats.cars_passed > 150, "{} cars passed", game.stats.cars_passed);
+    assert!(game.stats.trips.iter().all(|trip| trip.id != game.stats.cyclist_trips[0].id));
+}
+
+#[test]
+fn a_right_turn_and_the_cyclist_beside_it_take_turns() {
+    let mut game = Game::with_seed(0);
+    game.verbose = false;
+    game.scenario.lanes.add_bike_lanes();
+    game.scenario.spawns = vec![ScheduledSpawn { time: 1.0, direction: Direction::North, route: Route::Right, lane: None }];
+    game.scenario.duration = Some(30.0);
+    game.scenario.prepare().unwrap();
+
+    // The car catches up with the cyclist just before the corner
+    assert!(game.spawn_bicycle(Direction::North));
+    game.run_to_end().unwrap();
+
+    assert_eq!(game.stats.crashes, 0);
+    assert_eq!(game.stats.cars_passed, 1);
+    assert_eq!(game.stats.cyclist_trips.len(), 1);
+    assert!(game.stats.yield_time > 0.0, "nobody gave way");
+    assert_eq!(game.stats.right_hooks, game.stats.close_call);
+}
+
+#[test]
+fn bike_lanes_take_the_kerb_slot_of_a_cross_junction() {
+    let mut scenario = Scenario::default();
+    scenario.set_cyclist_rate(2.0);
+    assert!(scenario.prepare().is_ok());
+    assert_eq!(scenario.lanes.lanes(&Direction::North), [vec![Route::Left, Route::UTurn], vec![Route::Straight, Route::Right]]);
+    assert_eq!(scenario.lanes.exit_lanes(&Direction::East), 2);
+    assert_eq!(scenario.bike_lanes(), Direction::ALL);
+
+    let mut roundabout = Scenario { junction: Junction::Roundabout, ..Scenario::default() };
+    roundabout.set_cyclist_rate(2.0);
+    assert!(roundabout.prepare().is_err());
+
+    // The t-junction has no road to the north
+    let mut t_junction = Scenario::load(Path::new("scenarios/t_junction.json")).unwrap();
+    t_junction.cyclist_rates.insert(Direction::North, 2.0);
+    assert!(t_junction.prepare().is_err());
+}
+
+#[test]
+fn right_turns_only_track_cyclists() {
+    // Two cars turning right from the same lane, one close behind the other, and a cyclist
+    let (x, y) = lane_start(&Direction::North, 1);
+    let mut cyclist = Car::bicycle(3, Direction::North, 0.0);
+    cyclist.y = y + 200.0;
+    let cars = vec![
+        Car::new(1, Direction::North, Route::Right, x, y + 200.0, 0.0),
+        Car::new(2, Direction::North, Route::Right, x, y + 150.0, 1.0),
+        cyclist,
+    ];
+    let mut grid = SpatialGrid::new(CELL_SIZE);
+    for (i, car) in cars.iter().enumerate() {
+        grid.insert(i, car_bounds(car));
+    }
+    let tracking = build_car_tracking(&cars, &grid);
+    let tracked = |id: usize| -> Vec<usize> { tracking.get(&id).map_or(Vec::new(), |others| others.iter().map(|t| t.id).collect()) };
+    assert_eq!(tracked(1), [3]);
+    assert_eq!(tracked(2), [3]);
+    assert!(tracked(3).contains(&1));
+}
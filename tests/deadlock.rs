@@ -0,0 +1,123 @@
+// The deadlock detector on hand-built wait-for graphs: a cycle gets one car released, a car
+// stopped behind a moving one only once it has waited out the timeout, and a deadlock nobody
+// can be released from is still reported.
+
+use smart_road::deadlock::*;
+use smart_road::types::*;
+
+fn stopped_cars(count: usize) -> Vec<Car> {
+    (1..=count)
+        .map(|id| Car::new(id, Direction::ALL[id % 4].clone(), Route::Straight, 0.0, 0.0, id as f32))
+        .collect()
+}
+
+fn released_in_cycle(size: usize) -> (Vec<usize>, usize) {
+    let cars = stopped_cars(size);
+    let mut detector = DeadlockDetector::new();
+    let mut stats = Stats::default();
+    for id in 1..=size {
+        detector.wait(id, id % size + 1, WaitReason::Yield);
+    }
+    let released: Vec<usize> = detector.resolve(&cars, 0.0, &mut stats).into_iter().filter_map(|deadlock| deadlock.released).collect();
+    (released, stats.deadlocks)
+}
+
+#[test]
+fn two_car_cycle_releases_the_oldest() {
+    let (released, deadlocks) = released_in_cycle(2);
+    assert_eq!(released, vec![1]);
+    assert_eq!(deadlocks, 1);
+}
+
+#[test]
+fn three_car_cycle_releases_exactly_one() {
+    let (released, deadlocks) = released_in_cycle(3);
+    assert_eq!(released, vec![1]);
+    assert_eq!(deadlocks, 1);
+}
+
+#[test]
+fn cars_held_by_a_body_are_not_released() {
+    let cars = stopped_cars(2);
+    let mut detector = DeadlockDetector::new();
+    let mut stats = Stats::default();
+    detector.wait(1, 2, WaitReason::Blocked);
+    detector.wait(2, 1, WaitReason::Yield);
+    let released: Vec<usize> = detector.resolve(&cars, 0.0, &mut stats).into_iter().filter_map(|deadlock| deadlock.released).collect();
+    // Car 1 is older but letting it go wouldn't move it
+    assert_eq!(released, vec![2]);
+    assert!(detector.is_released(2, RELEASE_TIME - 0.1));
+    assert!(!detector.is_released(2, RELEASE_TIME));
+}
+
+#[test]
+fn cycles_through_a_pedestrian_release_a_car() {
+    // Car 1 stops for someone who waits at the kerb for car 2, which yields to car 1
+    let cars = stopped_cars(2);
+    let mut detector = DeadlockDetector::new();
+    let mut stats = Stats::default();
+    detector.wait(1, pedestrian_node(1), WaitReason::Crossing);
+    detector.wait(pedestrian_node(1), 2, WaitReason::Crossing);
+    detector.wait(2, 1, WaitReason::Yield);
+    let released: Vec<usize> = detector.resolve(&cars, 0.0, &mut stats).into_iter().filter_map(|deadlock| deadlock.released).collect();
+    assert_eq!(released, vec![2]);
+    assert_eq!(stats.deadlocks, 1);
+}
+
+#[test]
+fn waits_shorter_than_the_timeout_are_left_alone() {
+    let mut cars = stopped_cars(2);
+    cars[1].velocity = Vec2::new(0.0, 50.0); // car 2 keeps moving, there is no cycle
+    let mut detector = DeadlockDetector::new();
+    let mut stats = Stats::default();
+
+    let mut now = 0.0;
+    while now <= DEADLOCK_TIMEOUT {
+        detector.clear_waits();
+        detector.wait(1, 2, WaitReason::Yield);
+        assert!(detector.resolve(&cars, now, &mut stats).is_empty(), "released after {} s", now);
+        now += 0.5;
+    }
+    detector.clear_waits();
+    detector.wait(1, 2, WaitReason::Yield);
+    let released = detector.resolve(&cars, now, &mut stats);
+    assert_eq!(released.len(), 1);
+    assert_eq!(released[0].released, Some(1));
+    assert_eq!(stats.deadlocks, 1);
+}
+
+#[test]
+fn cycles_nobody_can_leave_are_reported_once() {
+    let cars = stopped_cars(2);
+    let mut detector = DeadlockDetector::new();
+    let mut stats = Stats::default();
+    let mut found = Vec::new();
+    for step in 0..3 {
+        detector.clear_waits();
+        detector.wait(1, 2, WaitReason::Blocked);
+        detector.wait(2, 1, WaitReason::Blocked);
+        found.extend(detector.resolve(&cars, step as f32 * 0.1, &mut stats));
+    }
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].car, 1);
+    assert_eq!(found[0].released, None);
+    assert_eq!(stats.deadlocks, 0);
+    assert_eq!(stats.unresolved_deadlocks, 1);
+}
+
+#[test]
+fn stuck_cars_nothing_can_free_are_reported() {
+    let cars = stopped_cars(2);
+    let mut detector = DeadlockDetector::new();
+    let mut stats = Stats::default();
+    detector.wait(1, 2, WaitReason::Gate);
+    assert!(detector.resolve(&cars, 0.0, &mut stats).is_empty());
+
+    detector.clear_waits();
+    detector.wait(1, 2, WaitReason::Gate);
+    let found = detector.resolve(&cars, DEADLOCK_TIMEOUT + 1.0, &mut stats);
+    // Car 2 stopped too and waits on nobody, neither only yields to the manager
+    assert_eq!(found.iter().map(|deadlock| (deadlock.car, deadlock.released)).collect::<Vec<_>>(), [(1, None), (2, None)]);
+    assert_eq!(stats.deadlocks, 0);
+    assert_eq!(stats.unresolved_deadlocks, 2);
+}
@@ -0,0 +1,108 @@
+// The event log as tests see it: ordered per car, filterable, one JSON object per line.
+
+use smart_road::events::*;
+use smart_road::game::Game;
+use smart_road::scenario::ScheduledSpawn;
+use smart_road::types::*;
+
+// Crossing straight movements, so the manager has to decide between the two cars
+fn crossing_game(events: EventLog) -> Game {
+    let mut game = Game::with_seed(0);
+    game.verbose = false;
+    game.events = events;
+    game.scenario.spawns = vec![
+        ScheduledSpawn { time: 0.0, direction: Direction::East, route: Route::Straight, lane: None },
+        ScheduledSpawn { time: 0.5, direction: Direction::South, route: Route::Straight, lane: None },
+    ];
+    game.scenario.duration = Some(20.0);
+    game.run_to_end().unwrap();
+    game
+}
+
+fn event_names(events: &EventLog, car_id: usize) -> Vec<&'static str> {
+    events
+        .for_car(car_id)
+        .filter(|event| event.car == Some(car_id))
+        .map(|event| match event.kind {
+            EventKind::Spawned { .. } => "spawned",
+            EventKind::EnteredIntersection => "entered",
+            EventKind::Exited { .. } => "exited",
+            _ => "other",
+        })
+        .filter(|name| *name != "other")
+        .collect()
+}
+
+#[test]
+fn every_car_spawns_enters_and_exits_in_order() {
+    let game = crossing_game(EventLog::recording());
+    for car_id in [1, 2] {
+        assert_eq!(event_names(&game.events, car_id), ["spawned", "entered", "exited"]);
+    }
+    assert!(game.events.events.windows(2).all(|pair| pair[0].time <= pair[1].time));
+}
+
+#[test]
+fn yields_are_logged_once_per_car_waited_on() {
+    let game = crossing_game(EventLog::recording());
+    let yields: Vec<_> = game.events.events.iter().filter(|e| matches!(e.kind, EventKind::YieldedTo { .. })).collect();
+    assert!(!yields.is_empty());
+    assert!(game.events.events.iter().any(|e| matches!(e.kind, EventKind::WonPriority { .. })));
+    for (i, event) in yields.iter().enumerate() {
+        assert!(!yields[..i].iter().any(|other| other.car == event.car && other.kind == event.kind));
+    }
+}
+
+#[test]
+fn nothing_is_kept_without_a_file_to_write_to() {
+    let game = crossing_game(EventLog::new());
+    assert!(game.events.events.is_empty());
+    assert_eq!(game.stats.cars_passed, 2);
+}
+
+#[test]
+fn filter_keeps_only_the_chosen_cars() {
+    let game = crossing_game(EventLog::for_cars(&[2]));
+    assert!(!game.events.events.is_empty());
+    assert!(game.events.events.iter().all(|event| event.involves(2)));
+}
+
+#[test]
+fn json_lines_are_one_event_each() {
+    let game = crossing_game(EventLog::recording());
+    let lines = game.events.to_json_lines().unwrap();
+    assert_eq!(lines.lines().count(), game.events.events.len());
+    for line in lines.lines() {
+        let value: serde_json::Value = serde_json::from_str(line).unwrap();
+        assert!(value["time"].is_number() && value["event"].is_string());
+    }
+}
+
+#[test]
+fn cars_leaving_in_the_last_frame_are_written() {
+    let path = std::env::temp_dir().join(format!("smart-road-events-last-{}.jsonl", std::process::id()));
+    let run = |duration: f32| {
+        let mut game = Game::with_seed(0);
+        game.verbose = false;
+        game.events = EventLog::recording();
+        game.events_path = Some(path.clone());
+        game.scenario.spawns = vec![ScheduledSpawn { time: 0.0, direction: Direction::East, route: Route::Straight, lane: None }];
+        game.scenario.duration = Some(duration);
+        game.run_to_end().unwrap();
+        game
+    };
+    let exit_time = run(20.0)
+        .events
+        .for_car(1)
+        .find(|event| matches!(event.kind, EventKind::Exited { .. }))
+        .unwrap()
+        .time;
+
+    // End the run on the frame the car leaves in
+    let game = run(exit_time);
+    let written = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(game.stats.cars_passed, 1);
+    assert_eq!(written.lines().filter(|line| line.contains("\"exited\"")).count(), 1);
+}
@@ -0,0 +1,134 @@
+// Batch runs: every junction x manager x demand x seed combination runs once, a seed gives the same
+// replicate whichever thread runs it, and the results table lists them in order.
+
+use smart_road::experiment::*;
+use smart_road::scenario::Scenario;
+use smart_road::types::Junction;
+use std::fs;
+
+const DURATION: f32 = 20.0;
+
+fn experiment(name: &str) -> Experiment {
+    Experiment {
+        name: name.to_string(),
+        duration: DURATION,
+        managers: vec!["reference-point".to_string()],
+        junctions: vec![Junction::Cross, Junction::Roundabout],
+        demand: vec![10.0, 20.0],
+        seeds: vec![1, 2],
+        scenario: None,
+    }
+}
+
+#[test]
+fn runs_cover_every_combination_in_order() {
+    let runs = experiment("order").runs().unwrap();
+    assert_eq!(runs.len(), 8);
+    let seeds: Vec<u64> = runs.iter().map(|run| run.seed).collect();
+    assert_eq!(seeds, vec![1, 2, 1, 2, 1, 2, 1, 2]);
+    let demand: Vec<f32> = runs.iter().map(|run| run.demand).collect();
+    assert_eq!(demand, vec![10.0, 10.0, 20.0, 20.0, 10.0, 10.0, 20.0, 20.0]);
+
+    let mut unknown = experiment("unknown");
+    unknown.managers.push("traffic-lights".to_string());
+    assert!(unknown.runs().is_err());
+
+    // The roundabout has no manager to compare
+    let mut both = experiment("both");
+    both.managers.push("first-come".to_string());
+    let runs = both.runs().unwrap();
+    assert_eq!(runs.len(), 12);
+    assert_eq!(runs.iter().filter(|run| run.junction == Junction::Roundabout).count(), 4);
+}
+
+#[test]
+fn experiment_files_are_checked() {
+    let dir = std::env::temp_dir().join(format!("smart-road-experiment-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let load = |json: &str| {
+        let path = dir.join("experiment.json");
+        fs::write(&path, json).unwrap();
+        Experiment::load(&path)
+    };
+    let valid = load(r#"{ "name": "ok", "duration": 20, "managers": ["first-come"], "demand": [10], "seeds": [1] }"#).unwrap();
+    assert_eq!(valid.junctions, [Junction::Cross]);
+    for json in [
+        r#"{ "name": "typo", "duration": 20, "managers": ["first-come"], "demand": [10], "seed": [1] }"#,
+        r#"{ "name": "typo", "duration": 20, "managers": ["first-come"], "demand": [10], "seeds": [1], "scenaro": "a.json" }"#,
+        r#"{ "name": "zero", "duration": 0, "managers": ["first-come"], "demand": [10], "seeds": [1] }"#,
+        r#"{ "name": "empty", "duration": 20, "managers": [], "demand": [10], "seeds": [1] }"#,
+        r#"{ "name": "empty", "duration": 20, "managers": ["first-come"], "demand": [], "seeds": [1] }"#,
+        r#"{ "name": "empty", "duration": 20, "managers": ["first-come"], "demand": [10], "seeds": [] }"#,
+        r#"{ "name": "empty", "duration": 20, "managers": ["first-come"], "junctions": [], "demand": [10], "seeds": [1] }"#,
+        r#"{ "name": "negative", "duration": 20, "managers": ["first-come"], "demand": [-5], "seeds": [1] }"#,
+    ] {
+        assert!(load(json).is_err(), "{}", json);
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn replicates_are_seeded() {
+    let runs = experiment("seeds").runs().unwrap();
+    let base = Scenario::default();
+    let first = runs[0].execute(&base, DURATION).unwrap();
+    assert_eq!(first.seed, 1);
+    assert_eq!(first.csv_row(), runs[0].execute(&base, DURATION).unwrap().csv_row());
+    assert_ne!(first.csv_row(), runs[1].execute(&base, DURATION).unwrap().csv_row());
+}
+
+#[test]
+fn batch_table_has_a_row_per_run() {
+    let dir = std::env::temp_dir().join(format!("smart-road-batch-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let experiment_path = dir.join("experiment.json");
+    fs::write(
+        &experiment_path,
+        r#"{ "name": "tiny", "duration": 20, "managers": ["reference-point"], "junctions": ["cross", "roundabout"], "demand": [10, 20], "seeds": [1, 2] }"#,
+    )
+    .unwrap();
+
+    let args: Vec<String> = [experiment_path.to_str().unwrap(), "--output", dir.to_str().unwrap(), "--threads", "3"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    run_batch(&args).unwrap();
+
+    let table = fs::read_to_string(dir.join("tiny_results.csv")).unwrap();
+    let rows: Vec<&str> = table.lines().skip(1).collect();
+    let runs = experiment("tiny").runs().unwrap();
+    assert_eq!(rows.len(), runs.len());
+    let base = Scenario { name: "tiny".to_string(), ..Scenario::default() };
+    for (row, run) in rows.iter().zip(&runs) {
+        // Threads finish in any order, the table is still in run order
+        assert_eq!(*row, format!("{},{}", run.demand, run.execute(&base, DURATION).unwrap().csv_row()));
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn runs_the_base_scenario_cant_take_stop_the_batch() {
+    // Cyclists have no bike lanes on the roundabout
+    let mut base = Scenario::default();
+    base.set_cyclist_rate(2.0);
+    let runs = experiment("cyclists").runs().unwrap();
+    assert!(runs[0].execute(&base, DURATION).is_ok());
+    let roundabout = runs.iter().find(|run| run.junction == Junction::Roundabout).unwrap();
+    assert!(roundabout.execute(&base, DURATION).is_err());
+
+    let dir = std::env::temp_dir().join(format!("smart-road-batch-invalid-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("cyclists.json"), r#"{ "cyclist_rates": { "North": 2 } }"#).unwrap();
+    let experiment_path = dir.join("experiment.json");
+    fs::write(
+        &experiment_path,
+        r#"{ "name": "invalid", "duration": 20, "managers": ["reference-point"], "junctions": ["cross", "roundabout"], "demand": [10], "seeds": [1], "scenario": "cyclists.json" }"#,
+    )
+    .unwrap();
+    let args: Vec<String> = [experiment_path.to_str().unwrap(), "--output", dir.to_str().unwrap()].iter().map(|s| s.to_string()).collect();
+    let error = run_batch(&args).unwrap_err();
+    let wrote = dir.join("invalid_results.csv").exists();
+    fs::remove_dir_all(&dir).unwrap();
+    assert!(error.contains("Bike lanes"), "{}", error);
+    assert!(!wrote);
+}
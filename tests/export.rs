@@ -0,0 +1,97 @@
+// Exported files: every CSV row has as many columns as its header, and names that would break
+// a row come back out of the quotes unchanged.
+
+use smart_road::export::*;
+use smart_road::game::Game;
+use std::fs;
+use std::path::PathBuf;
+
+// Split a CSV line, commas inside quotes don't split and "" is a quote
+fn fields(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                fields.last_mut().unwrap().push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            _ => fields.last_mut().unwrap().push(c),
+        }
+    }
+    fields
+}
+
+fn export_dir(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("smart-road-{}-{}", name, std::process::id()))
+}
+
+fn finished_game() -> Game {
+    let mut game = Game::with_seed(21);
+    game.verbose = false;
+    game.scenario.name = "rush \"hour\", am".to_string();
+    game.scenario.set_rate(20.0);
+    game.scenario.duration = Some(60.0);
+    game.run_to_end().unwrap();
+    game
+}
+
+#[test]
+fn csv_rows_match_their_headers() {
+    let game = finished_game();
+    let dir = export_dir("csv");
+    let paths = export_stats(&dir, ExportFormat::Csv, &game.run_info(), &game.stats).unwrap();
+    assert_eq!(paths.len(), 5);
+
+    for path in &paths {
+        let text = fs::read_to_string(path).unwrap();
+        let mut lines = text.lines();
+        let header = fields(lines.next().unwrap());
+        let mut rows = 0;
+        for line in lines {
+            let row = fields(line);
+            assert_eq!(row.len(), header.len(), "{}: {}", path.display(), line);
+            assert_eq!(row[1], "rush \"hour\", am");
+            rows += 1;
+        }
+        assert!(rows > 0, "{} has no rows", path.display());
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn summary_header_and_row_agree() {
+    let game = finished_game();
+    let summary = RunSummary::new(&game.run_info(), &game.stats);
+    let header = fields(RunSummary::csv_header());
+    let row = fields(&summary.csv_row());
+    assert_eq!(header.len(), row.len());
+    let passed = header.iter().position(|column| column == "cars_passed").unwrap();
+    assert_eq!(row[passed], game.stats.cars_passed.to_string());
+}
+
+#[test]
+fn fields_are_quoted_only_when_needed() {
+    assert_eq!(csv_field("plain"), "plain");
+    assert_eq!(csv_field("a,b"), "\"a,b\"");
+    assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+}
+
+#[test]
+fn json_export_holds_the_whole_run() {
+    let game = finished_game();
+    let dir = export_dir("json");
+    let paths = export_stats(&dir, ExportFormat::Json, &game.run_info(), &game.stats).unwrap();
+    assert_eq!(paths.len(), 1);
+
+    let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(&paths[0]).unwrap()).unwrap();
+    assert_eq!(json["run"]["scenario"], "rush \"hour\", am");
+    assert_eq!(json["summary"]["cars_passed"], game.stats.cars_passed);
+    assert_eq!(json["trips"].as_array().unwrap().len(), game.stats.trips.len());
+    assert_eq!(json["timeseries"].as_array().unwrap().len(), game.stats.samples.len());
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,62 @@
+// The footprint guard: whatever the manager decided, a move that would leave a car's body in
+// another car is taken back, or finished with the old heading or lane.
+
+use smart_road::collision::footprints_overlap;
+use smart_road::game::{Game, HEADLESS_STEP};
+use smart_road::movement::MoveSnapshot;
+use smart_road::scenario::ScheduledSpawn;
+use smart_road::types::*;
+use std::collections::HashMap;
+
+#[test]
+fn a_left_turn_swinging_into_the_next_lane_is_held_back() {
+    // The manager only compares the cars along their own lanes, the left turn's body swings
+    // into the straight lane beside it as it turns
+    let mut game = Game::with_seed(0);
+    game.verbose = false;
+    game.scenario.spawns = vec![
+        ScheduledSpawn { time: 0.0, direction: Direction::East, route: Route::Left, lane: None },
+        ScheduledSpawn { time: 0.7, direction: Direction::East, route: Route::Straight, lane: None },
+    ];
+    game.scenario.duration = Some(30.0);
+    while game.app_state == AppState::Running {
+        game.update(HEADLESS_STEP);
+        if let [a, b] = &game.cars[..] {
+            assert!(!footprints_overlap(a, b), "overlap at {:.2} s", game.sim_time);
+        }
+    }
+    assert!(game.stats.footprint_hold_time > 0.0, "the guard never had to step in");
+    assert_eq!(game.stats.cars_passed, 2);
+    assert_eq!(game.stats.crashes, 0);
+}
+
+#[test]
+fn snapshot_takes_back_a_turn_a_lane_change_or_the_whole_move() {
+    let mut car = Car::new(1, Direction::North, Route::Left, 500.0, 100.0, 0.0);
+    let mut inside: HashMap<CollisionType, Vec<usize>> = HashMap::new();
+    inside.insert(CollisionType::NL, vec![1]);
+    let snapshot = MoveSnapshot::take(&car, &inside);
+    assert!(!snapshot.keep_heading(&mut car), "nothing to undo yet");
+    assert!(!snapshot.keep_lane(&mut car));
+
+    // Turned and moved on
+    car.rotation = 1.0;
+    car.rotated = true;
+    car.y += 5.0;
+    assert!(snapshot.keep_heading(&mut car));
+    assert_eq!((car.rotation, car.rotated, car.y), (0.0, false, 105.0), "the move itself stays");
+
+    // Drifted towards the next lane
+    car.x += 3.0;
+    car.lane += 1;
+    assert!(snapshot.keep_lane(&mut car));
+    assert_eq!((car.x, car.lane), (500.0, 0));
+
+    // Entered the crossing, which also put it on another list
+    car.entered = true;
+    inside.insert(CollisionType::ES, vec![1]);
+    snapshot.restore(&mut car, &mut inside);
+    assert_eq!((car.x, car.y, car.entered), (500.0, 100.0, false));
+    assert_eq!(inside[&CollisionType::NL], vec![1]);
+    assert!(inside[&CollisionType::ES].is_empty());
+}
@@ -0,0 +1,174 @@
+// Safety invariants of the simulation core, checked headlessly on random spawn sequences.
+// Every yield the manager gives is kept, the only thing that can take back a move is the
+// footprint guard, which has its own tests in footprint_guard.rs.
+// INVARIANT_CASES=<n> changes how many sequences each manager is run on.
+
+use smart_road::collision::{footprints_overlap, Manager};
+use smart_road::game::{Game, HEADLESS_STEP};
+use smart_road::scenario::ScheduledSpawn;
+use smart_road::types::*;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+const DEFAULT_CASES: u64 = 1000;
+const MAX_CARS: usize = 16;
+const SPAWN_WINDOW: f32 = 20.0; // seconds over which a sequence's cars appear
+const MAX_STOPPED: f32 = 15.0; // longest a car may stand still before it counts as a deadlock
+const DRAIN_LIMIT: f32 = 120.0; // time after the last spawn for every car to leave
+const PRINTED_FAILURES: usize = 3;
+const U_TURN_SHARE: f32 = 0.1; // U-turns are rare, they take the crossing alone like a left turn
+
+fn random_spawns(seed: u64) -> Vec<ScheduledSpawn> {
+    let mut rng = fastrand::Rng::with_seed(seed);
+    let count = rng.usize(1..=MAX_CARS);
+    let mut spawns: Vec<ScheduledSpawn> = (0..count)
+        .map(|_| ScheduledSpawn {
+            time: (rng.f32() * SPAWN_WINDOW * 10.0).round() / 10.0,
+            direction: Direction::ALL[rng.usize(..Direction::ALL.len())].clone(),
+            route: if rng.f32() < U_TURN_SHARE {
+                Route::UTurn
+            } else {
+                Route::ALL[rng.usize(..Route::ALL.len() - 1)].clone() // UTurn is last
+            },
+            lane: None,
+        })
+        .collect();
+    spawns.sort_by(|a, b| a.time.total_cmp(&b.time));
+    spawns
+}
+
+// Run the spawns until every car has left, the error names the first broken invariant
+fn check_invariants(spawns: &[ScheduledSpawn], manager: Manager) -> Result<(), String> {
+    let mut game = Game::with_seed(0);
+    game.verbose = false;
+    game.manager = manager;
+    game.scenario.spawns = spawns.to_vec();
+
+    let last_spawn = spawns.last().map_or(0.0, |s| s.time);
+    let mut stopped_since: HashMap<usize, ((f32, f32), f32)> = HashMap::new();
+
+    loop {
+        game.update(HEADLESS_STEP);
+        let now = game.sim_time;
+
+        for (i, car) in game.cars.iter().enumerate() {
+            if let Some(other) = game.cars[i + 1..].iter().find(|other| footprints_overlap(car, other)) {
+                return Err(format!("cars {} and {} overlap at {:.2} s", car.id, other.id, now));
+            }
+
+            let (position, since) = stopped_since.entry(car.id).or_insert(((car.x, car.y), now));
+            if *position != (car.x, car.y) {
+                *position = (car.x, car.y);
+                *since = now;
+            } else if now - *since > MAX_STOPPED {
+                return Err(format!("car {} has not moved for {} s at {:.2} s", car.id, MAX_STOPPED, now));
+            }
+        }
+
+        let all_spawned = game.traffic.next_spawn == spawns.len() && game.traffic.backlog.is_empty();
+        if all_spawned && game.cars.is_empty() {
+            break;
+        }
+        if now > last_spawn + DRAIN_LIMIT {
+            return Err(format!("{} cars still on the map at {:.2} s", game.cars.len(), now));
+        }
+    }
+
+    if game.stats.cars_passed != spawns.len() {
+        return Err(format!("{} of {} cars left the map", game.stats.cars_passed, spawns.len()));
+    }
+    if !game.priority_map.is_empty() || !game.priority_ref.is_empty() {
+        return Err(format!(
+            "priority maps not empty with no cars left: {:?} {:?}",
+            game.priority_map, game.priority_ref
+        ));
+    }
+    if game.in_intersection.values().any(|ids| !ids.is_empty()) {
+        return Err(format!("intersection not empty with no cars left: {:?}", game.in_intersection));
+    }
+    Ok(())
+}
+
+// Drop spawns one at a time for as long as the sequence keeps failing
+fn shrink(spawns: &[ScheduledSpawn], manager: Manager, error: String) -> (Vec<ScheduledSpawn>, String) {
+    let mut spawns = spawns.to_vec();
+    let mut error = error;
+    let mut i = 0;
+    while i < spawns.len() {
+        let mut smaller = spawns.clone();
+        smaller.remove(i);
+        match check_invariants(&smaller, manager) {
+            Err(e) => {
+                spawns = smaller;
+                error = e;
+                i = 0;
+            }
+            Ok(()) => i += 1,
+        }
+    }
+    (spawns, error)
+}
+
+// The failing sequence as a scenario file, so it can be replayed with --scenario
+fn scenario_json(spawns: &[ScheduledSpawn]) -> String {
+    let lines: Vec<String> = spawns
+        .iter()
+        .map(|s| format!("    {{ \"time\": {}, \"direction\": \"{:?}\", \"route\": \"{:?}\" }}", s.time, s.direction, s.route))
+        .collect();
+    format!("{{\n  \"spawns\": [\n{}\n  ]\n}}", lines.join(",\n"))
+}
+
+fn check_random_sequences(manager: Manager) {
+    let cases = std::env::var("INVARIANT_CASES")
+        .ok()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(DEFAULT_CASES);
+    let threads = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+
+    let next = AtomicU64::new(0);
+    let failures = Mutex::new(Vec::new());
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| loop {
+                let seed = next.fetch_add(1, Ordering::Relaxed);
+                if seed >= cases {
+                    break;
+                }
+                if let Err(error) = check_invariants(&random_spawns(seed), manager) {
+                    failures.lock().unwrap().push((seed, error));
+                }
+            });
+        }
+    });
+
+    let mut failures = failures.into_inner().unwrap();
+    if failures.is_empty() {
+        return;
+    }
+    failures.sort_by_key(|(seed, _)| *seed);
+    for (seed, error) in failures.iter().take(PRINTED_FAILURES) {
+        let (spawns, error) = shrink(&random_spawns(*seed), manager, error.clone());
+        println!(
+            "seed {} with {}: {}\nshrunk to {} cars:\n{}\n",
+            seed,
+            manager.name(),
+            error,
+            spawns.len(),
+            scenario_json(&spawns)
+        );
+    }
+    let seeds: Vec<String> = failures.iter().map(|(seed, _)| seed.to_string()).collect();
+    panic!("{} of {} sequences failed, seeds: {}", failures.len(), cases, seeds.join(", "));
+}
+
+#[test]
+fn reference_point_keeps_invariants() {
+    check_random_sequences(Manager::ReferencePoint);
+}
+
+#[test]
+fn first_come_keeps_invariants() {
+    check_random_sequences(Manager::FirstCome);
+}
@@ -0,0 +1,144 @@
+// Lane layouts: which lanes a route may use, and cars changing lanes to reach theirs.
+
+use smart_road::collision::footprints_overlap;
+use smart_road::game::{Game, HEADLESS_STEP};
+use smart_road::lanes::*;
+use smart_road::scenario::ScheduledSpawn;
+use smart_road::types::*;
+
+// North has a double left and a shared straight/right lane, East only two lanes
+fn custom_layout() -> LaneLayout {
+    let mut layout = LaneLayout::default();
+    layout.approaches.insert(
+        Direction::North,
+        vec![vec![Route::Left], vec![Route::Left, Route::Straight], vec![Route::Straight, Route::Right]],
+    );
+    layout.approaches.insert(Direction::East, vec![vec![Route::Left], vec![Route::Straight, Route::Right]]);
+    layout
+}
+
+#[test]
+fn routes_use_the_nearest_allowed_lane() {
+    let layout = custom_layout();
+    assert!(layout.validate().is_ok());
+    assert_eq!(layout.lanes_for(&Direction::North, &Route::Left), [0, 1]);
+    assert_eq!(layout.lanes_for(&Direction::North, &Route::Straight), [1, 2]);
+    assert_eq!(layout.target_lane(&Direction::East, 0, &Route::Right), Some(1));
+
+    let mut missing = LaneLayout::default();
+    missing.approaches.insert(Direction::West, vec![vec![Route::Left, Route::Straight]]);
+    assert!(missing.validate().is_err());
+}
+
+#[test]
+fn approaches_have_at_most_three_lanes() {
+    let mut four = LaneLayout::default();
+    four.approaches.insert(
+        Direction::South,
+        vec![vec![Route::Left], vec![Route::Left], vec![Route::Straight], vec![Route::Right]],
+    );
+    assert!(four.validate().is_err());
+}
+
+// The manager doesn't tell lanes apart, two cars turning left side by side share one collision
+// type and one gate list and are never compared with each other
+#[test]
+fn double_left_turns_share_a_collision_type() {
+    let mut game = Game::with_seed(0);
+    game.verbose = false;
+    game.scenario.lanes = custom_layout();
+    game.scenario.spawns = vec![
+        ScheduledSpawn { time: 0.0, direction: Direction::North, route: Route::Left, lane: Some(0) },
+        ScheduledSpawn { time: 0.0, direction: Direction::North, route: Route::Left, lane: Some(1) },
+    ];
+    game.scenario.duration = Some(20.0);
+
+    let mut side_by_side = false;
+    while game.app_state == AppState::Running {
+        game.update(HEADLESS_STEP);
+        if let [a, b] = &game.cars[..] {
+            assert_eq!(a.collision_types[0], b.collision_types[0]);
+            assert!(!footprints_overlap(a, b), "overlap at {:.2} s", game.sim_time);
+            side_by_side |= game.in_intersection.get(&CollisionType::NL).is_some_and(|ids| ids.len() == 2);
+        }
+    }
+    assert!(side_by_side, "both should be let into the crossing together");
+    assert_eq!(game.stats.cars_passed, 2);
+    assert_eq!(game.stats.crashes, 0);
+}
+
+#[test]
+fn cars_change_into_their_lane_before_turning() {
+    let mut game = Game::with_seed(0);
+    game.verbose = false;
+    game.scenario.lanes = custom_layout();
+    game.scenario.spawns = vec![
+        ScheduledSpawn { time: 0.0, direction: Direction::North, route: Route::Right, lane: Some(0) },
+        ScheduledSpawn { time: 0.0, direction: Direction::East, route: Route::Left, lane: Some(1) },
+    ];
+    game.scenario.duration = Some(20.0);
+
+    let mut lanes_at_turn = Vec::new();
+    while game.app_state == AppState::Running {
+        game.update(HEADLESS_STEP);
+        for car in &game.cars {
+            if car.rotated && !lanes_at_turn.iter().any(|&(id, _)| id == car.id) {
+                lanes_at_turn.push((car.id, car.lane));
+            }
+        }
+    }
+
+    lanes_at_turn.sort_unstable();
+    assert_eq!(lanes_at_turn, [(1, 2), (2, 0)]);
+    assert_eq!(game.stats.cars_passed, 2);
+    assert_eq!(game.stats.crashes, 0);
+}
+
+// No road to the north and a two lane road to the west
+fn t_junction() -> LaneLayout {
+    let mut layout = LaneLayout::default();
+    layout.approaches.insert(Direction::South, vec![]);
+    layout.exits.insert(Direction::North, 0);
+    layout.exits.insert(Direction::West, 2);
+    for direction in [Direction::North, Direction::East, Direction::West] {
+        let lanes = layout.default_lanes(&direction);
+        layout.approaches.insert(direction, lanes);
+    }
+    layout
+}
+
+#[test]
+fn missing_legs_leave_out_their_movements() {
+    let layout = t_junction();
+    assert!(layout.validate().is_ok());
+    assert_eq!(layout.lanes(&Direction::North), [vec![Route::Left, Route::UTurn], vec![], vec![Route::Right]]);
+    assert_eq!(layout.lanes(&Direction::West), [vec![Route::Left, Route::UTurn], vec![Route::Straight]]);
+    assert!(!layout.has_movement(&Direction::East, &Route::Left));
+    assert!(!layout.has_movement(&Direction::South, &Route::Straight));
+    assert!(layout.has_movement(&Direction::East, &Route::Right));
+
+    let mut into_closed_exit = t_junction();
+    into_closed_exit.approaches.insert(Direction::East, vec![vec![Route::Left, Route::Straight], vec![Route::Right]]);
+    assert!(into_closed_exit.validate().is_err());
+
+    // The west road has no third slot for a straight car to leave on
+    let mut too_narrow = t_junction();
+    too_narrow.approaches.insert(Direction::West, vec![vec![Route::Left], vec![], vec![Route::Straight]]);
+    assert!(too_narrow.validate().is_err());
+}
+
+#[test]
+fn t_junction_only_spawns_movements_it_has() {
+    let mut game = Game::with_seed(11);
+    game.verbose = false;
+    game.scenario.lanes = t_junction();
+    game.scenario.set_rate(12.0);
+    game.scenario.duration = Some(120.0);
+    game.run_to_end().unwrap();
+
+    assert!(game.stats.cars_passed > 50);
+    assert_eq!(game.stats.crashes, 0);
+    for trip in &game.stats.trips {
+        assert!(game.scenario.lanes.has_movement(&trip.direction, &trip.route), "{:?} {:?}", trip.direction, trip.route);
+    }
+}
@@ -0,0 +1,83 @@
+// Connected cars and human drivers on the same junction: humans ignore the manager, look for a
+// gap themselves and take a moment to move off, connected cars keep out of their way.
+
+use smart_road::cli::Options;
+use smart_road::driver::{gap_blocker, REACTION_TIME};
+use smart_road::game::{Game, HEADLESS_STEP};
+use smart_road::scenario::{ScheduledSpawn, Scenario};
+use smart_road::types::*;
+use std::path::Path;
+
+#[test]
+fn humans_and_connected_cars_share_the_junction_without_a_crash() {
+    let mut game = Game::with_seed(17);
+    game.verbose = false;
+    game.scenario = Scenario::load(Path::new("scenarios/mixed_traffic.json")).unwrap();
+    game.run_to_end().unwrap();
+
+    assert_eq!(game.stats.crashes, 0);
+    let humans = game.stats.driver_delay_distribution(Driver::Human);
+    let connected = game.stats.driver_delay_distribution(Driver::Autonomous);
+    assert!(humans.count > 50, "{} human drivers got through", humans.count);
+    assert!(connected.count > 50, "{} connected cars got through", connected.count);
+    assert_eq!(humans.count + connected.count, game.stats.cars_passed);
+    assert!(humans.mean > connected.mean, "humans {:.2} s, connected {:.2} s", humans.mean, connected.mean);
+}
+
+#[test]
+fn a_human_waits_for_a_gap_and_reacts_before_moving_off() {
+    let mut game = Game::with_seed(0);
+    game.verbose = false;
+    game.scenario.penetration = 0.0;
+    game.scenario.spawns = vec![
+        ScheduledSpawn { time: 0.0, direction: Direction::East, route: Route::Straight, lane: None },
+        ScheduledSpawn { time: 0.0, direction: Direction::North, route: Route::Straight, lane: None },
+    ];
+    game.scenario.duration = Some(30.0);
+    game.scenario.prepare().unwrap();
+
+    let mut clear_for = 0.0;
+    let mut waited = false;
+    while game.app_state == AppState::Running {
+        let human = game.cars.iter().position(|car| car.direction == Direction::North);
+        let before = human.map(|i| (game.cars[i].x, game.cars[i].y, game.cars[i].velocity));
+        let gap = human.and_then(|i| gap_blocker(&game.cars, i, HEADLESS_STEP));
+        game.update(HEADLESS_STEP);
+
+        let (Some((x, y, velocity)), Some(car)) = (before, game.cars.iter().find(|car| car.direction == Direction::North)) else {
+            continue;
+        };
+        let moved = car.x != x || car.y != y;
+        if gap.is_some() {
+            waited = true;
+            clear_for = 0.0;
+            assert!(!moved, "the human pulled out into a car at {:.2} s", game.sim_time);
+        } else {
+            clear_for += HEADLESS_STEP;
+            if velocity.x == 0.0 && velocity.y == 0.0 && moved {
+                assert!(clear_for >= REACTION_TIME - HEADLESS_STEP / 2.0, "moved off after {:.2} s", clear_for);
+            }
+        }
+    }
+    assert!(waited, "the human never had to wait");
+    assert_eq!(game.stats.crashes, 0);
+    assert_eq!(game.stats.cars_passed, 2);
+}
+
+#[test]
+fn penetration_is_the_share_of_connected_cars() {
+    let mut scenario = Scenario { penetration: 1.5, ..Scenario::default() };
+    assert!(scenario.prepare().is_err());
+    let args: Vec<String> = ["--penetration", "-0.2"].iter().map(|s| s.to_string()).collect();
+    assert!(Options::parse(&args).is_err());
+
+    for (penetration, driver) in [(0.0, Driver::Human), (1.0, Driver::Autonomous)] {
+        let mut game = Game::with_seed(4);
+        game.verbose = false;
+        game.scenario.penetration = penetration;
+        for direction in Direction::ALL {
+            assert!(game.spawn_car_in_lane(direction, Route::Straight, None));
+        }
+        assert!(game.cars.iter().all(|car| car.driver == driver));
+    }
+}
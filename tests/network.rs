@@ -0,0 +1,112 @@
+// Several intersections joined by links: cars cross them one after the other.
+
+use smart_road::lanes::exit_direction;
+use smart_road::network::*;
+use smart_road::scenario::{Scenario, ScheduledSpawn};
+use smart_road::types::*;
+
+fn node(name: &str, column: i32, scenario: Scenario) -> NodeConfig {
+    NodeConfig { name: name.to_string(), column, row: 0, scenario }
+}
+
+// A feeds B through its east side, only A gets cars
+fn two_nodes() -> NetworkConfig {
+    let spawns = [0.0, 2.0, 4.0]
+        .into_iter()
+        .map(|time| ScheduledSpawn { time, direction: Direction::East, route: Route::Straight, lane: None })
+        .collect();
+    NetworkConfig {
+        name: "pair".to_string(),
+        duration: Some(60.0),
+        nodes: vec![
+            node("A", 0, Scenario { spawns, ..Scenario::default() }),
+            node("B", 1, Scenario::default()),
+        ],
+        links: vec![LinkConfig { from: "A".to_string(), to: "B".to_string(), approach: Direction::East, travel_time: 5.0 }],
+        ..NetworkConfig::default()
+    }
+}
+
+#[test]
+fn cars_drive_on_to_the_next_intersection() {
+    let mut network = Network::new(&two_nodes(), 1).unwrap();
+    network.verbose = false;
+    network.run_to_end().unwrap();
+
+    assert_eq!(network.finished.len(), 3);
+    for journey in &network.finished {
+        assert_eq!(journey.path, "A>B");
+        assert_eq!(journey.destination, "B");
+        assert!(journey.travel_time() > 5.0);
+    }
+    assert_eq!(network.nodes[1].game.stats.cars_passed, 3);
+    assert!(network.nodes.iter().all(|node| node.game.stats.crashes == 0));
+}
+
+#[test]
+fn links_must_join_known_nodes_once_per_side() {
+    let mut config = two_nodes();
+    config.links[0].to = "Z".to_string();
+    assert!(Network::new(&config, 1).is_err());
+
+    let mut config = two_nodes();
+    config.links.push(config.links[0].clone());
+    assert!(Network::new(&config, 1).is_err());
+
+    // B is a one-way street out on its west side, nothing can come in there
+    let mut config = two_nodes();
+    config.nodes[1].scenario.lanes.approaches.insert(Direction::East, vec![]);
+    assert!(Network::new(&config, 1).is_err());
+
+    // B's east approach has a lane, but every exit it could take is closed
+    let mut config = two_nodes();
+    let lanes = &mut config.nodes[1].scenario.lanes;
+    for direction in Direction::ALL {
+        lanes.approaches.insert(direction, vec![]);
+    }
+    lanes.approaches.insert(Direction::East, vec![vec![]]);
+    for route in [Route::Left, Route::Straight, Route::Right] {
+        lanes.exits.insert(exit_direction(&Direction::East, &route), 0);
+    }
+    assert!(Network::new(&config, 1).err().is_some_and(|e| e.contains("no route leads on")));
+}
+
+#[test]
+fn coordinated_cars_are_paced_around_crossing_traffic() {
+    // A platoon on B's own sides crosses the link's cars right when they would arrive
+    let mut config = two_nodes();
+    config.nodes[1].scenario.spawns = (0..4)
+        .flat_map(|i| {
+            let time = 10.0 + i as f32 * 0.5;
+            [Direction::North, Direction::South]
+                .map(|direction| ScheduledSpawn { time, direction, route: Route::Straight, lane: None })
+        })
+        .collect();
+
+    // Stops of the cars that came down the link
+    let stops = |coordinate: bool| {
+        let mut config = config.clone();
+        config.coordinate = coordinate;
+        let mut network = Network::new(&config, 1).unwrap();
+        network.verbose = false;
+        network.run_to_end().unwrap();
+        assert_eq!(network.finished.len(), 11);
+        assert!(network.nodes.iter().all(|node| node.game.stats.crashes == 0));
+        network.finished.iter().filter(|journey| journey.hops == 2).map(|journey| journey.stops).sum::<usize>()
+    };
+    assert!(stops(false) > 0);
+    assert_eq!(stops(true), 0);
+}
+
+#[test]
+fn network_files_are_refused_as_scenarios() {
+    for file in ["scenarios/corridor_network.json", "scenarios/grid_network.json"] {
+        let error = Scenario::load(std::path::Path::new(file)).unwrap_err();
+        assert!(error.contains("--network"), "{}", error);
+        assert!(NetworkConfig::load(std::path::Path::new(file)).is_ok());
+    }
+    for file in ["crosswalks", "cyclists", "mixed_traffic", "multi_lane", "roundabout", "rush_hour", "t_junction"] {
+        let path = format!("scenarios/{}.json", file);
+        assert!(Scenario::load(std::path::Path::new(&path)).is_ok(), "{}", path);
+    }
+}
@@ -0,0 +1,86 @@
+// Roundabout junction: cars circulate counter-clockwise and entering cars give way to the ring.
+
+use smart_road::game::{Game, HEADLESS_STEP};
+use smart_road::lanes::{exit_direction, lane_position};
+use smart_road::roundabout::CENTRE;
+use smart_road::scenario::ScheduledSpawn;
+use smart_road::types::*;
+
+#[test]
+fn a_lone_car_leaves_by_the_exit_of_its_route() {
+    for direction in Direction::ALL {
+        for route in Route::ALL {
+            let mut game = Game::with_seed(0);
+            game.verbose = false;
+            game.scenario.junction = Junction::Roundabout;
+            game.scenario.spawns = vec![ScheduledSpawn { time: 0.0, direction: direction.clone(), route: route.clone(), lane: None }];
+            game.scenario.duration = Some(30.0);
+
+            let mut left_at = None;
+            while game.app_state == AppState::Running && game.stats.cars_passed == 0 {
+                game.update(HEADLESS_STEP);
+                if let Some(car) = game.cars.first().filter(|car| car.rotated && left_at.is_none()) {
+                    left_at = Some((car.x, car.y, car.lane));
+                }
+            }
+
+            // On its lane of the exit road, on the far side of the ring
+            let (x, y, lane) = left_at.expect("car never left the ring");
+            let exit = exit_direction(&direction, &route);
+            let lateral = lane_position(&exit, lane);
+            let on_exit = match exit {
+                Direction::North => (x - lateral).abs() < 1.0 && y > CENTRE.1,
+                Direction::South => (x - lateral).abs() < 1.0 && y < CENTRE.1,
+                Direction::East => (y - lateral).abs() < 1.0 && x > CENTRE.0,
+                Direction::West => (y - lateral).abs() < 1.0 && x < CENTRE.0,
+            };
+            assert!(on_exit, "{:?} {:?} left at ({}, {})", direction, route, x, y);
+            assert_eq!(game.stats.cars_passed, 1);
+            assert_eq!(game.stats.crashes, 0);
+        }
+    }
+}
+
+#[test]
+fn busy_roundabout_keeps_flowing_without_crashes() {
+    let mut game = Game::with_seed(9);
+    game.verbose = false;
+    game.scenario.junction = Junction::Roundabout;
+    game.scenario.set_rate(20.0);
+    game.scenario.duration = Some(120.0);
+    game.run_to_end().unwrap();
+
+    assert_eq!(game.stats.crashes, 0);
+    assert_eq!(game.stats.deadlocks, 0);
+    assert!(game.stats.cars_passed > 120, "only {} cars passed", game.stats.cars_passed);
+    assert!(game.stats.yield_time > 0.0);
+}
+
+#[test]
+fn u_turns_go_round_the_ring_and_back() {
+    for direction in Direction::ALL {
+        let mut game = Game::with_seed(0);
+        game.verbose = false;
+        game.scenario.junction = Junction::Roundabout;
+        game.scenario.spawns = vec![ScheduledSpawn { time: 0.0, direction: direction.clone(), route: Route::UTurn, lane: None }];
+        game.scenario.duration = Some(30.0);
+
+        // Add up the angle the car turns through on the ring
+        let mut last: Option<f32> = None;
+        let mut turned: f32 = 0.0;
+        while game.app_state == AppState::Running && game.stats.cars_passed == 0 {
+            game.update(HEADLESS_STEP);
+            if let Some(car) = game.cars.first().filter(|car| car.entered && !car.rotated) {
+                let angle = (car.y - CENTRE.1).atan2(car.x - CENTRE.0);
+                if let Some(last) = last {
+                    turned += (angle - last).rem_euclid(std::f32::consts::TAU);
+                }
+                last = Some(angle);
+            }
+        }
+
+        assert!(turned > 1.5 * std::f32::consts::PI, "{:?} only turned {}", direction, turned);
+        assert_eq!(game.stats.cars_passed, 1);
+        assert_eq!(game.stats.crashes, 0);
+    }
+}
@@ -0,0 +1,81 @@
+// Demand between nodes of a network: cheapest paths by travel time, and the trips they produce.
+
+use smart_road::network::*;
+use smart_road::routing::*;
+use smart_road::scenario::Scenario;
+use smart_road::types::*;
+
+fn link(from: &str, to: &str, approach: Direction) -> LinkConfig {
+    LinkConfig { from: from.to_string(), to: to.to_string(), approach, travel_time: 4.0 }
+}
+
+// Two by two grid, NW to SE can go round either way
+fn grid(demand: Vec<OdDemand>) -> NetworkConfig {
+    let nodes = [("NW", 0, 0), ("NE", 1, 0), ("SW", 0, 1), ("SE", 1, 1)]
+        .into_iter()
+        .map(|(name, column, row)| NodeConfig { name: name.to_string(), column, row, scenario: Scenario::default() })
+        .collect();
+    let links = vec![
+        link("NW", "NE", Direction::East),
+        link("NE", "NW", Direction::West),
+        link("SW", "SE", Direction::East),
+        link("SE", "SW", Direction::West),
+        link("NW", "SW", Direction::South),
+        link("SW", "NW", Direction::North),
+        link("NE", "SE", Direction::South),
+        link("SE", "NE", Direction::North),
+    ];
+    NetworkConfig { name: "grid".to_string(), duration: Some(120.0), nodes, links, demand, ..NetworkConfig::default() }
+}
+
+fn od(origin: &str, destination: &str, rate: f32) -> OdDemand {
+    OdDemand { origin: origin.to_string(), destination: destination.to_string(), rate }
+}
+
+// Nodes a path goes through after the first one
+fn nodes_after_start(network: &Network, path: &PlannedPath) -> Vec<usize> {
+    network
+        .path_links(path.start.clone(), &path.routes)
+        .into_iter()
+        .map(|link| network.links[link].to)
+        .collect()
+}
+
+#[test]
+fn paths_avoid_slow_links() {
+    let mut network = Network::new(&grid(Vec::new()), 1).unwrap();
+    let path = network.entry_plan(0, 3).unwrap();
+    assert_eq!(path.routes.len(), 3);
+    let via = nodes_after_start(&network, &path)[0];
+
+    // Make the way through that node slow, the other side of the grid wins
+    let slow = network.links.iter().position(|link| link.from == 0 && link.to == via).unwrap();
+    network.link_times[slow] = 60.0;
+    assert!(network.is_congested(slow));
+    let detour = network.entry_plan(0, 3).unwrap();
+    assert_ne!(nodes_after_start(&network, &detour)[0], via);
+    assert!(detour.cost < path.cost + 60.0);
+}
+
+#[test]
+fn demand_cars_reach_their_destination() {
+    let mut network = Network::new(&grid(vec![od("NW", "SE", 12.0), od("NE", "NE", 6.0)]), 2).unwrap();
+    network.verbose = false;
+    network.run_to_end().unwrap();
+
+    let summaries = network.od_summaries();
+    let pair = |origin: &str, destination: &str| {
+        summaries.iter().find(|s| s.origin == origin && s.destination == destination).map_or(0, |s| s.count)
+    };
+    assert!(pair("NW", "SE") > 10);
+    assert!(pair("NE", "NE") > 3);
+    assert_eq!(pair("NW", "SE") + pair("NE", "NE"), network.finished.len());
+    assert!(network.finished.iter().filter(|j| j.origin == "NW").all(|j| j.hops == 3));
+}
+
+#[test]
+fn unreachable_destinations_are_refused() {
+    let mut config = grid(vec![od("NW", "SE", 6.0)]);
+    config.links.retain(|link| link.to != "SE");
+    assert!(Network::new(&config, 1).is_err());
+}
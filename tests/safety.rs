@@ -0,0 +1,60 @@
+// Surrogate safety measures on hand-built cars: time-to-collision while two cars close in on
+// each other, post-encroachment time once they have gone through their conflict point.
+
+use smart_road::collision::{conflict_point, time_to_collision};
+use smart_road::safety::*;
+use smart_road::types::*;
+use std::collections::HashMap;
+
+const STEP: f32 = 0.05;
+
+fn moving(direction: Direction, x: f32, y: f32, vx: f32, vy: f32) -> Car {
+    let id = if direction == Direction::North { 1 } else { 2 };
+    let mut car = Car::new(id, direction, Route::Straight, x, y, 0.0);
+    car.velocity = Vec2::new(vx, vy);
+    car
+}
+
+#[test]
+fn ttc_of_a_car_running_into_a_stopped_one() {
+    let stopped = moving(Direction::North, 500.0, 300.0, 0.0, 0.0);
+    let behind = moving(Direction::North, 500.0, 100.0, 0.0, 100.0);
+    // Bodies touch once the gap between centres is down to one car length
+    let ttc = time_to_collision(&behind, &stopped, 5.0).unwrap();
+    assert!((ttc - (200.0 - CAR_LENGTH) / 100.0).abs() < 1e-4, "ttc {}", ttc);
+
+    assert_eq!(time_to_collision(&behind, &stopped, 1.0), None, "beyond the horizon");
+    let beside = moving(Direction::North, 560.0, 100.0, 0.0, 100.0);
+    assert_eq!(time_to_collision(&beside, &stopped, 5.0), None, "parallel lanes never meet");
+}
+
+#[test]
+fn pet_of_crossing_cars_where_one_stops_short() {
+    let (px, py) = conflict_point(CollisionType::NS, CollisionType::ES).unwrap();
+    // Both 100 px from the conflict point at 100 px/s
+    let mut north = moving(Direction::North, px, py - 100.0, 0.0, 100.0);
+    let mut east = moving(Direction::East, px - 100.0, py, 100.0, 0.0);
+    assert!(conflicting(&north, &east));
+
+    let mut open = HashMap::new();
+    let mut stats = Stats::default();
+    let mut now = 0.0;
+    while now < 5.0 {
+        // The east car brakes from 0.1 s to 2 s and lets the north car through
+        east.velocity.x = if (0.1..2.0).contains(&now) { 0.0 } else { 100.0 };
+        measure_conflicts(&[north.clone(), east.clone()], TTC_THRESHOLD, now, &mut open, &mut stats);
+        north.y += north.velocity.y * STEP;
+        east.x += east.velocity.x * STEP;
+        now += STEP;
+    }
+    car_left(1, &mut open, &mut stats);
+    car_left(2, &mut open, &mut stats);
+
+    assert_eq!(stats.close_call, 1);
+    let event = &stats.conflicts[0];
+    assert!(event.min_ttc < TTC_THRESHOLD);
+    // North clears the point when its back passes it at 1.3 s, east reaches it with its front
+    // at 2.6 s
+    let pet = event.pet.unwrap();
+    assert!((pet - 1.3).abs() <= 2.0 * STEP, "pet {}", pet);
+}
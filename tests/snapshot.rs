@@ -0,0 +1,91 @@
+// Saving and loading the whole simulation state: a resumed run must not differ from one that
+// was never interrupted.
+
+use smart_road::collision::Manager;
+use smart_road::events::EventLog;
+use smart_road::export::RunSummary;
+use smart_road::game::{Game, HEADLESS_STEP};
+use smart_road::snapshot::SNAPSHOT_VERSION;
+use std::path::PathBuf;
+
+const SAVE_AT: f32 = 30.0;
+const DURATION: f32 = 60.0;
+
+fn busy_game() -> Game {
+    let mut game = Game::with_seed(9);
+    game.verbose = false;
+    game.events = EventLog::recording();
+    game.scenario.set_rate(25.0);
+    game.scenario.duration = Some(DURATION);
+    game
+}
+
+fn snapshot_file(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("smart-road-{}-{}.json", name, std::process::id()))
+}
+
+fn summary(game: &Game) -> String {
+    RunSummary::new(&game.run_info(), &game.stats).csv_row()
+}
+
+#[test]
+fn resumed_run_matches_uninterrupted_run() {
+    let path = snapshot_file("resume");
+    let mut original = busy_game();
+    while original.sim_time < SAVE_AT {
+        original.update(HEADLESS_STEP);
+    }
+    original.save_snapshot(&path).unwrap();
+    original.run_to_end().unwrap();
+
+    let mut resumed = Game::with_seed(0);
+    resumed.verbose = false;
+    resumed.load_snapshot(&path).unwrap();
+    resumed.run_to_end().unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(summary(&resumed), summary(&original));
+    assert_eq!(resumed.events.events, original.events.events);
+    assert_eq!(resumed.stats.trips.len(), original.stats.trips.len());
+}
+
+#[test]
+fn branches_keep_the_saved_situation() {
+    let path = snapshot_file("branch");
+    let mut game = busy_game();
+    while game.sim_time < SAVE_AT {
+        game.update(HEADLESS_STEP);
+    }
+    game.save_snapshot(&path).unwrap();
+
+    let mut branch = Game::with_seed(0);
+    branch.verbose = false;
+    branch.load_snapshot(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(branch.sim_time, game.sim_time);
+    assert_eq!(branch.cars.len(), game.cars.len());
+    assert_eq!(branch.priority_map, game.priority_map);
+    assert_eq!(branch.priority_ref, game.priority_ref);
+    assert_eq!(branch.rng.get_seed(), game.rng.get_seed());
+
+    branch.manager = Manager::FirstCome;
+    branch.run_to_end().unwrap();
+    assert_eq!(branch.stats.crashes, 0);
+    assert!(branch.stats.cars_passed >= game.stats.cars_passed);
+}
+
+#[test]
+fn files_of_another_version_are_refused() {
+    let path = snapshot_file("version");
+    let mut game = busy_game();
+    game.update(HEADLESS_STEP);
+    game.save_snapshot(&path).unwrap();
+    let mut json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    json["version"] = (SNAPSHOT_VERSION - 1).into();
+    std::fs::write(&path, json.to_string()).unwrap();
+
+    let error = Game::with_seed(0).load_snapshot(&path).unwrap_err();
+    std::fs::remove_file(&path).unwrap();
+    assert!(error.contains("version"), "{}", error);
+}
@@ -0,0 +1,44 @@
+// The spatial grid only narrows down which cars are compared, it must never hide a pair that
+// is close enough to matter, even when the two sit in different cells.
+
+use smart_road::collision::*;
+use smart_road::spatial::*;
+use smart_road::types::*;
+
+fn square(x: f32, y: f32, half: f32) -> Aabb {
+    Aabb { min_x: x - half, min_y: y - half, max_x: x + half, max_y: y + half }
+}
+
+#[test]
+fn bounds_reaching_into_a_neighbouring_cell_are_found_there() {
+    let mut grid = SpatialGrid::new(CELL_SIZE);
+    // Centred in the first cell, reaching across its right edge
+    grid.insert(0, square(CELL_SIZE - 5.0, 60.0, 10.0));
+    grid.insert(1, square(CELL_SIZE * 3.5, 60.0, 10.0));
+
+    assert_eq!(grid.query(square(CELL_SIZE + 10.0, 60.0, 6.0)), vec![0]);
+    // Spanning both, each index once
+    assert_eq!(grid.query(square(CELL_SIZE * 2.0, 60.0, CELL_SIZE * 1.6)), vec![0, 1]);
+    assert!(grid.query(square(CELL_SIZE * 2.5, CELL_SIZE * 3.0, 5.0)).is_empty());
+}
+
+#[test]
+fn cars_in_neighbouring_cells_are_still_compared() {
+    // Crossing straight movements just either side of a cell edge
+    let edge = CELL_SIZE * 2.0;
+    let north = Car::new(1, Direction::North, Route::Straight, 600.0, edge - 20.0, 0.0);
+    let east = Car::new(2, Direction::East, Route::Straight, 560.0, edge + 20.0, 0.0);
+    let far = Car::new(3, Direction::West, Route::Straight, 100.0, 600.0, 0.0);
+    let cars = vec![north, east, far];
+
+    let mut grid = SpatialGrid::new(CELL_SIZE);
+    for (i, car) in cars.iter().enumerate() {
+        grid.insert(i, car_bounds(car));
+    }
+    let tracking = build_car_tracking(&cars, &grid);
+
+    let tracked = |id: usize| -> Vec<usize> { tracking[&id].iter().map(|t| t.id).collect() };
+    assert_eq!(tracked(1), vec![2]);
+    assert_eq!(tracked(2), vec![1]);
+    assert!(tracked(3).is_empty());
+}
@@ -0,0 +1,83 @@
+// Aggregates behind the statistics screen and the exports.
+
+use smart_road::game::Game;
+use smart_road::lanes::MAX_LANES;
+use smart_road::stats::*;
+use smart_road::types::*;
+use std::collections::HashMap;
+
+#[test]
+fn distribution_of_a_known_sample() {
+    let delays = Distribution::from_values(&[4.0, 1.0, 3.0, 2.0, 5.0]);
+    assert_eq!(delays.count, 5);
+    assert_eq!(delays.mean, 3.0);
+    assert_eq!(delays.median, 3.0);
+    // Rank 0.95 * 4 = 3.8, between the 4th and 5th value
+    assert!((delays.p95 - 4.8).abs() < 1e-5, "p95 {}", delays.p95);
+    assert_eq!((delays.min, delays.max), (1.0, 5.0));
+
+    assert_eq!(percentile(&[10.0, 20.0], 25.0), 12.5);
+    assert_eq!(percentile(&[7.0], 95.0), 7.0);
+}
+
+#[test]
+fn empty_samples_give_zeros() {
+    let empty = Distribution::from_values(&[]);
+    assert_eq!(empty.count, 0);
+    assert_eq!((empty.mean, empty.median, empty.p95, empty.min, empty.max), (0.0, 0.0, 0.0, 0.0, 0.0));
+    assert_eq!(percentile(&[], 50.0), 0.0);
+
+    let histogram = Histogram::from_values(&[], HISTOGRAM_BINS);
+    assert_eq!(histogram.counts, vec![0; HISTOGRAM_BINS]);
+    assert_eq!(histogram.bin_width, 0.0);
+}
+
+#[test]
+fn histogram_puts_the_maximum_in_the_last_bin() {
+    let values: Vec<f32> = (0..10).map(|v| v as f32).collect();
+    let histogram = Histogram::from_values(&values, 10);
+    assert_eq!(histogram.min, 0.0);
+    assert!((histogram.bin_width - 0.9).abs() < 1e-5);
+    assert_eq!(histogram.counts, vec![1; 10]);
+
+    // All the same value, one bin takes everything
+    let histogram = Histogram::from_values(&[2.5, 2.5, 2.5], 4);
+    assert_eq!(histogram.counts, vec![3, 0, 0, 0]);
+    assert_eq!(histogram.bin_width, 1.0);
+}
+
+#[test]
+fn long_frames_still_sample_every_interval() {
+    let mut game = Game::with_seed(3);
+    game.verbose = false;
+    game.scenario.set_rate(20.0);
+    game.scenario.duration = Some(30.0);
+    game.speed_multiplier = 8.0;
+    while game.sim_time < 30.0 {
+        game.update(0.25); // two simulated seconds a frame
+    }
+
+    let times: Vec<f32> = game.stats.samples.iter().map(|s| s.time).collect();
+    let expected: Vec<f32> = (0..=30).map(|t| t as f32 * SAMPLE_INTERVAL).collect();
+    assert_eq!(times, expected);
+}
+
+#[test]
+fn samples_count_cars_by_the_lane_they_are_in() {
+    // Every route shares the inner lane, and a second straight car is in the middle one
+    let car = |id: usize, route: Route, lane: usize| {
+        let mut car = Car::new(id, Direction::North, route, 535.0, 100.0, 0.0);
+        car.lane = lane;
+        car
+    };
+    let cars = vec![car(1, Route::Left, 0), car(2, Route::Straight, 0), car(3, Route::Right, 0), car(4, Route::Straight, 1)];
+    let sample = Sample::take(0.0, &cars, &HashMap::new(), &Stats::default());
+
+    assert_eq!(sample.lanes.len(), Direction::ALL.len() * MAX_LANES);
+    let north: Vec<(usize, usize, usize)> = sample.lanes
+        .iter()
+        .filter(|lane| lane.direction == Direction::North)
+        .map(|lane| (lane.lane, lane.cars, lane.queue))
+        .collect();
+    assert_eq!(north, [(0, 3, 3), (1, 1, 1), (2, 0, 0)]);
+}
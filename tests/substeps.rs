@@ -0,0 +1,66 @@
+// Long frames are split into sub-steps, so no car moves more than MAX_STEP_DISTANCE between
+// collision checks and a fast car can't jump through another one at a low frame rate.
+
+use smart_road::collision::footprints_overlap;
+use smart_road::game::{Game, MAX_STEP_DISTANCE};
+use smart_road::lanes::lane_position;
+use smart_road::movement::move_car;
+use smart_road::scenario::ScheduledSpawn;
+use smart_road::types::*;
+use std::collections::HashMap;
+
+// A 4 fps frame at x8 speed: two simulated seconds
+const FRAME: f32 = 0.25;
+const MULTIPLIER: f32 = 8.0;
+
+fn no_footprints_overlap(cars: &[Car]) -> bool {
+    cars.iter()
+        .enumerate()
+        .all(|(i, a)| cars[i + 1..].iter().all(|b| !footprints_overlap(a, b)))
+}
+
+#[test]
+fn fast_cars_on_crossing_paths_never_overlap_on_long_frames() {
+    let mut game = Game::with_seed(0);
+    game.verbose = false;
+    game.speed_multiplier = MULTIPLIER;
+    game.scenario.spawns = vec![
+        ScheduledSpawn { time: 0.0, direction: Direction::North, route: Route::Straight, lane: None },
+        ScheduledSpawn { time: 0.0, direction: Direction::East, route: Route::Straight, lane: None },
+    ];
+    game.scenario.duration = Some(30.0);
+
+    while game.app_state == AppState::Running && game.stats.cars_passed < 2 {
+        game.update(FRAME);
+        assert!(game.cars.iter().all(|car| car.max_speed == SUPER));
+        assert!(no_footprints_overlap(&game.cars), "cars overlap at {:.2} s", game.sim_time);
+    }
+    assert_eq!(game.stats.cars_passed, 2);
+    assert_eq!(game.stats.crashes, 0);
+}
+
+#[test]
+fn one_step_per_long_frame_would_jump_a_car_through_another() {
+    // An east-bound car in the crossing and a north-bound one just short of its lane
+    let x = lane_position(&Direction::North, 1);
+    let y = lane_position(&Direction::East, 1);
+    let east = Car::new(1, Direction::East, Route::Straight, x, y, 0.0);
+    let start = Car::new(2, Direction::North, Route::Straight, x, y - (CAR_LENGTH + CAR_WIDTH) / 2.0 - 10.0, 0.0);
+    assert!(!footprints_overlap(&start, &east));
+
+    // In one step the north-bound car ends up on the far side without ever touching it
+    let mut jumped = start.clone();
+    move_car(&mut jumped, FRAME * MULTIPLIER, &mut HashMap::new(), Junction::Cross);
+    assert!(jumped.y > east.y + (CAR_LENGTH + CAR_WIDTH) / 2.0);
+    assert!(!footprints_overlap(&jumped, &east));
+
+    // Steps of MAX_STEP_DISTANCE catch the overlap on the way
+    let mut stepped = start.clone();
+    let step = MAX_STEP_DISTANCE / SUPER;
+    let mut touched = false;
+    while stepped.y < jumped.y {
+        move_car(&mut stepped, step, &mut HashMap::new(), Junction::Cross);
+        touched |= footprints_overlap(&stepped, &east);
+    }
+    assert!(touched);
+}
@@ -0,0 +1,79 @@
+// U-turns: a half circle in the crossing back onto the road the car came on.
+
+use smart_road::collision::{collision_types_for, conflict_point, movements_conflict};
+use smart_road::game::{Game, HEADLESS_STEP};
+use smart_road::lanes::lane_position;
+use smart_road::scenario::ScheduledSpawn;
+use smart_road::types::*;
+
+#[test]
+fn u_turns_conflict_with_the_opposing_straight_and_left() {
+    for direction in Direction::ALL {
+        let opposite = direction.opposite();
+        assert!(movements_conflict(&direction, &Route::UTurn, &opposite, &Route::Straight));
+        assert!(movements_conflict(&direction, &Route::UTurn, &opposite, &Route::Left));
+        assert!(movements_conflict(&direction, &Route::UTurn, &opposite, &Route::UTurn));
+        assert!(!movements_conflict(&direction, &Route::UTurn, &opposite, &Route::Right));
+    }
+}
+
+// The reference-point manager measures U-turns to a point of their own instead of letting
+// the lower id go first
+#[test]
+fn u_turns_have_a_reference_point_for_every_conflict() {
+    for direction in Direction::ALL {
+        let types = collision_types_for(&Route::UTurn, &direction);
+        for &other in &types[1..] {
+            assert!(conflict_point(types[0], other).is_some(), "{:?} and {:?}", types[0], other);
+            assert_eq!(conflict_point(types[0], other), conflict_point(other, types[0]));
+        }
+    }
+}
+
+#[test]
+fn a_lone_car_comes_back_on_its_own_slot() {
+    for junction in Junction::ALL {
+        for direction in Direction::ALL {
+            let mut game = Game::with_seed(0);
+            game.verbose = false;
+            game.scenario.junction = junction;
+            game.scenario.spawns = vec![ScheduledSpawn { time: 0.0, direction: direction.clone(), route: Route::UTurn, lane: None }];
+            game.scenario.duration = Some(30.0);
+
+            let mut last = None;
+            while game.app_state == AppState::Running && game.stats.cars_passed == 0 {
+                game.update(HEADLESS_STEP);
+                if let Some(car) = game.cars.first() {
+                    last = Some((car.x, car.y, car.lane));
+                }
+            }
+
+            let (x, y, lane) = last.expect("car never appeared");
+            let back = lane_position(&direction.opposite(), lane);
+            let lateral = match direction {
+                Direction::North | Direction::South => x,
+                Direction::East | Direction::West => y,
+            };
+            assert!((lateral - back).abs() < 1.0, "{:?} {:?} left at ({}, {})", junction, direction, x, y);
+            assert_eq!(game.stats.cars_passed, 1);
+            assert_eq!(game.stats.trips[0].route, Route::UTurn);
+            assert_eq!(game.stats.crashes, 0);
+        }
+    }
+}
+
+#[test]
+fn weighted_u_turns_mix_with_other_traffic() {
+    let mut game = Game::with_seed(3);
+    game.verbose = false;
+    game.scenario.set_rate(8.0);
+    game.scenario.route_weights.insert(Route::UTurn, 1.0);
+    game.scenario.duration = Some(180.0);
+    game.run_to_end().unwrap();
+
+    assert_eq!(game.stats.crashes, 0);
+    let summaries = game.stats.movement_summaries(game.sim_time);
+    let u_turns: usize = summaries.iter().filter(|s| s.route == Route::UTurn).map(|s| s.count).sum();
+    assert!(u_turns > 10, "only {} U-turns", u_turns);
+    assert!(game.stats.cars_passed > 80);
+}